@@ -0,0 +1,611 @@
+use alris_staking_client::{
+    accounts::{
+        active_reward_pools, deserialize_stake_deposit_receipt, deserialize_user_stake_index,
+    },
+    allowlist::Allowlist,
+    alris_staking::state::{StakeDepositReceipt, StakePool, SOULBOUND_STAKE_MINT},
+    instructions::{self, DepositArgs, InitializeStakePoolArgs, RewardMint},
+    pda::{
+        find_stake_deposit_receipt_address, find_stake_pool_address, find_user_stake_index_address,
+    },
+    PROGRAM_ID,
+};
+use anchor_lang::{prelude::Pubkey, Discriminator};
+use anyhow::{anyhow, bail, Context as _, Result};
+use serde_json::{json, Value};
+use solana_client::{
+    rpc_config::RpcProgramAccountsConfig,
+    rpc_filter::{Memcmp, RpcFilterType},
+};
+use solana_sdk::{instruction::Instruction, signature::Signer};
+use spl_associated_token_account::{
+    get_associated_token_address_with_program_id,
+    instruction::create_associated_token_account_idempotent,
+};
+
+use crate::{
+    context::{read_keypair, Context},
+    Command, Flag,
+};
+
+/// Receipts a single owner can hold in one pool before `deposit` gives up looking for a nonce
+const MAX_RECEIPT_NONCE_SEARCH: u32 = 1024;
+
+pub fn run(ctx: &Context, command: Command) -> Result<Value> {
+    match command {
+        Command::CreatePool {
+            mint,
+            nonce,
+            max_weight,
+            min_duration,
+            max_duration,
+            soulbound,
+        } => {
+            let (stake_pool, _) = find_stake_pool_address(nonce, &mint, &ctx.pubkey());
+            let mut report = ctx.execute(vec![instructions::initialize_stake_pool(
+                &ctx.pubkey(),
+                &ctx.pubkey(),
+                &mint,
+                &ctx.fetch_token_program(&mint)?,
+                InitializeStakePoolArgs {
+                    nonce,
+                    max_weight,
+                    min_duration,
+                    max_duration,
+                    options: if soulbound { SOULBOUND_STAKE_MINT } else { 0 },
+                },
+            )])?;
+            report["stake_pool"] = json!(stake_pool.to_string());
+            Ok(report)
+        }
+        Command::ShowPool { pool } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            Ok(stake_pool_to_json(&pool.pool, &stake_pool))
+        }
+        Command::AddRewardPool {
+            pool,
+            reward_mint,
+            index,
+        } => ctx.execute(vec![instructions::add_reward_pool(
+            &pool.pool,
+            &ctx.pubkey(),
+            &ctx.pubkey(),
+            &RewardMint {
+                mint: reward_mint,
+                token_program: ctx.fetch_token_program(&reward_mint)?,
+            },
+            index,
+        )]),
+        Command::FundRewardPool {
+            pool,
+            index,
+            amount,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let reward_mint = ctx.fetch_reward_mint(&reward_vault_at(&stake_pool, index)?)?;
+            let from = get_associated_token_address_with_program_id(
+                &ctx.pubkey(),
+                &reward_mint.mint,
+                &reward_mint.token_program,
+            );
+            ctx.execute(vec![instructions::fund_reward_pool(
+                &pool.pool,
+                &stake_pool,
+                &ctx.pubkey(),
+                &from,
+                &reward_mint,
+                index,
+                amount,
+            )])
+        }
+        Command::SetRewardPoolPolicy {
+            pool,
+            index,
+            policy,
+            release_period,
+            recipient,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            reward_vault_at(&stake_pool, index)?;
+            ctx.execute(vec![instructions::set_reward_pool_policy(
+                &pool.pool,
+                &stake_pool,
+                recipient.as_ref(),
+                index,
+                policy.value(),
+                release_period,
+            )])
+        }
+        Command::SweepUndistributed { pool, index } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let reward_mint = ctx.fetch_reward_mint(&reward_vault_at(&stake_pool, index)?)?;
+            ctx.execute(vec![instructions::sweep_undistributed(
+                &pool.pool,
+                &stake_pool,
+                &reward_mint,
+                index,
+            )])
+        }
+        Command::SweepRewardDust {
+            pool,
+            index,
+            destination,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let reward_mint = ctx.fetch_reward_mint(&reward_vault_at(&stake_pool, index)?)?;
+            ctx.execute(vec![instructions::sweep_reward_dust(
+                &pool.pool,
+                &stake_pool,
+                &destination,
+                &reward_mint,
+                index,
+            )])
+        }
+        Command::SetFlags {
+            pool,
+            enable,
+            disable,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let flags = Flag::apply(stake_pool.flags, &enable, &disable);
+            let mut report = ctx.execute(vec![instructions::set_flags(
+                &pool.pool,
+                &ctx.pubkey(),
+                flags,
+            )])?;
+            report["previous_flags"] = json!(stake_pool.flags);
+            report["flags"] = json!(flags);
+            Ok(report)
+        }
+        Command::SetDepositCaps {
+            pool,
+            max_total_deposit,
+            max_deposit_per_owner,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let max_total_deposit = max_total_deposit.unwrap_or(stake_pool.max_total_deposit);
+            let max_deposit_per_owner =
+                max_deposit_per_owner.unwrap_or(stake_pool.max_deposit_per_owner);
+            let mut report = ctx.execute(vec![instructions::set_deposit_caps(
+                &pool.pool,
+                &ctx.pubkey(),
+                max_total_deposit,
+                max_deposit_per_owner,
+            )])?;
+            report["max_total_deposit"] = json!(max_total_deposit);
+            report["max_deposit_per_owner"] = json!(max_deposit_per_owner);
+            Ok(report)
+        }
+        Command::SetDepositAllowlist { pool, allowlist } => {
+            let root = match allowlist {
+                Some(path) => read_allowlist(&path)?.root(),
+                None => [0; 32],
+            };
+            let mut report = ctx.execute(vec![instructions::set_deposit_allowlist(
+                &pool.pool,
+                &ctx.pubkey(),
+                root,
+            )])?;
+            report["deposit_allowlist_root"] = json!(to_hex(&root));
+            Ok(report)
+        }
+        Command::SetMinDepositAmount { pool, amount } => {
+            ctx.execute(vec![instructions::set_min_deposit_amount(
+                &pool.pool,
+                &ctx.pubkey(),
+                amount,
+            )])
+        }
+        Command::MigrateStakePool { pool } => ctx.execute(vec![instructions::migrate_stake_pool(
+            &pool.pool,
+            &ctx.pubkey(),
+            &ctx.pubkey(),
+        )]),
+        Command::TransferAuthority {
+            pool,
+            new_authority,
+        } => ctx.execute(vec![instructions::transfer_authority(
+            &pool.pool,
+            &ctx.pubkey(),
+            &new_authority,
+        )]),
+        Command::UpdateTokenMeta {
+            pool,
+            name,
+            symbol,
+            uri,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            ctx.execute(vec![instructions::update_token_meta(
+                &pool.pool,
+                &stake_pool,
+                name,
+                symbol,
+                uri,
+            )])
+        }
+        Command::Deposit {
+            pool,
+            amount,
+            lockup_duration,
+            nonce,
+            owner,
+            allowlist,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let payer = ctx.pubkey();
+            let owner_keypair = owner.as_deref().map(read_keypair).transpose()?;
+            let token_authority = owner_keypair.as_ref().map(Signer::pubkey);
+            let owner = token_authority.unwrap_or(payer);
+            let allowlist_proof = match allowlist {
+                Some(path) => Some(
+                    read_allowlist(&path)?
+                        .proof(&owner)
+                        .ok_or_else(|| anyhow!("{owner} is not in allowlist {path}"))?,
+                ),
+                None => None,
+            };
+            let nonce = match nonce {
+                Some(nonce) => nonce,
+                None => find_unused_receipt_nonce(ctx, &pool.pool, &owner)?,
+            };
+            let token_program = ctx.fetch_token_program(&stake_pool.mint)?;
+            let stake_token_program = stake_pool.stake_token_program(&token_program);
+            let from = get_associated_token_address_with_program_id(
+                &owner,
+                &stake_pool.mint,
+                &token_program,
+            );
+            let destination = get_associated_token_address_with_program_id(
+                &owner,
+                &stake_pool.stake_mint,
+                &stake_token_program,
+            );
+            let mut report = ctx.execute_with_signers(
+                vec![
+                    create_associated_token_account_idempotent(
+                        &payer,
+                        &owner,
+                        &stake_pool.stake_mint,
+                        &stake_token_program,
+                    ),
+                    instructions::deposit(
+                        &pool.pool,
+                        &stake_pool,
+                        &payer,
+                        &owner,
+                        token_authority.as_ref(),
+                        &from,
+                        &destination,
+                        &token_program,
+                        DepositArgs {
+                            nonce,
+                            amount,
+                            lockup_duration,
+                            allowlist_proof,
+                        },
+                    ),
+                ],
+                &owner_keypair.iter().collect::<Vec<_>>(),
+            )?;
+            report["stake_deposit_receipt"] = json!(find_stake_deposit_receipt_address(
+                &owner, &pool.pool, nonce
+            )
+            .0
+            .to_string());
+            Ok(report)
+        }
+        Command::Claim { pool, receipt } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let (mut ixs, reward_mints, reward_recipients) =
+                reward_recipient_atas(ctx, &stake_pool)?;
+            ixs.push(match receipt.as_slice() {
+                [receipt] => instructions::claim_all(
+                    &pool.pool,
+                    &stake_pool,
+                    &ctx.pubkey(),
+                    receipt,
+                    &ctx.fetch_token_program(&stake_pool.mint)?,
+                    &reward_mints,
+                    &reward_recipients,
+                ),
+                receipts => instructions::claim_many(
+                    &pool.pool,
+                    &stake_pool,
+                    &ctx.pubkey(),
+                    receipts,
+                    &reward_mints,
+                    &reward_recipients,
+                ),
+            }?);
+            ctx.execute(ixs)
+        }
+        Command::PendingRewards { pool, receipt } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let return_data = ctx.simulate_return_data(&[instructions::get_claimable(
+                &pool.pool,
+                &stake_pool,
+                &receipt,
+            )])?;
+            let claimable = instructions::decode_claimable(&return_data)
+                .map_err(|err| anyhow::anyhow!("{err}"))?;
+            Ok(json!({
+                "receipt": receipt.to_string(),
+                "reward_pools": active_reward_pools(&stake_pool)
+                    .map(|(index, reward_pool)| json!({
+                        "index": index,
+                        "reward_vault": reward_pool.reward_vault.to_string(),
+                        "claimable": claimable[index],
+                    }))
+                    .collect::<Vec<_>>(),
+            }))
+        }
+        Command::Withdraw {
+            pool,
+            receipt,
+            lp_authority,
+        } => {
+            let stake_pool = ctx.fetch_stake_pool(&pool.pool)?;
+            let now = ctx.rpc.get_block_time(ctx.rpc.get_slot()?)?;
+            // one instruction per rent recipient, as `withdraw_many` refunds a single account
+            let mut receipts_by_rent_recipient: Vec<(Pubkey, Vec<Pubkey>)> = vec![];
+            for receipt in &receipt {
+                let deposit = ctx.fetch_stake_deposit_receipt(receipt)?;
+                if !stake_pool.escape_hatch_enabled() {
+                    deposit
+                        .validate_unlocked(now)
+                        .map_err(|err| anyhow::anyhow!("{receipt}: {err}"))?;
+                }
+                let rent_recipient = stake_pool.rent_recipient(&deposit);
+                match receipts_by_rent_recipient
+                    .iter_mut()
+                    .find(|(recipient, _)| *recipient == rent_recipient)
+                {
+                    Some((_, receipts)) => receipts.push(*receipt),
+                    None => receipts_by_rent_recipient.push((rent_recipient, vec![*receipt])),
+                }
+            }
+            let owner = ctx.pubkey();
+            let lp_authority = lp_authority.as_deref().map(read_keypair).transpose()?;
+            let lp_authority_key = lp_authority.as_ref().map(Signer::pubkey);
+            let token_program = ctx.fetch_token_program(&stake_pool.mint)?;
+            let (mut ixs, reward_mints, reward_recipients) =
+                reward_recipient_atas(ctx, &stake_pool)?;
+            ixs.push(create_associated_token_account_idempotent(
+                &owner,
+                &owner,
+                &stake_pool.mint,
+                &token_program,
+            ));
+            let from = get_associated_token_address_with_program_id(
+                &lp_authority_key.unwrap_or(owner),
+                &stake_pool.stake_mint,
+                &stake_pool.stake_token_program(&token_program),
+            );
+            let destination = get_associated_token_address_with_program_id(
+                &owner,
+                &stake_pool.mint,
+                &token_program,
+            );
+            for (rent_recipient, receipts) in &receipts_by_rent_recipient {
+                ixs.push(match receipts.as_slice() {
+                    [receipt] => instructions::withdraw(
+                        &pool.pool,
+                        &stake_pool,
+                        &owner,
+                        receipt,
+                        &from,
+                        lp_authority_key.as_ref(),
+                        &destination,
+                        rent_recipient,
+                        &token_program,
+                        &reward_mints,
+                        &reward_recipients,
+                    ),
+                    receipts => instructions::withdraw_many(
+                        &pool.pool,
+                        &stake_pool,
+                        &owner,
+                        receipts,
+                        &from,
+                        lp_authority_key.as_ref(),
+                        &destination,
+                        rent_recipient,
+                        &token_program,
+                        &reward_mints,
+                        &reward_recipients,
+                    ),
+                }?);
+            }
+            ctx.execute_with_signers(ixs, &lp_authority.iter().collect::<Vec<_>>())
+        }
+        Command::ListReceipts { pool, owner } => {
+            let receipts = fetch_receipts(ctx, &pool.pool, owner.as_ref())?;
+            Ok(json!({
+                "receipts": receipts
+                    .iter()
+                    .map(|(address, receipt)| receipt_to_json(address, receipt))
+                    .collect::<Vec<_>>(),
+            }))
+        }
+    }
+}
+
+fn reward_vault_at(stake_pool: &StakePool, index: u8) -> Result<Pubkey> {
+    match stake_pool.reward_pools.get(usize::from(index)) {
+        Some(reward_pool) if !reward_pool.is_empty() => Ok(reward_pool.reward_vault),
+        _ => bail!("no reward pool at index {index}"),
+    }
+}
+
+/// The reward mint and the keypair's associated token account for each active reward pool,
+/// in pool order, along with the instructions creating any of the accounts that are missing.
+fn reward_recipient_atas(
+    ctx: &Context,
+    stake_pool: &StakePool,
+) -> Result<(Vec<Instruction>, Vec<RewardMint>, Vec<Pubkey>)> {
+    let owner = ctx.pubkey();
+    let mut ixs = vec![];
+    let mut reward_mints = vec![];
+    let mut recipients = vec![];
+    for (_, reward_pool) in active_reward_pools(stake_pool) {
+        let reward_mint = ctx.fetch_reward_mint(&reward_pool.reward_vault)?;
+        ixs.push(create_associated_token_account_idempotent(
+            &owner,
+            &owner,
+            &reward_mint.mint,
+            &reward_mint.token_program,
+        ));
+        recipients.push(get_associated_token_address_with_program_id(
+            &owner,
+            &reward_mint.mint,
+            &reward_mint.token_program,
+        ));
+        reward_mints.push(reward_mint);
+    }
+    Ok((ixs, reward_mints, recipients))
+}
+
+fn find_unused_receipt_nonce(ctx: &Context, stake_pool: &Pubkey, owner: &Pubkey) -> Result<u32> {
+    // the index only lists receipts the owner authorized since it was created, so the search
+    // still probes
+    let (index, _) = find_user_stake_index_address(owner, stake_pool);
+    let start = match ctx.rpc.get_account_data(&index) {
+        Ok(data) => deserialize_user_stake_index(&data)?.next_nonce,
+        Err(_) => 0,
+    };
+    let end = start.saturating_add(MAX_RECEIPT_NONCE_SEARCH);
+    for nonce in start..end {
+        let (receipt, _) = find_stake_deposit_receipt_address(owner, stake_pool, nonce);
+        if ctx.rpc.get_account_data(&receipt).is_err() {
+            return Ok(nonce);
+        }
+    }
+    bail!("no unused receipt nonce in {start}..{end}, pass --nonce")
+}
+
+fn fetch_receipts(
+    ctx: &Context,
+    stake_pool: &Pubkey,
+    owner: Option<&Pubkey>,
+) -> Result<Vec<(Pubkey, StakeDepositReceipt)>> {
+    // discriminator, then owner at 8, payer at 40 and stake_pool at 72
+    let mut filters = vec![
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            0,
+            StakeDepositReceipt::DISCRIMINATOR.to_vec(),
+        )),
+        RpcFilterType::Memcmp(Memcmp::new_raw_bytes(72, stake_pool.to_bytes().to_vec())),
+    ];
+    if let Some(owner) = owner {
+        filters.push(RpcFilterType::Memcmp(Memcmp::new_raw_bytes(
+            8,
+            owner.to_bytes().to_vec(),
+        )));
+    }
+    ctx.rpc
+        .get_program_accounts_with_config(
+            &PROGRAM_ID,
+            RpcProgramAccountsConfig {
+                filters: Some(filters),
+                ..RpcProgramAccountsConfig::default()
+            },
+        )?
+        .into_iter()
+        .map(|(address, account)| {
+            deserialize_stake_deposit_receipt(&account.data)
+                .map(|receipt| (address, receipt))
+                .map_err(|err| anyhow::anyhow!("{address} is not a StakeDepositReceipt: {err}"))
+        })
+        .collect()
+}
+
+/// Parses an allowlist file of one `<owner> [max_amount]` per line, skipping blank lines and
+/// `#` comments
+fn read_allowlist(path: &str) -> Result<Allowlist> {
+    let contents = std::fs::read_to_string(path)
+        .with_context(|| format!("failed to read allowlist {path}"))?;
+    let mut entries = Vec::new();
+    for (number, line) in contents.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+        let entry =
+            parse_allowlist_entry(line).with_context(|| format!("{path}:{}", number + 1))?;
+        entries.push(entry);
+    }
+    Ok(Allowlist::new(entries))
+}
+
+fn parse_allowlist_entry(line: &str) -> Result<(Pubkey, u64)> {
+    let mut fields = line.split_whitespace();
+    let owner = fields.next().unwrap_or_default().parse()?;
+    let max_amount = fields.next().map(str::parse).transpose()?.unwrap_or(0);
+    if fields.next().is_some() {
+        bail!("expected `<owner> [max_amount]`");
+    }
+    Ok((owner, max_amount))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+fn stake_pool_to_json(address: &Pubkey, stake_pool: &StakePool) -> Value {
+    json!({
+        "address": address.to_string(),
+        "authority": stake_pool.authority.to_string(),
+        "creator": stake_pool.creator.to_string(),
+        "mint": stake_pool.mint.to_string(),
+        "vault": stake_pool.vault.to_string(),
+        "stake_mint": stake_pool.stake_mint.to_string(),
+        "total_weighted_stake": stake_pool.total_weighted_stake_u128().to_string(),
+        "base_weight": stake_pool.base_weight,
+        "max_weight": stake_pool.max_weight,
+        "min_duration": stake_pool.min_duration,
+        "max_duration": stake_pool.max_duration,
+        "nonce": stake_pool.nonce,
+        "flags": stake_pool.flags,
+        "options": stake_pool.options,
+        "unbacked_lp_cap": stake_pool.unbacked_lp_cap,
+        "unbacked_lp_cap_authority": stake_pool.unbacked_lp_cap_authority.to_string(),
+        "minted_unbacked": stake_pool.minted_unbacked,
+        "max_total_deposit": stake_pool.max_total_deposit,
+        "max_deposit_per_owner": stake_pool.max_deposit_per_owner,
+        "total_deposit_amount": stake_pool.total_deposit_amount,
+        "min_deposit_amount": stake_pool.min_deposit_amount,
+        "deposit_allowlist_root": stake_pool
+            .has_deposit_allowlist()
+            .then(|| to_hex(&stake_pool.deposit_allowlist_root)),
+        "reward_pools": active_reward_pools(stake_pool)
+            .map(|(index, reward_pool)| json!({
+                "index": index,
+                "reward_vault": reward_pool.reward_vault.to_string(),
+                "rewards_per_effective_stake": reward_pool
+                    .rewards_per_effective_stake_u128()
+                    .to_string(),
+                "last_amount": reward_pool.last_amount,
+                "total_funded": reward_pool.total_funded,
+                "undistributed_amount": reward_pool.undistributed_amount,
+                "undistributed_policy": reward_pool.undistributed_policy,
+                "undistributed_recipient": reward_pool.undistributed_recipient.to_string(),
+                "release_period": reward_pool.release_period,
+                "release_amount": reward_pool.release_amount,
+                "release_start_timestamp": reward_pool.release_start_timestamp,
+            }))
+            .collect::<Vec<_>>(),
+    })
+}
+
+fn receipt_to_json(address: &Pubkey, receipt: &StakeDepositReceipt) -> Value {
+    json!({
+        "address": address.to_string(),
+        "owner": receipt.owner.to_string(),
+        "deposit_amount": receipt.deposit_amount,
+        "effective_stake": receipt.effective_stake_u128().to_string(),
+        "deposit_timestamp": receipt.deposit_timestamp,
+        "lockup_duration": receipt.lockup_duration,
+        "unlocks_at": receipt.deposit_timestamp.saturating_add_unsigned(receipt.lockup_duration),
+    })
+}
@@ -0,0 +1,209 @@
+use std::fmt;
+
+use alris_staking_client::{
+    accounts::{deserialize_stake_deposit_receipt, deserialize_stake_pool},
+    alris_staking::state::{StakeDepositReceipt, StakePool},
+    instructions::RewardMint,
+};
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use anchor_spl::token_interface::TokenAccount;
+use anyhow::{anyhow, bail, Context as _, Result};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use serde_json::{json, Value};
+use solana_client::{rpc_client::RpcClient, rpc_config::RpcSimulateTransactionConfig};
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    instruction::Instruction,
+    signature::{read_keypair_file, Keypair, Signer},
+    transaction::Transaction,
+};
+
+pub struct Context {
+    pub rpc: RpcClient,
+    pub payer: Keypair,
+    pub dry_run: bool,
+}
+
+impl Context {
+    pub fn new(url: &str, keypair: Option<&str>, dry_run: bool) -> Result<Self> {
+        let keypair_path = match keypair {
+            Some(path) => path.to_string(),
+            None => format!(
+                "{}/.config/solana/id.json",
+                std::env::var("HOME").context("HOME is not set, pass --keypair")?
+            ),
+        };
+        let payer = read_keypair(&keypair_path)?;
+        Ok(Self {
+            rpc: RpcClient::new_with_commitment(url.to_string(), CommitmentConfig::confirmed()),
+            payer,
+            dry_run,
+        })
+    }
+
+    pub fn pubkey(&self) -> Pubkey {
+        self.payer.pubkey()
+    }
+
+    pub fn fetch_stake_pool(&self, address: &Pubkey) -> Result<StakePool> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .with_context(|| format!("failed to fetch StakePool {address}"))?;
+        deserialize_stake_pool(&data).map_err(|err| anyhow!("{address} is not a StakePool: {err}"))
+    }
+
+    pub fn fetch_stake_deposit_receipt(&self, address: &Pubkey) -> Result<StakeDepositReceipt> {
+        let data = self
+            .rpc
+            .get_account_data(address)
+            .with_context(|| format!("failed to fetch StakeDepositReceipt {address}"))?;
+        deserialize_stake_deposit_receipt(&data)
+            .map_err(|err| anyhow!("{address} is not a StakeDepositReceipt: {err}"))
+    }
+
+    /// Owner of a mint or token account, either the token program or Token-2022
+    pub fn fetch_token_program(&self, address: &Pubkey) -> Result<Pubkey> {
+        Ok(self
+            .rpc
+            .get_account(address)
+            .with_context(|| format!("failed to fetch token program of {address}"))?
+            .owner)
+    }
+
+    /// Mint of a reward vault and the token program owning it
+    pub fn fetch_reward_mint(&self, reward_vault: &Pubkey) -> Result<RewardMint> {
+        let account = self
+            .rpc
+            .get_account(reward_vault)
+            .with_context(|| format!("failed to fetch reward vault {reward_vault}"))?;
+        let vault = TokenAccount::try_deserialize(&mut &account.data[..])
+            .map_err(|err| anyhow!("{reward_vault} is not a token account: {err}"))?;
+        Ok(RewardMint {
+            mint: vault.mint,
+            token_program: account.owner,
+        })
+    }
+
+    /// Simulates `instructions` and returns the return data of the last one to set it
+    pub fn simulate_return_data(&self, instructions: &[Instruction]) -> Result<Vec<u8>> {
+        let transaction = Transaction::new_with_payer(instructions, Some(&self.payer.pubkey()));
+        let simulation = self
+            .rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )?
+            .value;
+        if let Some(err) = simulation.err {
+            bail!(
+                "simulation failed: {err}\n{}",
+                simulation.logs.unwrap_or_default().join("\n")
+            );
+        }
+        let return_data = simulation
+            .return_data
+            .context("simulation returned no data")?;
+        Ok(BASE64_STANDARD.decode(return_data.data.0)?)
+    }
+
+    /// Signs with the payer and sends `instructions`, or simulates them under `--dry-run`,
+    /// failing with `SimulationFailed` when the simulation does.
+    pub fn execute(&self, instructions: Vec<Instruction>) -> Result<Value> {
+        self.execute_with_signers(instructions, &[])
+    }
+
+    /// `execute` with `signers` signing next to the payer
+    pub fn execute_with_signers(
+        &self,
+        instructions: Vec<Instruction>,
+        signers: &[&Keypair],
+    ) -> Result<Value> {
+        let blockhash = self.rpc.get_latest_blockhash()?;
+        let mut keypairs = vec![&self.payer];
+        keypairs.extend(signers);
+        let transaction = Transaction::new_signed_with_payer(
+            &instructions,
+            Some(&self.payer.pubkey()),
+            &keypairs,
+            blockhash,
+        );
+
+        if !self.dry_run {
+            let signature = self
+                .rpc
+                .send_and_confirm_transaction(&transaction)
+                .context("transaction failed")?;
+            return Ok(json!({ "signature": signature.to_string() }));
+        }
+
+        let simulation = self
+            .rpc
+            .simulate_transaction_with_config(
+                &transaction,
+                RpcSimulateTransactionConfig {
+                    sig_verify: false,
+                    replace_recent_blockhash: true,
+                    ..RpcSimulateTransactionConfig::default()
+                },
+            )?
+            .value;
+        let err = simulation.err.map(|err| err.to_string());
+        let report = json!({
+            "instructions": instructions.iter().map(instruction_to_json).collect::<Vec<_>>(),
+            "simulation": {
+                "err": err,
+                "units_consumed": simulation.units_consumed,
+                "logs": simulation.logs.unwrap_or_default(),
+            },
+        });
+        match err {
+            Some(err) => Err(SimulationFailed { report, err }.into()),
+            None => Ok(report),
+        }
+    }
+}
+
+/// A `--dry-run` simulation that failed. It carries the report `execute` would have
+/// returned so the simulation and its logs are still printed ahead of the error.
+#[derive(Debug)]
+pub struct SimulationFailed {
+    pub report: Value,
+    err: String,
+}
+
+impl fmt::Display for SimulationFailed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "simulation failed: {}", self.err)
+    }
+}
+
+impl std::error::Error for SimulationFailed {}
+
+pub fn read_keypair(path: &str) -> Result<Keypair> {
+    read_keypair_file(path).map_err(|err| anyhow!("failed to read keypair {path}: {err}"))
+}
+
+fn instruction_to_json(instruction: &Instruction) -> Value {
+    json!({
+        "program_id": instruction.program_id.to_string(),
+        "accounts": instruction
+            .accounts
+            .iter()
+            .map(|meta| json!({
+                "pubkey": meta.pubkey.to_string(),
+                "is_signer": meta.is_signer,
+                "is_writable": meta.is_writable,
+            }))
+            .collect::<Vec<_>>(),
+        "data": instruction
+            .data
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>(),
+    })
+}
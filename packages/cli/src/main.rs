@@ -0,0 +1,368 @@
+//! `alris-staking`: operator tooling for the Alris staking program.
+
+mod commands;
+mod context;
+mod output;
+
+use alris_staking_client::alris_staking::state::DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD;
+use anchor_lang::prelude::Pubkey;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+
+use crate::{
+    context::{Context, SimulationFailed},
+    output::OutputFormat,
+};
+
+#[derive(Parser)]
+#[command(name = "alris-staking", version, about)]
+struct Cli {
+    /// RPC endpoint
+    #[arg(
+        long,
+        short = 'u',
+        global = true,
+        env = "ALRIS_RPC_URL",
+        default_value = "http://localhost:8899"
+    )]
+    url: String,
+
+    /// Keypair paying fees and acting as authority or owner. Defaults to the Solana CLI keypair.
+    #[arg(long, short = 'k', global = true, env = "ALRIS_KEYPAIR")]
+    keypair: Option<String>,
+
+    /// Print the instructions and the simulated outcome without sending the transaction
+    #[arg(long, global = true)]
+    dry_run: bool,
+
+    /// Output format, `json` for scripting
+    #[arg(long, short = 'o', global = true, value_enum, default_value_t = OutputFormat::Text)]
+    output: OutputFormat,
+
+    #[command(subcommand)]
+    command: Command,
+}
+
+#[derive(Args)]
+struct PoolArg {
+    /// StakePool address
+    #[arg(long)]
+    pool: Pubkey,
+}
+
+#[derive(Subcommand)]
+enum Command {
+    /// Create a StakePool for `mint` with the keypair as authority
+    CreatePool {
+        #[arg(long)]
+        mint: Pubkey,
+        #[arg(long, default_value_t = 0)]
+        nonce: u8,
+        /// Weight of a max duration lockup, scaled by 10^9
+        #[arg(long)]
+        max_weight: u64,
+        /// Minimum lockup in seconds
+        #[arg(long)]
+        min_duration: u64,
+        /// Lockup in seconds earning `max_weight`
+        #[arg(long)]
+        max_duration: u64,
+        /// Create the LP mint as a non-transferable Token-2022 mint
+        #[arg(long)]
+        soulbound: bool,
+    },
+    /// Show a StakePool and its reward pools
+    ShowPool {
+        #[command(flatten)]
+        pool: PoolArg,
+    },
+    /// Add a reward pool at `index`
+    AddRewardPool {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        reward_mint: Pubkey,
+        #[arg(long)]
+        index: u8,
+    },
+    /// Fund the reward pool at `index` from the keypair's associated token account
+    FundRewardPool {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        index: u8,
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Configure what happens to rewards arriving while nothing is staked
+    SetRewardPoolPolicy {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        index: u8,
+        #[arg(long, value_enum)]
+        policy: UndistributedPolicy,
+        /// Seconds over which held rewards are released under the `hold` policy
+        #[arg(long, default_value_t = DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD)]
+        release_period: u64,
+        /// Token account the `sweep` policy sends undistributed rewards to
+        #[arg(long)]
+        recipient: Option<Pubkey>,
+    },
+    /// Send the undistributed rewards of a reward pool to its recipient
+    SweepUndistributed {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        index: u8,
+    },
+    /// Send the whole tokens of unclaimable reward dust to `destination`
+    SweepRewardDust {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        index: u8,
+        #[arg(long)]
+        destination: Pubkey,
+    },
+    /// Turn pool flags on or off, leaving the others untouched
+    SetFlags {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long, value_enum)]
+        enable: Vec<Flag>,
+        #[arg(long, value_enum)]
+        disable: Vec<Flag>,
+    },
+    /// Cap the pool's total deposits and each owner's deposits, leaving out a cap keeps it
+    SetDepositCaps {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// Zero for no cap
+        #[arg(long)]
+        max_total_deposit: Option<u64>,
+        /// Zero for no cap
+        #[arg(long)]
+        max_deposit_per_owner: Option<u64>,
+    },
+    /// Restrict deposits to the owners of an allowlist file, or open them to anyone
+    SetDepositAllowlist {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// One `<owner> [max_amount]` per line, a missing or zero `max_amount` for no limit.
+        /// Leave out to lift the allowlist.
+        #[arg(long)]
+        allowlist: Option<String>,
+    },
+    /// Set the smallest amount a single deposit may stake
+    SetMinDepositAmount {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// Zero for no minimum
+        #[arg(long)]
+        amount: u64,
+    },
+    /// Grow a pool created before reward pools tracked undistributed rewards into the
+    /// current layout, no other command works on it until then
+    MigrateStakePool {
+        #[command(flatten)]
+        pool: PoolArg,
+    },
+    /// Hand the pool authority over to `new_authority`
+    TransferAuthority {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        new_authority: Pubkey,
+    },
+    /// Create or update the Metaplex metadata of the LP mint
+    UpdateTokenMeta {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        name: String,
+        #[arg(long)]
+        symbol: String,
+        #[arg(long)]
+        uri: String,
+    },
+    /// Stake from the keypair's associated token account
+    Deposit {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        amount: u64,
+        /// Lockup in seconds
+        #[arg(long)]
+        lockup_duration: u64,
+        /// Receipt nonce, defaults to the first unused one
+        #[arg(long)]
+        nonce: Option<u32>,
+        /// Keypair owning the tokens and the receipt, signing for the transfer while the
+        /// CLI keypair only pays fees and rent
+        #[arg(long)]
+        owner: Option<String>,
+        /// Allowlist file the pool's allowlist root was built from, see
+        /// `set-deposit-allowlist`, required while the pool has one
+        #[arg(long)]
+        allowlist: Option<String>,
+    },
+    /// Claim all rewards of one or more receipts into the keypair's associated token accounts
+    Claim {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// Repeat to claim several receipts in one instruction
+        #[arg(long, required = true)]
+        receipt: Vec<Pubkey>,
+    },
+    /// Show the rewards a receipt could claim now, as computed by the program
+    PendingRewards {
+        #[command(flatten)]
+        pool: PoolArg,
+        #[arg(long)]
+        receipt: Pubkey,
+    },
+    /// Withdraw one or more unlocked receipts along with their pending rewards
+    Withdraw {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// Repeat to withdraw several receipts in one instruction
+        #[arg(long, required = true)]
+        receipt: Vec<Pubkey>,
+        /// Keypair holding the receipt's LP tokens when they were moved off the owner's
+        /// associated token account
+        #[arg(long)]
+        lp_authority: Option<String>,
+    },
+    /// List the pool's deposit receipts
+    ListReceipts {
+        #[command(flatten)]
+        pool: PoolArg,
+        /// Only list receipts of this owner
+        #[arg(long)]
+        owner: Option<Pubkey>,
+    },
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum Flag {
+    EscapeHatch,
+    DisableDeposits,
+    DepositIgnoresLp,
+    WithdrawIgnoresLp,
+    RefundRentToOwner,
+}
+
+impl Flag {
+    fn bits(self) -> u8 {
+        use alris_staking_client::alris_staking::state::*;
+        match self {
+            Flag::EscapeHatch => ESCAPE_HATCH_ENABLED,
+            Flag::DisableDeposits => DISABLE_DEPOSITS,
+            Flag::DepositIgnoresLp => DEPOSIT_IGNORES_LP,
+            Flag::WithdrawIgnoresLp => WITHDRAW_IGNORES_LP,
+            Flag::RefundRentToOwner => REFUND_RENT_TO_OWNER,
+        }
+    }
+
+    /// `flags` with `enable` turned on, then `disable` turned off
+    fn apply(flags: u8, enable: &[Flag], disable: &[Flag]) -> u8 {
+        let flags = enable.iter().fold(flags, |flags, flag| flags | flag.bits());
+        disable
+            .iter()
+            .fold(flags, |flags, flag| flags & !flag.bits())
+    }
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum UndistributedPolicy {
+    Hold,
+    Sweep,
+}
+
+impl UndistributedPolicy {
+    fn value(self) -> u8 {
+        use alris_staking_client::alris_staking::state::*;
+        match self {
+            UndistributedPolicy::Hold => UNDISTRIBUTED_HOLD,
+            UndistributedPolicy::Sweep => UNDISTRIBUTED_SWEEP,
+        }
+    }
+}
+
+fn main() {
+    let cli = Cli::parse();
+    let output = cli.output;
+    let result = Context::new(&cli.url, cli.keypair.as_deref(), cli.dry_run)
+        .and_then(|ctx| commands::run(&ctx, cli.command));
+    match result {
+        Ok(report) => output::print(output, &report),
+        Err(err) => {
+            if let Some(failed) = err.downcast_ref::<SimulationFailed>() {
+                output::print(output, &failed.report);
+            }
+            output::print_error(output, &err);
+            std::process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clap::CommandFactory;
+
+    use super::*;
+
+    #[test]
+    fn cli_definition_is_valid() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn flag_bits_round_trip() {
+        let flags = Flag::value_variants();
+        let all = Flag::apply(0, flags, &[]);
+        assert_eq!(all.count_ones() as usize, flags.len());
+        assert_eq!(Flag::apply(all, &[], flags), 0);
+
+        for flag in flags {
+            let name = flag.to_possible_value().unwrap();
+            let parsed = Flag::from_str(name.get_name(), false).unwrap();
+            assert_eq!(parsed.bits(), flag.bits());
+            assert_eq!(Flag::apply(0, &[*flag], &[]), flag.bits());
+            assert_eq!(Flag::apply(all, &[], &[*flag]), all & !flag.bits());
+        }
+    }
+
+    #[test]
+    fn set_flags_parses_enable_and_disable() {
+        use alris_staking_client::alris_staking::state::*;
+
+        let pool = Pubkey::new_unique();
+        let cli = Cli::try_parse_from([
+            "alris-staking",
+            "set-flags",
+            "--pool",
+            &pool.to_string(),
+            "--enable",
+            "escape-hatch",
+            "--enable",
+            "refund-rent-to-owner",
+            "--disable",
+            "disable-deposits",
+        ])
+        .unwrap();
+        let Command::SetFlags {
+            pool: PoolArg { pool: parsed },
+            enable,
+            disable,
+        } = cli.command
+        else {
+            panic!("expected set-flags");
+        };
+        assert_eq!(parsed, pool);
+        assert_eq!(
+            Flag::apply(DISABLE_DEPOSITS, &enable, &disable),
+            ESCAPE_HATCH_ENABLED | REFUND_RENT_TO_OWNER
+        );
+    }
+}
@@ -0,0 +1,59 @@
+use clap::ValueEnum;
+use serde_json::{json, Value};
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum OutputFormat {
+    Text,
+    Json,
+}
+
+pub fn print(format: OutputFormat, report: &Value) {
+    match format {
+        OutputFormat::Json => println!("{}", serde_json::to_string_pretty(report).unwrap()),
+        OutputFormat::Text => print_text(report, 0),
+    }
+}
+
+pub fn print_error(format: OutputFormat, err: &anyhow::Error) {
+    match format {
+        OutputFormat::Json => println!(
+            "{}",
+            serde_json::to_string_pretty(&json!({ "error": format!("{err:#}") })).unwrap()
+        ),
+        OutputFormat::Text => eprintln!("error: {err:#}"),
+    }
+}
+
+fn print_text(value: &Value, indent: usize) {
+    let pad = "  ".repeat(indent);
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                if value.is_object() || value.is_array() {
+                    println!("{pad}{key}:");
+                    print_text(value, indent + 1);
+                } else {
+                    println!("{pad}{key}: {}", scalar(value));
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                if item.is_object() || item.is_array() {
+                    println!("{pad}-");
+                    print_text(item, indent + 1);
+                } else {
+                    println!("{pad}- {}", scalar(item));
+                }
+            }
+        }
+        _ => println!("{pad}{}", scalar(value)),
+    }
+}
+
+fn scalar(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        value => value.to_string(),
+    }
+}
@@ -20,6 +20,9 @@ export const ESCAPE_HATCH_ENABLED = 1;
 export const DEPOSITS_DISABLED = 4;
 export const DEPOSIT_IGNORES_LP = 8;
 export const WITHDRAW_IGNORES_LP = 16;
+export const REFUND_RENT_TO_OWNER = 32;
+
+export const SOULBOUND_STAKE_MINT = 1;
 
 export const ALR_STAKING_PROGRAM_ID = new anchor.web3.PublicKey(
   "ALRPAsu4Aqmb8VCVcnjFQo5XF2K8MCDGmTECvZw4YJVY"
@@ -17,7 +17,7 @@ export const _AlrisStakingIDL = {
         {
           "name": "authority",
           "isMut": false,
-          "isSigner": false
+          "isSigner": true
         },
         {
           "name": "mint",
@@ -42,7 +42,20 @@ export const _AlrisStakingIDL = {
         {
           "name": "tokenProgram",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "Token program of `mint`, the LP mint and vault are created under it as well. The",
+            "vault initialization fails if it does not own `mint`."
+          ]
+        },
+        {
+          "name": "stakeTokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program the LP mint is created under, Token-2022 for a `SOULBOUND_STAKE_MINT`",
+            "StakePool and `token_program` otherwise"
+          ]
         },
         {
           "name": "rent",
@@ -71,9 +84,53 @@ export const _AlrisStakingIDL = {
         {
           "name": "maxDuration",
           "type": "u64"
+        },
+        {
+          "name": "options",
+          "type": "u8"
         }
       ]
     },
+    {
+      "name": "migrateStakePool",
+      "accounts": [
+        {
+          "name": "payer",
+          "isMut": true,
+          "isSigner": true,
+          "docs": [
+            "Pays the rent of the grown StakePool"
+          ]
+        },
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "Its discriminator, size and authority are checked by the handler."
+          ]
+        },
+        {
+          "name": "vault",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "The StakePool's vault, its balance seeds `total_deposit_amount`"
+          ]
+        },
+        {
+          "name": "systemProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
     {
       "name": "transferAuthority",
       "accounts": [
@@ -135,73 +192,171 @@ export const _AlrisStakingIDL = {
         {
           "name": "tokenProgram",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "Token program of `stake_mint`"
+          ]
         }
       ],
       "args": [
         {
           "name": "amount",
           "type": "u64"
+        },
+        {
+          "name": "reason",
+          "type": "string"
         }
       ]
     },
     {
-      "name": "addRewardPool",
+      "name": "burnUnbackedLp",
       "accounts": [
-        {
-          "name": "payer",
-          "isMut": true,
-          "isSigner": true
-        },
         {
           "name": "authority",
           "isMut": false,
-          "isSigner": true
+          "isSigner": true,
+          "docs": [
+            "Authority of the StakePool, holds `from`"
+          ]
         },
         {
-          "name": "rewardMint",
-          "isMut": false,
+          "name": "stakeMint",
+          "isMut": true,
           "isSigner": false
         },
         {
-          "name": "stakePool",
+          "name": "from",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "rewardVault",
+          "name": "stakePool",
           "isMut": true,
           "isSigner": false
         },
         {
           "name": "tokenProgram",
           "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `stake_mint`"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "amount",
+          "type": "u64"
+        },
+        {
+          "name": "reason",
+          "type": "string"
+        }
+      ]
+    },
+    {
+      "name": "setUnbackedLpCap",
+      "accounts": [
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true,
+          "docs": [
+            "Authority of the StakePool to lower the cap, `unbacked_lp_cap_authority` to raise or",
+            "lower it"
+          ]
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
           "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "cap",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "setUnbackedLpCapAuthority",
+      "accounts": [
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true,
+          "docs": [
+            "Authority of the StakePool while no `unbacked_lp_cap_authority` is named,",
+            "`unbacked_lp_cap_authority` after"
+          ]
         },
         {
-          "name": "rent",
+          "name": "newAuthority",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "systemProgram",
+          "name": "stakePool",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "setDepositCaps",
+      "accounts": [
+        {
+          "name": "authority",
           "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
           "isSigner": false
         }
       ],
       "args": [
         {
-          "name": "index",
-          "type": "u8"
+          "name": "maxTotalDeposit",
+          "type": "u64"
+        },
+        {
+          "name": "maxDepositPerOwner",
+          "type": "u64"
         }
       ]
     },
     {
-      "name": "setFlags",
+      "name": "setMinDepositAmount",
       "accounts": [
         {
           "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
           "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "minDepositAmount",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "setDepositAllowlist",
+      "accounts": [
+        {
+          "name": "authority",
+          "isMut": false,
           "isSigner": true
         },
         {
@@ -212,13 +367,18 @@ export const _AlrisStakingIDL = {
       ],
       "args": [
         {
-          "name": "flags",
-          "type": "u8"
+          "name": "root",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          }
         }
       ]
     },
     {
-      "name": "deposit",
+      "name": "addRewardPool",
       "accounts": [
         {
           "name": "payer",
@@ -226,155 +386,222 @@ export const _AlrisStakingIDL = {
           "isSigner": true
         },
         {
-          "name": "owner",
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "rewardMint",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "from",
+          "name": "stakePool",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "vault",
+          "name": "rewardVault",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "stakeMint",
-          "isMut": true,
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `reward_mint`, the vault initialization fails if it does not own the mint"
+          ]
+        },
+        {
+          "name": "rent",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "destination",
-          "isMut": true,
+          "name": "systemProgram",
+          "isMut": false,
           "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "index",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "fundRewardPool",
+      "accounts": [
+        {
+          "name": "funder",
+          "isMut": false,
+          "isSigner": true,
+          "docs": [
+            "Owner of the `from` token account"
+          ]
         },
         {
-          "name": "stakePool",
+          "name": "from",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "stakeDepositReceipt",
+          "name": "stakePool",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "tokenProgram",
-          "isMut": false,
-          "isSigner": false
+          "name": "rewardVault",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "Vault of the RewardPool at `index`"
+          ]
         },
         {
-          "name": "rent",
+          "name": "rewardMint",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "systemProgram",
+          "name": "tokenProgram",
           "isMut": false,
-          "isSigner": false
+          "isSigner": false,
+          "docs": [
+            "Token program of `reward_mint`"
+          ]
         }
       ],
       "args": [
         {
-          "name": "nonce",
-          "type": "u32"
+          "name": "index",
+          "type": "u8"
         },
         {
           "name": "amount",
           "type": "u64"
-        },
-        {
-          "name": "lockupDuration",
-          "type": "u64"
         }
       ]
     },
     {
-      "name": "claimAll",
+      "name": "setFlags",
       "accounts": [
         {
-          "name": "claimBase",
-          "accounts": [
-            {
-              "name": "owner",
-              "isMut": true,
-              "isSigner": true
-            },
-            {
-              "name": "stakePool",
-              "isMut": true,
-              "isSigner": false
-            },
-            {
-              "name": "stakeDepositReceipt",
-              "isMut": true,
-              "isSigner": false
-            },
-            {
-              "name": "tokenProgram",
-              "isMut": false,
-              "isSigner": false
-            }
-          ]
+          "name": "authority",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
+          "isSigner": false
         }
       ],
-      "args": []
+      "args": [
+        {
+          "name": "flags",
+          "type": "u8"
+        }
+      ]
     },
     {
-      "name": "withdraw",
+      "name": "setRewardPoolPolicy",
       "accounts": [
         {
-          "name": "claimBase",
-          "accounts": [
-            {
-              "name": "owner",
-              "isMut": true,
-              "isSigner": true
-            },
-            {
-              "name": "stakePool",
-              "isMut": true,
-              "isSigner": false
-            },
-            {
-              "name": "stakeDepositReceipt",
-              "isMut": true,
-              "isSigner": false
-            },
-            {
-              "name": "tokenProgram",
-              "isMut": false,
-              "isSigner": false
-            }
-          ]
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
         },
         {
-          "name": "vault",
+          "name": "stakePool",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "stakeMint",
-          "isMut": true,
+          "name": "rewardVault",
+          "isMut": false,
           "isSigner": false
         },
         {
-          "name": "from",
+          "name": "undistributedRecipient",
+          "isMut": false,
+          "isSigner": false,
+          "isOptional": true,
+          "docs": [
+            "Token account the undistributed rewards are swept to, the funder's or a treasury.",
+            "Required by `UNDISTRIBUTED_SWEEP`."
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "index",
+          "type": "u8"
+        },
+        {
+          "name": "policy",
+          "type": "u8"
+        },
+        {
+          "name": "releasePeriod",
+          "type": "u64"
+        }
+      ]
+    },
+    {
+      "name": "sweepUndistributed",
+      "accounts": [
+        {
+          "name": "authority",
+          "isMut": false,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "destination",
+          "name": "rewardVault",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "Vault of the RewardPool at `index`"
+          ]
+        },
+        {
+          "name": "undistributedRecipient",
           "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "Must be the RewardPool's `undistributed_recipient`"
+          ]
+        },
+        {
+          "name": "rewardMint",
+          "isMut": false,
           "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `reward_mint`"
+          ]
         }
       ],
-      "args": []
+      "args": [
+        {
+          "name": "index",
+          "type": "u8"
+        }
+      ]
     },
     {
-      "name": "updateTokenMeta",
+      "name": "sweepRewardDust",
       "accounts": [
         {
           "name": "authority",
@@ -382,219 +609,1616 @@ export const _AlrisStakingIDL = {
           "isSigner": true
         },
         {
-          "name": "metadataAccount",
+          "name": "stakePool",
           "isMut": true,
           "isSigner": false
         },
         {
-          "name": "stakePool",
+          "name": "rewardVault",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "Vault of the RewardPool at `index`"
+          ]
+        },
+        {
+          "name": "destination",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "rewardMint",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "stakeMint",
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `reward_mint`"
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "index",
+          "type": "u8"
+        }
+      ]
+    },
+    {
+      "name": "deposit",
+      "accounts": [
+        {
+          "name": "payer",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "owner",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "metadataProgram",
+          "name": "from",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "vault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "mint",
           "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "StakePool mint, passed for `transfer_checked`"
+          ]
+        },
+        {
+          "name": "stakeMint",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "destination",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "stakeDepositReceipt",
+          "isMut": true,
           "isSigner": false
         },
+        {
+          "name": "userStakeIndex",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "tokens and grown as receipts are added. Left alone unless `is_owner_funded`."
+          ]
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `mint`"
+          ]
+        },
+        {
+          "name": "stakeTokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `stake_mint`, see `StakePool::stake_token_program`"
+          ]
+        },
         {
           "name": "rent",
           "isMut": false,
           "isSigner": false
         },
         {
-          "name": "systemProgram",
-          "isMut": false,
-          "isSigner": false
-        }
-      ],
-      "args": [
+          "name": "systemProgram",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "tokenAuthority",
+          "isMut": false,
+          "isSigner": true,
+          "isOptional": true,
+          "docs": [
+            "Signs the transfer out of `from` in place of `payer`, as the owner or as a delegate",
+            "the owner approved on `from`. A relayer then only pays fees and rent, and the tokens",
+            "must be the owner's."
+          ]
+        },
+        {
+          "name": "depositAllowance",
+          "isMut": true,
+          "isSigner": false,
+          "isOptional": true,
+          "docs": [
+            "allowlist and created on the owner's first allowlisted deposit. Only `owner` signing",
+            "can draw it down."
+          ]
+        }
+      ],
+      "args": [
+        {
+          "name": "nonce",
+          "type": "u32"
+        },
+        {
+          "name": "amount",
+          "type": "u64"
+        },
+        {
+          "name": "lockupDuration",
+          "type": "u64"
+        },
+        {
+          "name": "allowlistProof",
+          "type": {
+            "option": {
+              "defined": "AllowlistProof"
+            }
+          }
+        }
+      ]
+    },
+    {
+      "name": "claimAll",
+      "accounts": [
+        {
+          "name": "claimBase",
+          "accounts": [
+            {
+              "name": "owner",
+              "isMut": true,
+              "isSigner": true
+            },
+            {
+              "name": "stakePool",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "stakeDepositReceipt",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "tokenProgram",
+              "isMut": false,
+              "isSigner": false,
+              "docs": [
+                "Token program of the StakePool mint. Rewards are paid through the token program",
+                "passed with each reward vault."
+              ]
+            }
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "claimMany",
+      "docs": [
+        "`claim_all` over several receipts of the owner, passed after the reward accounts"
+      ],
+      "accounts": [
+        {
+          "name": "owner",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
+          "isSigner": false
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "withdraw",
+      "accounts": [
+        {
+          "name": "claimBase",
+          "accounts": [
+            {
+              "name": "owner",
+              "isMut": true,
+              "isSigner": true
+            },
+            {
+              "name": "stakePool",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "stakeDepositReceipt",
+              "isMut": true,
+              "isSigner": false
+            },
+            {
+              "name": "tokenProgram",
+              "isMut": false,
+              "isSigner": false,
+              "docs": [
+                "Token program of the StakePool mint. Rewards are paid through the token program",
+                "passed with each reward vault."
+              ]
+            }
+          ]
+        },
+        {
+          "name": "vault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "mint",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "StakePool mint, passed for `transfer_checked`"
+          ]
+        },
+        {
+          "name": "stakeMint",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "from",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "LP token account burned from, held by the owner or by `lp_authority`"
+          ]
+        },
+        {
+          "name": "destination",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "stakeTokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `stake_mint`, see `StakePool::stake_token_program`"
+          ]
+        },
+        {
+          "name": "userStakeIndex",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "deposited since the index was introduced have none."
+          ]
+        },
+        {
+          "name": "rentRecipient",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "lpAuthority",
+          "isMut": false,
+          "isSigner": true,
+          "isOptional": true,
+          "docs": [
+            "Holder of `from` when the owner's LP tokens have moved to another wallet, signs for",
+            "the burn in place of the owner"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "withdrawMany",
+      "docs": [
+        "`withdraw` over several unlocked receipts of the owner, passed after the reward",
+        "accounts"
+      ],
+      "accounts": [
+        {
+          "name": "owner",
+          "isMut": true,
+          "isSigner": true
+        },
+        {
+          "name": "stakePool",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "vault",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "mint",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "StakePool mint, passed for `transfer_checked`"
+          ]
+        },
+        {
+          "name": "stakeMint",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "from",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "LP token account burned from, held by the owner or by `lp_authority`"
+          ]
+        },
+        {
+          "name": "destination",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "tokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of the StakePool mint"
+          ]
+        },
+        {
+          "name": "stakeTokenProgram",
+          "isMut": false,
+          "isSigner": false,
+          "docs": [
+            "Token program of `stake_mint`, see `StakePool::stake_token_program`"
+          ]
+        },
+        {
+          "name": "userStakeIndex",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "rentRecipient",
+          "isMut": true,
+          "isSigner": false,
+          "docs": [
+            "refunding different accounts are withdrawn in separate instructions."
+          ]
+        },
+        {
+          "name": "lpAuthority",
+          "isMut": false,
+          "isSigner": true,
+          "isOptional": true,
+          "docs": [
+            "Holder of `from` when the owner's LP tokens have moved to another wallet, signs for",
+            "the burn in place of the owner"
+          ]
+        }
+      ],
+      "args": []
+    },
+    {
+      "name": "getClaimable",
+      "docs": [
+        "Read-only, the claimable amounts by reward pool index are set as return data"
+      ],
+      "accounts": [
+        {
+          "name": "stakePool",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "stakeDepositReceipt",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [],
+      "returns": {
+        "array": [
+          "u64",
+          10
+        ]
+      }
+    },
+    {
+      "name": "updateTokenMeta",
+      "accounts": [
+        {
+          "name": "authority",
+          "isMut": true,
+          "isSigner": true,
+          "docs": [
+            "Pays for the metadata account when it is created, or for the metadata written into a",
+            "soulbound LP mint"
+          ]
+        },
+        {
+          "name": "metadataAccount",
+          "isMut": true,
+          "isSigner": false
+        },
+        {
+          "name": "stakePool",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "stakeMint",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "metadataProgram",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "rent",
+          "isMut": false,
+          "isSigner": false
+        },
+        {
+          "name": "systemProgram",
+          "isMut": false,
+          "isSigner": false
+        }
+      ],
+      "args": [
+        {
+          "name": "name",
+          "type": "string"
+        },
+        {
+          "name": "symbol",
+          "type": "string"
+        },
+        {
+          "name": "uri",
+          "type": "string"
+        }
+      ]
+    }
+  ],
+  "accounts": [
+    {
+      "name": "StakePool",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "creator",
+            "type": "publicKey"
+          },
+          {
+            "name": "authority",
+            "type": "publicKey"
+          },
+          {
+            "name": "totalWeightedStake",
+            "type": "u128"
+          },
+          {
+            "name": "vault",
+            "type": "publicKey"
+          },
+          {
+            "name": "mint",
+            "type": "publicKey"
+          },
+          {
+            "name": "stakeMint",
+            "type": "publicKey"
+          },
+          {
+            "name": "rewardPools",
+            "type": {
+              "array": [
+                {
+                  "defined": "RewardPool"
+                },
+                10
+              ]
+            }
+          },
+          {
+            "name": "baseWeight",
+            "type": "u64"
+          },
+          {
+            "name": "maxWeight",
+            "type": "u64"
+          },
+          {
+            "name": "minDuration",
+            "type": "u64"
+          },
+          {
+            "name": "maxDuration",
+            "type": "u64"
+          },
+          {
+            "name": "nonce",
+            "type": "u8"
+          },
+          {
+            "name": "bumpSeed",
+            "type": "u8"
+          },
+          {
+            "name": "flags",
+            "type": "u8"
+          },
+          {
+            "name": "options",
+            "type": "u8"
+          },
+          {
+            "name": "padding0",
+            "type": {
+              "array": [
+                "u8",
+                4
+              ]
+            }
+          },
+          {
+            "name": "unbackedLpCap",
+            "docs": [
+              "Most LP tokens `dangerously_mint_lp` may have outstanding, zero until set. The",
+              "authority may only lower it, raising it takes `unbacked_lp_cap_authority`."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "mintedUnbacked",
+            "docs": [
+              "LP tokens minted by `dangerously_mint_lp` less those burned by `burn_unbacked_lp`.",
+              "A burn counts against it whichever LP tokens it burns, so burning receipt backed LP",
+              "frees headroom under the cap. The LP supply exceeds what the receipts minted by at",
+              "most this much."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "maxTotalDeposit",
+            "docs": [
+              "Most the receipts may hold in deposits altogether, zero for no cap"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "maxDepositPerOwner",
+            "docs": [
+              "Most one owner's receipts may hold in deposits, as summed by its `UserStakeIndex`,",
+              "zero for no cap. While it is set every deposit must be authorized by its owner so the",
+              "index lists it."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "totalDepositAmount",
+            "docs": [
+              "Sum of `deposit_amount` over the open receipts, seeded from the vault balance for a",
+              "StakePool migrated from the legacy layout"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "minDepositAmount",
+            "docs": [
+              "Smallest amount one deposit may stake, after any transfer fee, zero for no minimum"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "depositAllowlistRoot",
+            "docs": [
+              "Root of the merkle allowlist of owners who may deposit, see `allowlist`, all zero",
+              "when anyone may deposit"
+            ],
+            "type": {
+              "array": [
+                "u8",
+                32
+              ]
+            }
+          },
+          {
+            "name": "unbackedLpCapAuthority",
+            "docs": [
+              "Key allowed to raise `unbacked_lp_cap`, named once by the authority, default while",
+              "nobody may raise it"
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "reserved0",
+            "type": {
+              "array": [
+                "u8",
+                144
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "StakeDepositReceipt",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "owner",
+            "type": "publicKey"
+          },
+          {
+            "name": "payer",
+            "type": "publicKey"
+          },
+          {
+            "name": "stakePool",
+            "type": "publicKey"
+          },
+          {
+            "name": "lockupDuration",
+            "type": "u64"
+          },
+          {
+            "name": "depositTimestamp",
+            "type": "i64"
+          },
+          {
+            "name": "depositAmount",
+            "type": "u64"
+          },
+          {
+            "name": "effectiveStake",
+            "type": "u128"
+          },
+          {
+            "name": "claimedAmounts",
+            "type": {
+              "array": [
+                "u128",
+                10
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "UserStakeIndex",
+      "docs": [
+        "Open receipts of one owner in one StakePool, kept by `deposit` and `withdraw` so clients",
+        "can enumerate positions without scanning program accounts. Only deposits the owner",
+        "authorized, by signing or through a `token_authority` drawing on its tokens, are listed,",
+        "up to `MAX_USER_STAKE_INDEX_RECEIPTS`, so nobody else can grow the index. Receipts",
+        "deposited before the index existed or by others staking their own tokens for the owner",
+        "are not listed."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "owner",
+            "type": "publicKey"
+          },
+          {
+            "name": "stakePool",
+            "type": "publicKey"
+          },
+          {
+            "name": "nextNonce",
+            "docs": [
+              "Above the nonce of every receipt deposited through the index, the nonce to use next"
+            ],
+            "type": "u32"
+          },
+          {
+            "name": "totalDepositAmount",
+            "docs": [
+              "Sum of `deposit_amount` over `receipts`"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "totalEffectiveStake",
+            "docs": [
+              "Sum of `effective_stake` over `receipts`"
+            ],
+            "type": "u128"
+          },
+          {
+            "name": "receipts",
+            "type": {
+              "vec": "publicKey"
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "DepositAllowance",
+      "docs": [
+        "What one owner deposited into a StakePool while it had a deposit allowlist, kept by",
+        "`deposit` to hold the owner to the `max_amount` of its allowlist leaf. Only deposits the",
+        "owner signs count against it, and withdrawals do not give allowance back."
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "owner",
+            "type": "publicKey"
+          },
+          {
+            "name": "stakePool",
+            "type": "publicKey"
+          },
+          {
+            "name": "depositedAmount",
+            "docs": [
+              "Sum of the amounts deposited against the allowlist, after any transfer fee"
+            ],
+            "type": "u64"
+          }
+        ]
+      }
+    }
+  ],
+  "types": [
+    {
+      "name": "AllowlistProof",
+      "docs": [
+        "Proof passed to `deposit` that the owner is in the StakePool's allowlist"
+      ],
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "maxAmount",
+            "docs": [
+              "`max_amount` of the owner's leaf, the most its deposits may add up to, zero for no",
+              "limit"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "proof",
+            "docs": [
+              "Sibling hashes from the owner's leaf up to the root"
+            ],
+            "type": {
+              "vec": {
+                "array": [
+                  "u8",
+                  32
+                ]
+              }
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "RewardPool",
+      "type": {
+        "kind": "struct",
+        "fields": [
+          {
+            "name": "rewardVault",
+            "type": "publicKey"
+          },
+          {
+            "name": "rewardsPerEffectiveStake",
+            "type": "u128"
+          },
+          {
+            "name": "lastAmount",
+            "type": "u64"
+          },
+          {
+            "name": "totalFunded",
+            "docs": [
+              "Cumulative amount transferred in through `fund_reward_pool`"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "undistributedAmount",
+            "docs": [
+              "Tokens that arrived while nothing was staked and have not been credited to stakers.",
+              "Always included in `last_amount`."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "releaseStartTimestamp",
+            "docs": [
+              "Start of the running `UNDISTRIBUTED_HOLD` release, the first accrual with stakers",
+              "after the tokens were held"
+            ],
+            "type": "i64"
+          },
+          {
+            "name": "releasePeriod",
+            "docs": [
+              "Seconds it takes to release `undistributed_amount` under `UNDISTRIBUTED_HOLD`"
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "undistributedRecipient",
+            "docs": [
+              "Token account receiving `undistributed_amount` under `UNDISTRIBUTED_SWEEP`"
+            ],
+            "type": "publicKey"
+          },
+          {
+            "name": "undistributedPolicy",
+            "type": "u8"
+          },
+          {
+            "name": "padding0",
+            "type": {
+              "array": [
+                "u8",
+                7
+              ]
+            }
+          },
+          {
+            "name": "rewardRemainder",
+            "docs": [
+              "Scaled rewards left over by the integer division in the last accrual, carried into",
+              "the next one"
+            ],
+            "type": "u128"
+          },
+          {
+            "name": "unclaimableDust",
+            "docs": [
+              "Scaled rewards floored away from receipt claims. Whole tokens of it can never be",
+              "claimed and may be swept by the authority."
+            ],
+            "type": "u128"
+          },
+          {
+            "name": "releaseAmount",
+            "docs": [
+              "`undistributed_amount` when the running release started, zero when none is running.",
+              "What it exceeds `undistributed_amount` by has been released."
+            ],
+            "type": "u64"
+          },
+          {
+            "name": "reserved0",
+            "type": {
+              "array": [
+                "u8",
+                24
+              ]
+            }
+          }
+        ]
+      }
+    },
+    {
+      "name": "AccountingError",
+      "docs": [
+        "Failures of the stake and reward math, each raised by the program as the `ErrorCode`",
+        "of the same name"
+      ],
+      "type": {
+        "kind": "enum",
+        "variants": [
+          {
+            "name": "MathOverflow"
+          },
+          {
+            "name": "MathUnderflow"
+          },
+          {
+            "name": "PrecisionMath"
+          },
+          {
+            "name": "ConversionFailure"
+          },
+          {
+            "name": "DurationTooShort"
+          },
+          {
+            "name": "InvalidStakePoolDuration"
+          },
+          {
+            "name": "InvalidStakePoolWeight"
+          }
+        ]
+      }
+    }
+  ],
+  "events": [
+    {
+      "name": "StakePoolInitialized",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "creator",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "authority",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "mint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeMint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "vault",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "nonce",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "baseWeight",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "maxWeight",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "minDuration",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "maxDuration",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "options",
+          "type": "u8",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "StakePoolMigrated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "authority",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousLen",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "len",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "RewardPoolAdded",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "index",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "rewardMint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "rewardVault",
+          "type": "publicKey",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "RewardPoolFunded",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "index",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "funder",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "totalFunded",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "rewardsPerEffectiveStake",
+          "type": "u128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "RewardPoolPolicyUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "index",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "policy",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "releasePeriod",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "undistributedRecipient",
+          "type": "publicKey",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "UndistributedSwept",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "index",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "recipient",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "RewardDustSwept",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "index",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "destination",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "FlagsUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousFlags",
+          "type": "u8",
+          "index": false
+        },
+        {
+          "name": "flags",
+          "type": "u8",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "AuthorityTransferred",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousAuthority",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "newAuthority",
+          "type": "publicKey",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "LpMinted",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeMint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "destination",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "mintedUnbacked",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "reason",
+          "type": "string",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "UnbackedLpBurned",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeMint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "from",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "mintedUnbacked",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "reason",
+          "type": "string",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "UnbackedLpCapUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousCap",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "cap",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "UnbackedLpCapAuthorityUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousAuthority",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "newAuthority",
+          "type": "publicKey",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "DepositCapsUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousMaxTotalDeposit",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "maxTotalDeposit",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "previousMaxDepositPerOwner",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "maxDepositPerOwner",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "MinDepositAmountUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousMinDepositAmount",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "minDepositAmount",
+          "type": "u64",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "DepositAllowlistUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "previousRoot",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        },
+        {
+          "name": "root",
+          "type": {
+            "array": [
+              "u8",
+              32
+            ]
+          },
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "TokenMetaUpdated",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeMint",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "metadataAccount",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "name",
+          "type": "string",
+          "index": false
+        },
+        {
+          "name": "symbol",
+          "type": "string",
+          "index": false
+        },
+        {
+          "name": "uri",
+          "type": "string",
+          "index": false
+        },
+        {
+          "name": "created",
+          "type": "bool",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "Deposited",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeDepositReceipt",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "owner",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "payer",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "tokenAuthority",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "weight",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "effectiveStake",
+          "type": "u128",
+          "index": false
+        },
+        {
+          "name": "lockupDuration",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "depositTimestamp",
+          "type": "i64",
+          "index": false
+        },
+        {
+          "name": "lpMinted",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "totalWeightedStake",
+          "type": "u128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "Withdrawn",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeDepositReceipt",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "owner",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "lpAuthority",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "rentRecipient",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "amount",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "effectiveStake",
+          "type": "u128",
+          "index": false
+        },
+        {
+          "name": "lpBurned",
+          "type": "u64",
+          "index": false
+        },
+        {
+          "name": "escapeHatchEnabled",
+          "type": "bool",
+          "index": false
+        },
+        {
+          "name": "totalWeightedStake",
+          "type": "u128",
+          "index": false
+        }
+      ]
+    },
+    {
+      "name": "RewardsClaimed",
+      "fields": [
+        {
+          "name": "stakePool",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "stakeDepositReceipt",
+          "type": "publicKey",
+          "index": false
+        },
+        {
+          "name": "owner",
+          "type": "publicKey",
+          "index": false
+        },
         {
-          "name": "name",
-          "type": "string"
+          "name": "rewardPoolIndex",
+          "type": "u8",
+          "index": false
         },
         {
-          "name": "symbol",
-          "type": "string"
+          "name": "rewardVault",
+          "type": "publicKey",
+          "index": false
         },
         {
-          "name": "uri",
-          "type": "string"
+          "name": "amount",
+          "type": "u64",
+          "index": false
         }
       ]
     }
   ],
-  "accounts": [
-    {
-      "name": "stakePool",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "creator",
-            "type": "publicKey"
-          },
-          {
-            "name": "authority",
-            "type": "publicKey"
-          },
-          {
-            "name": "totalWeightedStake",
-            "type": "u128"
-          },
-          {
-            "name": "vault",
-            "type": "publicKey"
-          },
-          {
-            "name": "mint",
-            "type": "publicKey"
-          },
-          {
-            "name": "stakeMint",
-            "type": "publicKey"
-          },
-          {
-            "name": "rewardPools",
-            "type": {
-              "array": [
-                {
-                  "defined": "RewardPool"
-                },
-                10
-              ]
-            }
-          },
-          {
-            "name": "baseWeight",
-            "type": "u64"
-          },
-          {
-            "name": "maxWeight",
-            "type": "u64"
-          },
-          {
-            "name": "minDuration",
-            "type": "u64"
-          },
-          {
-            "name": "maxDuration",
-            "type": "u64"
-          },
-          {
-            "name": "nonce",
-            "type": "u8"
-          },
-          {
-            "name": "bumpSeed",
-            "type": "u8"
-          },
-          {
-            "name": "flags",
-            "type": "u8"
-          },
-          {
-            "name": "padding0",
-            "type": {
-              "array": [
-                "u8",
-                5
-              ]
-            }
-          },
-          {
-            "name": "reserved0",
-            "type": {
-              "array": [
-                "u8",
-                256
-              ]
-            }
-          }
-        ]
-      }
-    },
-    {
-      "name": "stakeDepositReceipt",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "owner",
-            "type": "publicKey"
-          },
-          {
-            "name": "payer",
-            "type": "publicKey"
-          },
-          {
-            "name": "stakePool",
-            "type": "publicKey"
-          },
-          {
-            "name": "lockupDuration",
-            "type": "u64"
-          },
-          {
-            "name": "depositTimestamp",
-            "type": "i64"
-          },
-          {
-            "name": "depositAmount",
-            "type": "u64"
-          },
-          {
-            "name": "effectiveStake",
-            "type": "u128"
-          },
-          {
-            "name": "claimedAmounts",
-            "type": {
-              "array": [
-                "u128",
-                10
-              ]
-            }
-          }
-        ]
-      }
-    }
-  ],
-  "types": [
-    {
-      "name": "RewardPool",
-      "type": {
-        "kind": "struct",
-        "fields": [
-          {
-            "name": "rewardVault",
-            "type": "publicKey"
-          },
-          {
-            "name": "rewardsPerEffectiveStake",
-            "type": "u128"
-          },
-          {
-            "name": "lastAmount",
-            "type": "u64"
-          },
-          {
-            "name": "padding0",
-            "type": {
-              "array": [
-                "u8",
-                8
-              ]
-            }
-          }
-        ]
-      }
-    }
-  ],
   "errors": [
     {
       "code": 6000,
@@ -665,6 +2289,196 @@ export const _AlrisStakingIDL = {
       "code": 6013,
       "name": "DepositsDisabled",
       "msg": "Deposits disabled by administrator"
+    },
+    {
+      "code": 6014,
+      "name": "InvalidRewardPoolIndex",
+      "msg": "RewardPool index is out of range or empty"
+    },
+    {
+      "code": 6015,
+      "name": "NoStakeToReward",
+      "msg": "Cannot fund rewards while nothing is staked"
+    },
+    {
+      "code": 6016,
+      "name": "InvalidAmount",
+      "msg": "Amount must be greater than zero"
+    },
+    {
+      "code": 6017,
+      "name": "InvalidUndistributedPolicy",
+      "msg": "Unknown undistributed rewards policy"
+    },
+    {
+      "code": 6018,
+      "name": "InvalidUndistributedRecipient",
+      "msg": "Invalid undistributed rewards recipient"
+    },
+    {
+      "code": 6019,
+      "name": "MathOverflow",
+      "msg": "Math overflow"
+    },
+    {
+      "code": 6020,
+      "name": "MathUnderflow",
+      "msg": "Math underflow"
+    },
+    {
+      "code": 6021,
+      "name": "ConversionFailure",
+      "msg": "Number conversion failed"
+    },
+    {
+      "code": 6022,
+      "name": "RewardVaultBalanceDecreased",
+      "msg": "Reward vault balance is below its last recorded amount"
+    },
+    {
+      "code": 6023,
+      "name": "InvalidStakeTokenMint",
+      "msg": "Token account mint does not match the StakePool mint"
+    },
+    {
+      "code": 6024,
+      "name": "InvalidLpTokenMint",
+      "msg": "Token account mint does not match the StakePool lp mint"
+    },
+    {
+      "code": 6025,
+      "name": "InvalidLpTokenOwner",
+      "msg": "Lp token account is not owned by the StakeDepositReceipt owner or LP authority"
+    },
+    {
+      "code": 6026,
+      "name": "InvalidRewardRecipientMint",
+      "msg": "Reward recipient mint does not match the RewardPool vault"
+    },
+    {
+      "code": 6027,
+      "name": "InvalidRewardRecipientOwner",
+      "msg": "Reward recipient is not owned by the StakeDepositReceipt owner"
+    },
+    {
+      "code": 6028,
+      "name": "UnsupportedMintExtension",
+      "msg": "Mint has a token extension the StakePool does not support"
+    },
+    {
+      "code": 6029,
+      "name": "InvalidTokenProgram",
+      "msg": "Token program does not own the mint or token account"
+    },
+    {
+      "code": 6030,
+      "name": "InvalidRewardMint",
+      "msg": "Reward mint does not match the RewardPool vault"
+    },
+    {
+      "code": 6031,
+      "name": "InvalidStakePoolOptions",
+      "msg": "Unknown StakePool option"
+    },
+    {
+      "code": 6032,
+      "name": "InvalidMetadataAccount",
+      "msg": "Metadata program or account does not match the stake mint"
+    },
+    {
+      "code": 6033,
+      "name": "UnbackedLpCapExceeded",
+      "msg": "Minting would exceed the StakePool unbacked LP cap"
+    },
+    {
+      "code": 6034,
+      "name": "UnbackedLpBurnExceedsMinted",
+      "msg": "Burn exceeds the unbacked LP tokens outstanding"
+    },
+    {
+      "code": 6035,
+      "name": "InvalidUnbackedLpReason",
+      "msg": "Reason must be between 1 and 200 bytes"
+    },
+    {
+      "code": 6036,
+      "name": "MissingStakeDepositReceipts",
+      "msg": "No StakeDepositReceipt was passed"
+    },
+    {
+      "code": 6037,
+      "name": "DuplicateStakeDepositReceipt",
+      "msg": "StakeDepositReceipt was passed more than once"
+    },
+    {
+      "code": 6038,
+      "name": "InvalidRentRecipient",
+      "msg": "Rent recipient is not the account the StakeDepositReceipt rent is refunded to"
+    },
+    {
+      "code": 6039,
+      "name": "InvalidDepositSourceOwner",
+      "msg": "Deposit token account is not held by the StakeDepositReceipt owner"
+    },
+    {
+      "code": 6040,
+      "name": "TotalDepositCapExceeded",
+      "msg": "Deposit would exceed the StakePool total deposit cap"
+    },
+    {
+      "code": 6041,
+      "name": "OwnerDepositCapExceeded",
+      "msg": "Deposit would exceed the StakePool per owner deposit cap"
+    },
+    {
+      "code": 6042,
+      "name": "DepositTooSmall",
+      "msg": "Deposit is below the StakePool minimum deposit amount"
+    },
+    {
+      "code": 6043,
+      "name": "ZeroLpDeposit",
+      "msg": "Deposit is too small to mint any LP tokens"
+    },
+    {
+      "code": 6044,
+      "name": "DepositNotAllowlisted",
+      "msg": "Owner is not in the StakePool deposit allowlist"
+    },
+    {
+      "code": 6045,
+      "name": "MissingDepositAllowance",
+      "msg": "DepositAllowance is required while the StakePool has a deposit allowlist"
+    },
+    {
+      "code": 6046,
+      "name": "DepositAllowanceExceeded",
+      "msg": "Deposit would exceed the owner's allowlisted amount"
+    },
+    {
+      "code": 6047,
+      "name": "StakePoolAlreadyMigrated",
+      "msg": "StakePool is not in the legacy layout"
+    },
+    {
+      "code": 6048,
+      "name": "UserStakeIndexFull",
+      "msg": "UserStakeIndex holds as many receipts as it can list"
+    },
+    {
+      "code": 6049,
+      "name": "MissingOwnerSignature",
+      "msg": "Owner must sign deposits while the StakePool has a deposit allowlist"
+    },
+    {
+      "code": 6050,
+      "name": "MissingOwnerAuthorization",
+      "msg": "Owner must authorize deposits while the StakePool has a per owner deposit cap"
+    },
+    {
+      "code": 6051,
+      "name": "InvalidUnbackedLpCapAuthority",
+      "msg": "Signer is not the StakePool's unbacked LP cap authority"
     }
   ]
 } as const;
@@ -1,9 +1,12 @@
-import { AnchorProvider, BN, IdlAccounts, utils } from "@coral-xyz/anchor";
+import { AnchorProvider, BN, IdlAccounts } from "@coral-xyz/anchor";
 import { Program, web3 } from "@coral-xyz/anchor";
-import { TOKEN_PROGRAM_ID } from "@coral-xyz/anchor/dist/cjs/utils/token";
 import {
   createAssociatedTokenAccountInstruction,
   createMintToInstruction,
+  getAssociatedTokenAddressSync,
+  TOKEN_2022_PROGRAM_ID,
+  TOKEN_PROGRAM_ID,
+  unpackAccount,
 } from "@solana/spl-token";
 import { AlrisStaking, _AlrisStakingIDL } from "./idl";
 import {
@@ -13,8 +16,10 @@ import {
   METADATA_PROGRAM_ID,
   REWARD_MINT,
   SCALE_FACTOR_BASE_SQUARED,
+  SOULBOUND_STAKE_MINT,
 } from "./constants";
 import {
+  AllowlistProof,
   StakeDepositReceipt,
   StakeDepositReceiptData,
   StakePool,
@@ -108,10 +113,101 @@ export class AlrisStakingProgram {
     )[0];
   }
 
-  getAta(mint: web3.PublicKey, owner: web3.PublicKey): web3.PublicKey {
-    return utils.token.associatedAddress({
-      mint,
-      owner,
+  get userStakeIndexPda(): web3.PublicKey {
+    return web3.PublicKey.findProgramAddressSync(
+      [
+        this.wallet.toBuffer(),
+        this.stakePoolPda.toBuffer(),
+        Buffer.from("userStakeIndex", "utf-8"),
+      ],
+      this.program.programId
+    )[0];
+  }
+  get depositAllowancePda(): web3.PublicKey {
+    return web3.PublicKey.findProgramAddressSync(
+      [
+        this.wallet.toBuffer(),
+        this.stakePoolPda.toBuffer(),
+        Buffer.from("depositAllowance", "utf-8"),
+      ],
+      this.program.programId
+    )[0];
+  }
+
+  getAta(
+    mint: web3.PublicKey,
+    owner: web3.PublicKey,
+    tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+  ): web3.PublicKey {
+    return getAssociatedTokenAddressSync(mint, owner, false, tokenProgram);
+  }
+  /**
+   * Token program of the LP mint, Token-2022 for a soulbound LP mint
+   */
+  getStakeTokenProgram(stakePool: StakePool): web3.PublicKey {
+    return (stakePool.options & SOULBOUND_STAKE_MINT) !== 0
+      ? TOKEN_2022_PROGRAM_ID
+      : TOKEN_PROGRAM_ID;
+  }
+  /**
+   * Where `withdraw` refunds the receipt's rent
+   */
+  getRentRecipient(
+    stakePool: StakePool,
+    stakeDepositReceipt: StakeDepositReceipt
+  ): web3.PublicKey {
+    return (stakePool.flags & StakePoolFlags.REFUND_RENT_TO_OWNER) !== 0
+      ? stakeDepositReceipt.owner
+      : stakeDepositReceipt.payer;
+  }
+  /**
+   * Reward vault of each active reward pool, in pool order
+   */
+  getRewardVaults(stakePool: StakePool): web3.PublicKey[] {
+    return stakePool.rewardPools
+      .map((rewardPool) => rewardPool.rewardVault)
+      .filter((rewardVault) => !rewardVault.equals(web3.PublicKey.default));
+  }
+  /**
+   * Reward vaults as passed to `deposit`
+   */
+  getRewardVaultAccounts(stakePool: StakePool): web3.AccountMeta[] {
+    return this.getRewardVaults(stakePool).map((rewardVault) => ({
+      isSigner: false,
+      isWritable: false,
+      pubkey: rewardVault,
+    }));
+  }
+  /**
+   * Reward vault, the wallet's token account of the reward mint, the reward
+   * mint and its token program for each active reward pool, in pool order, as
+   * paid out by `claimAll` and `withdraw`. The wallet's reward token accounts
+   * must exist.
+   */
+  async getRewardPayoutAccounts(
+    stakePool: StakePool
+  ): Promise<web3.AccountMeta[]> {
+    const rewardVaults = this.getRewardVaults(stakePool);
+    const rewardVaultInfos =
+      await this.program.provider.connection.getMultipleAccountsInfo(
+        rewardVaults
+      );
+    return rewardVaults.flatMap((rewardVault, index) => {
+      const info = rewardVaultInfos[index];
+      if (!info) {
+        throw new Error(`Reward vault ${rewardVault.toBase58()} not found`);
+      }
+      const { mint } = unpackAccount(rewardVault, info, info.owner);
+      return [
+        { isSigner: false, isWritable: true, pubkey: rewardVault },
+        {
+          isSigner: false,
+          isWritable: true,
+          pubkey: this.getAta(mint, this.wallet, info.owner),
+        },
+        { isSigner: false, isWritable: false, pubkey: mint },
+        { isSigner: false, isWritable: false, pubkey: info.owner },
+      ];
     });
   }
   getStakeDepositReceiptPda(nonce: number): web3.PublicKey {
@@ -205,11 +301,18 @@ export class AlrisStakingProgram {
     }
     throw new Error("No more nonces available");
   }
-  async getOrCreateTokenAccountInstruction(mint: web3.PublicKey): Promise<{
+  async getOrCreateTokenAccountInstruction(
+    mint: web3.PublicKey,
+    tokenProgram: web3.PublicKey = TOKEN_PROGRAM_ID
+  ): Promise<{
     instruction: web3.TransactionInstruction | null;
     ata: web3.PublicKey;
   }> {
-    const userTokenAccountAddress = this.getAta(mint, this.wallet);
+    const userTokenAccountAddress = this.getAta(
+      mint,
+      this.wallet,
+      tokenProgram
+    );
     const userTokenAccount =
       await this.program.provider.connection.getParsedAccountInfo(
         userTokenAccountAddress
@@ -220,7 +323,8 @@ export class AlrisStakingProgram {
           this.wallet,
           userTokenAccountAddress,
           this.wallet,
-          mint
+          mint,
+          tokenProgram
         ),
         ata: userTokenAccountAddress,
       };
@@ -269,7 +373,7 @@ export class AlrisStakingProgram {
     tx_hash: string;
   }> {
     let intialize_stake_pool_ix = await this.program.methods
-      .initializeStakePool(1, maxWeight, minDuration, maxDuration)
+      .initializeStakePool(1, maxWeight, minDuration, maxDuration, 0)
       .accounts({
         authority: ADMIN_PUBLIC_KEY,
         mint: ALR_MINT,
@@ -277,6 +381,7 @@ export class AlrisStakingProgram {
         stakeMint: this.stakeMint,
         vault: this.vaultPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        stakeTokenProgram: TOKEN_PROGRAM_ID,
         rent: web3.SYSVAR_RENT_PUBKEY,
         payer: this.wallet,
         systemProgram: web3.SystemProgram.programId,
@@ -315,20 +420,31 @@ export class AlrisStakingProgram {
       tx_hash,
     };
   }
+  /**
+   * Stake `amount` ALR from the wallet for `lockupDuration` seconds
+   * @param allowlistProof Proof that the wallet is in the deposit allowlist,
+   * required while the StakePool has one
+   */
   async deposit(
     amount: BN,
-    lockupDuration: BN
+    lockupDuration: BN,
+    allowlistProof: AllowlistProof | null = null
   ): Promise<{
     tx_hash: string;
     stakeDepositReceipt: StakeDepositReceipt;
     stakePool: StakePool;
   }> {
+    const stakePool = await this.getStakePool();
+    const stakeTokenProgram = this.getStakeTokenProgram(stakePool);
     const userALRMintTokenAccount = this.getAta(ALR_MINT, this.wallet);
 
     const {
       ata: userStakeMintTokenAccount,
       instruction: userStakeMintTokenAccountInstruction,
-    } = await this.getOrCreateTokenAccountInstruction(this.stakeMint);
+    } = await this.getOrCreateTokenAccountInstruction(
+      this.stakeMint,
+      stakeTokenProgram
+    );
 
     const nonce = await this.getNextUnusedStakeReceiptNonce(this.wallet);
 
@@ -337,27 +453,27 @@ export class AlrisStakingProgram {
       throw new Error("Amount must be greater than 0");
     }
     let txBuilder = this.program.methods
-      .deposit(nonce, amount, lockupDuration)
+      .deposit(nonce, amount, lockupDuration, allowlistProof)
       .accounts({
-        stakePool: this.stakePoolPda,
-        destination: userStakeMintTokenAccount,
-        from: userALRMintTokenAccount, // User should already have ALR in their wallet
-        owner: this.wallet,
         payer: this.wallet,
+        owner: this.wallet,
+        from: userALRMintTokenAccount, // User should already have ALR in their wallet
+        vault: this.vaultPda,
+        mint: ALR_MINT,
         stakeMint: this.stakeMint,
-        rent: web3.SYSVAR_RENT_PUBKEY,
+        destination: userStakeMintTokenAccount,
+        stakePool: this.stakePoolPda,
         stakeDepositReceipt: stakeDepositReceiptPda,
-        systemProgram: web3.SystemProgram.programId,
+        userStakeIndex: this.userStakeIndexPda,
         tokenProgram: TOKEN_PROGRAM_ID,
-        vault: this.vaultPda,
+        stakeTokenProgram,
+        rent: web3.SYSVAR_RENT_PUBKEY,
+        systemProgram: web3.SystemProgram.programId,
+        // the wallet signs as payer, so it transfers its own ALR
+        tokenAuthority: null,
+        depositAllowance: allowlistProof ? this.depositAllowancePda : null,
       })
-      .remainingAccounts([
-        {
-          isSigner: false,
-          isWritable: true,
-          pubkey: this.rewardVaultPda,
-        },
-      ]);
+      .remainingAccounts(this.getRewardVaultAccounts(stakePool));
 
     if (userStakeMintTokenAccountInstruction) {
       txBuilder = txBuilder.preInstructions([
@@ -371,18 +487,20 @@ export class AlrisStakingProgram {
       await this.program.account.stakeDepositReceipt.fetch(
         stakeDepositReceiptPda
       );
-    const stakePool = await this.program.account.stakePool.fetch(
-      this.stakePoolPda
-    );
     return {
       tx_hash: tx,
       stakeDepositReceipt,
-      stakePool,
+      stakePool: await this.getStakePool(),
     };
   }
   async withdraw(stakeDepositReceipt: web3.PublicKey): Promise<{
     tx_hash: string;
   }> {
+    const stakePool = await this.getStakePool();
+    const stakeTokenProgram = this.getStakeTokenProgram(stakePool);
+    const receipt = await this.program.account.stakeDepositReceipt.fetch(
+      stakeDepositReceipt
+    );
     const tx = await this.program.methods
       .withdraw()
       .accounts({
@@ -392,23 +510,17 @@ export class AlrisStakingProgram {
           stakePool: this.stakePoolPda,
           tokenProgram: TOKEN_PROGRAM_ID,
         },
-        stakeMint: this.stakeMint,
         vault: this.vaultPda,
+        mint: ALR_MINT,
+        stakeMint: this.stakeMint,
+        from: this.getAta(this.stakeMint, this.wallet, stakeTokenProgram),
         destination: this.getAta(ALR_MINT, this.wallet),
-        from: this.getAta(this.stakeMint, this.wallet),
+        stakeTokenProgram,
+        userStakeIndex: this.userStakeIndexPda,
+        rentRecipient: this.getRentRecipient(stakePool, receipt),
+        lpAuthority: null,
       })
-      .remainingAccounts([
-        {
-          isSigner: false,
-          isWritable: true,
-          pubkey: this.rewardVaultPda,
-        },
-        {
-          isSigner: false,
-          isWritable: true,
-          pubkey: this.userRewardMintAta,
-        },
-      ])
+      .remainingAccounts(await this.getRewardPayoutAccounts(stakePool))
       .rpc();
     return {
       tx_hash: tx,
@@ -418,6 +530,7 @@ export class AlrisStakingProgram {
     tx_hash: string;
     stakeDepositReceipt: StakeDepositReceipt;
   }> {
+    const stakePool = await this.getStakePool();
     const tx = await this.program.methods
       .claimAll()
       .accounts({
@@ -428,18 +541,7 @@ export class AlrisStakingProgram {
           stakeDepositReceipt,
         },
       })
-      .remainingAccounts([
-        {
-          isSigner: false,
-          isWritable: true,
-          pubkey: this.rewardVaultPda,
-        },
-        {
-          isSigner: false,
-          isWritable: true,
-          pubkey: this.userRewardMintAta,
-        },
-      ])
+      .remainingAccounts(await this.getRewardPayoutAccounts(stakePool))
       .rpc();
     const stakeDepositReceiptData =
       await this.program.account.stakeDepositReceipt.fetch(stakeDepositReceipt);
@@ -509,6 +611,8 @@ export class AlrisStakingProgram {
           (newFlags & StakePoolFlags.DEPOSIT_IGNORES_LP) !== 0,
         WITHDRAW_IGNORES_LP:
           (newFlags & StakePoolFlags.WITHDRAW_IGNORES_LP) !== 0,
+        REFUND_RENT_TO_OWNER:
+          (newFlags & StakePoolFlags.REFUND_RENT_TO_OWNER) !== 0,
       },
     };
   }
@@ -532,6 +636,8 @@ export class AlrisStakingProgram {
           (pool.flags & StakePoolFlags.DEPOSIT_IGNORES_LP) !== 0,
         WITHDRAW_IGNORES_LP:
           (pool.flags & StakePoolFlags.WITHDRAW_IGNORES_LP) !== 0,
+        REFUND_RENT_TO_OWNER:
+          (pool.flags & StakePoolFlags.REFUND_RENT_TO_OWNER) !== 0,
       },
     };
   }
@@ -650,4 +756,5 @@ export enum StakePoolFlags {
   DISABLE_DEPOSITS = 4,
   DEPOSIT_IGNORES_LP = 8,
   WITHDRAW_IGNORES_LP = 16,
+  REFUND_RENT_TO_OWNER = 32,
 }
@@ -1,4 +1,4 @@
-import { IdlAccounts, web3 } from "@coral-xyz/anchor";
+import { IdlAccounts, IdlTypes, web3 } from "@coral-xyz/anchor";
 import { AlrisStaking } from "./idl";
 
 export type StakePool = IdlAccounts<AlrisStaking>["stakePool"];
@@ -7,3 +7,4 @@ export type StakeDepositReceipt =
 export type StakeDepositReceiptData = StakeDepositReceipt & {
   address: web3.PublicKey;
 };
+export type AllowlistProof = IdlTypes<AlrisStaking>["AllowlistProof"];
@@ -0,0 +1,89 @@
+//! Deserializers for the program's accounts as returned by RPC.
+
+use alris_staking::state::{
+    DepositAllowance, RewardPool, StakeDepositReceipt, StakePool, UserStakeIndex,
+};
+use anchor_lang::{error::ErrorCode, prelude::*, Discriminator};
+
+/// Decodes a `StakePool` from raw account data. RPC buffers carry no alignment guarantee, so
+/// the zero copy struct is read unaligned.
+pub fn deserialize_stake_pool(data: &[u8]) -> Result<StakePool> {
+    if data.len() < 8 {
+        return err!(ErrorCode::AccountDiscriminatorNotFound);
+    }
+    if data[..8] != StakePool::DISCRIMINATOR {
+        return err!(ErrorCode::AccountDiscriminatorMismatch);
+    }
+    let body = data
+        .get(8..8 + StakePool::LEN)
+        .ok_or(ErrorCode::AccountDidNotDeserialize)?;
+    Ok(bytemuck::pod_read_unaligned(body))
+}
+
+/// Decodes a `StakeDepositReceipt` from raw account data.
+pub fn deserialize_stake_deposit_receipt(data: &[u8]) -> Result<StakeDepositReceipt> {
+    StakeDepositReceipt::try_deserialize(&mut &data[..])
+}
+
+/// Decodes a `UserStakeIndex` from raw account data.
+pub fn deserialize_user_stake_index(data: &[u8]) -> Result<UserStakeIndex> {
+    UserStakeIndex::try_deserialize(&mut &data[..])
+}
+
+/// Decodes a `DepositAllowance` from raw account data.
+pub fn deserialize_deposit_allowance(data: &[u8]) -> Result<DepositAllowance> {
+    DepositAllowance::try_deserialize(&mut &data[..])
+}
+
+/// Reward vaults of the pool's active reward pools, in the order the program expects them in
+/// remaining accounts.
+pub fn reward_vaults(stake_pool: &StakePool) -> Vec<Pubkey> {
+    active_reward_pools(stake_pool)
+        .map(|(_, reward_pool)| reward_pool.reward_vault)
+        .collect()
+}
+
+/// Active reward pools along with their index in `StakePool::reward_pools`.
+pub fn active_reward_pools(
+    stake_pool: &StakePool,
+) -> impl Iterator<Item = (usize, &RewardPool)> + '_ {
+    stake_pool
+        .reward_pools
+        .iter()
+        .enumerate()
+        .filter(|(_, reward_pool)| !reward_pool.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use bytemuck::Zeroable;
+
+    use super::*;
+
+    #[test]
+    fn deserialize_stake_pool_unaligned() {
+        let mut pool = StakePool::zeroed();
+        pool.authority = Pubkey::new_unique();
+        pool.max_weight = 42;
+        pool.reward_pools[2] = RewardPool::new(&Pubkey::new_unique());
+
+        // offset by one byte so the body is not 8-byte aligned
+        let mut data = vec![0u8];
+        data.extend_from_slice(&StakePool::DISCRIMINATOR);
+        data.extend_from_slice(bytemuck::bytes_of(&pool));
+
+        let decoded = deserialize_stake_pool(&data[1..]).unwrap();
+        assert_eq!(decoded.authority, pool.authority);
+        assert_eq!(decoded.max_weight, 42);
+        assert_eq!(reward_vaults(&decoded), [pool.reward_pools[2].reward_vault]);
+    }
+
+    #[test]
+    fn deserialize_stake_pool_wrong_discriminator() {
+        let data = [0u8; 8 + StakePool::LEN];
+        assert_eq!(
+            deserialize_stake_pool(&data).err(),
+            Some(ErrorCode::AccountDiscriminatorMismatch.into())
+        );
+    }
+}
@@ -0,0 +1,82 @@
+//! Deposit allowlists built off-chain: the merkle root the authority sets with
+//! `set_deposit_allowlist` and the proof each owner passes to `deposit`.
+
+use alris_staking::allowlist::{allowlist_leaf, allowlist_node, AllowlistProof};
+use anchor_lang::prelude::Pubkey;
+
+/// Merkle tree over `(owner, max_amount)` leaves in the order given, `max_amount` zero for
+/// no limit. A node without a sibling is carried up a level as is.
+pub struct Allowlist {
+    entries: Vec<(Pubkey, u64)>,
+    layers: Vec<Vec<[u8; 32]>>,
+}
+
+impl Allowlist {
+    pub fn new(entries: Vec<(Pubkey, u64)>) -> Self {
+        let mut layers = vec![entries
+            .iter()
+            .map(|(owner, max_amount)| allowlist_leaf(owner, *max_amount))
+            .collect::<Vec<_>>()];
+        while let Some(layer) = layers.last().filter(|layer| layer.len() > 1) {
+            let parents = layer
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => allowlist_node(left, right),
+                    [node] => *node,
+                    _ => unreachable!(),
+                })
+                .collect();
+            layers.push(parents);
+        }
+        Self { entries, layers }
+    }
+
+    /// All zero for an empty allowlist, which the program reads as no allowlist at all
+    pub fn root(&self) -> [u8; 32] {
+        self.layers
+            .last()
+            .and_then(|layer| layer.first())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Proof of the first entry of `owner`, `None` when it is not listed
+    pub fn proof(&self, owner: &Pubkey) -> Option<AllowlistProof> {
+        let mut position = self.entries.iter().position(|(entry, _)| entry == owner)?;
+        let max_amount = self.entries[position].1;
+        let mut proof = Vec::new();
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(position ^ 1) {
+                proof.push(*sibling);
+            }
+            position /= 2;
+        }
+        Some(AllowlistProof { max_amount, proof })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn every_entry_proves_the_root() {
+        for len in 1..=9u64 {
+            let entries: Vec<(Pubkey, u64)> = (0..len)
+                .map(|max_amount| (Pubkey::new_unique(), max_amount))
+                .collect();
+            let allowlist = Allowlist::new(entries.clone());
+            for (owner, max_amount) in &entries {
+                let proof = allowlist.proof(owner).unwrap();
+                assert_eq!(proof.max_amount, *max_amount);
+                assert_eq!(proof.root(owner), allowlist.root());
+            }
+            assert!(allowlist.proof(&Pubkey::new_unique()).is_none());
+        }
+    }
+
+    #[test]
+    fn empty_allowlist_has_zero_root() {
+        assert_eq!(Allowlist::new(Vec::new()).root(), [0; 32]);
+    }
+}
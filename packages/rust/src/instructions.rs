@@ -0,0 +1,835 @@
+//! Instruction builders. Builders taking a `StakePool` read the vault, stake mint and reward
+//! vaults from it and append the remaining accounts in the order the program walks them.
+//! The pool does not record token programs, so those are passed in: `token_program` is the
+//! program of the StakePool mint, which also owns its vault. The LP mint is owned by
+//! `StakePool::stake_token_program`.
+
+use alris_staking::{
+    accounts,
+    allowlist::AllowlistProof,
+    instruction,
+    state::{StakePool, MAX_REWARD_POOLS, SOULBOUND_STAKE_MINT},
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{instruction::Instruction, sysvar},
+    system_program, InstructionData,
+};
+use anchor_spl::{metadata::mpl_token_metadata, token_2022};
+
+use crate::{
+    accounts::reward_vaults,
+    pda::{
+        find_deposit_allowance_address, find_reward_vault_address,
+        find_stake_deposit_receipt_address, find_stake_mint_address,
+        find_stake_mint_metadata_address, find_stake_pool_address, find_user_stake_index_address,
+        find_vault_address,
+    },
+    PROGRAM_ID,
+};
+
+fn build(accounts: impl ToAccountMetas, data: impl InstructionData) -> Instruction {
+    Instruction {
+        program_id: PROGRAM_ID,
+        accounts: accounts.to_account_metas(None),
+        data: data.data(),
+    }
+}
+
+/// Reward vaults only, as read by `deposit`
+fn reward_vault_metas(stake_pool: &StakePool) -> Vec<AccountMeta> {
+    reward_vaults(stake_pool)
+        .into_iter()
+        .map(|reward_vault| AccountMeta::new_readonly(reward_vault, false))
+        .collect()
+}
+
+/// Mint of a reward pool's vault and the token program owning both
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct RewardMint {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Reward accounts that do not line up with the pool's active reward pools. The program would
+/// fail on the shorter account list, so the builders refuse to lay it out.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum RewardAccountsError {
+    RewardMintCount { expected: usize, actual: usize },
+    RewardRecipientCount { expected: usize, actual: usize },
+}
+
+impl std::fmt::Display for RewardAccountsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::RewardMintCount { expected, actual } => write!(
+                f,
+                "expected one reward mint per active reward pool, {expected}, got {actual}"
+            ),
+            Self::RewardRecipientCount { expected, actual } => write!(
+                f,
+                "expected one reward recipient per active reward pool, {expected}, got {actual}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for RewardAccountsError {}
+
+/// Reward vault, recipient, reward mint and token program of each active reward pool, as paid
+/// out by `claim_all` and `withdraw`. Reward mints and recipients are matched to the pool's
+/// active reward pools in order, one each.
+fn reward_payout_metas(
+    stake_pool: &StakePool,
+    reward_mints: &[RewardMint],
+    reward_recipients: &[Pubkey],
+) -> std::result::Result<Vec<AccountMeta>, RewardAccountsError> {
+    let reward_vaults = reward_vaults(stake_pool);
+    if reward_mints.len() != reward_vaults.len() {
+        return Err(RewardAccountsError::RewardMintCount {
+            expected: reward_vaults.len(),
+            actual: reward_mints.len(),
+        });
+    }
+    if reward_recipients.len() != reward_vaults.len() {
+        return Err(RewardAccountsError::RewardRecipientCount {
+            expected: reward_vaults.len(),
+            actual: reward_recipients.len(),
+        });
+    }
+    Ok(reward_vaults
+        .into_iter()
+        .zip(reward_mints)
+        .zip(reward_recipients)
+        .flat_map(|((reward_vault, reward_mint), recipient)| {
+            [
+                AccountMeta::new(reward_vault, false),
+                AccountMeta::new(*recipient, false),
+                AccountMeta::new_readonly(reward_mint.mint, false),
+                AccountMeta::new_readonly(reward_mint.token_program, false),
+            ]
+        })
+        .collect())
+}
+
+/// Receipts passed after the reward accounts to `claim_many` and `withdraw_many`
+fn receipt_metas(stake_deposit_receipts: &[Pubkey]) -> impl Iterator<Item = AccountMeta> + '_ {
+    stake_deposit_receipts
+        .iter()
+        .map(|receipt| AccountMeta::new(*receipt, false))
+}
+
+pub struct InitializeStakePoolArgs {
+    pub nonce: u8,
+    pub max_weight: u64,
+    pub min_duration: u64,
+    pub max_duration: u64,
+    /// `SOULBOUND_STAKE_MINT` or 0
+    pub options: u8,
+}
+
+pub fn initialize_stake_pool(
+    payer: &Pubkey,
+    authority: &Pubkey,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    args: InitializeStakePoolArgs,
+) -> Instruction {
+    let (stake_pool, _) = find_stake_pool_address(args.nonce, mint, authority);
+    let stake_token_program = if args.options & SOULBOUND_STAKE_MINT != 0 {
+        token_2022::ID
+    } else {
+        *token_program
+    };
+    build(
+        accounts::InitializeStakePool {
+            payer: *payer,
+            authority: *authority,
+            mint: *mint,
+            stake_pool,
+            stake_mint: find_stake_mint_address(&stake_pool).0,
+            vault: find_vault_address(&stake_pool).0,
+            token_program: *token_program,
+            stake_token_program,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::InitializeStakePool {
+            nonce: args.nonce,
+            max_weight: args.max_weight,
+            min_duration: args.min_duration,
+            max_duration: args.max_duration,
+            options: args.options,
+        },
+    )
+}
+
+/// Grows a StakePool still in the `LegacyStakePool` layout, `payer` covering the rent
+pub fn migrate_stake_pool(stake_pool: &Pubkey, payer: &Pubkey, authority: &Pubkey) -> Instruction {
+    build(
+        accounts::MigrateStakePool {
+            payer: *payer,
+            authority: *authority,
+            stake_pool: *stake_pool,
+            vault: find_vault_address(stake_pool).0,
+            system_program: system_program::ID,
+        },
+        instruction::MigrateStakePool {},
+    )
+}
+
+pub fn transfer_authority(
+    stake_pool: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::TransferAuthority {
+            authority: *authority,
+            new_authority: *new_authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::TransferAuthority {},
+    )
+}
+
+pub fn set_flags(stake_pool: &Pubkey, authority: &Pubkey, flags: u8) -> Instruction {
+    build(
+        accounts::SetFlags {
+            authority: *authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::SetFlags { flags },
+    )
+}
+
+/// `authority` is the pool authority to lower the cap, its `unbacked_lp_cap_authority` to
+/// raise it
+pub fn set_unbacked_lp_cap(stake_pool: &Pubkey, authority: &Pubkey, cap: u64) -> Instruction {
+    build(
+        accounts::SetUnbackedLpCap {
+            authority: *authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::SetUnbackedLpCap { cap },
+    )
+}
+
+/// `authority` is the pool authority while no `unbacked_lp_cap_authority` is named, the
+/// current one after
+pub fn set_unbacked_lp_cap_authority(
+    stake_pool: &Pubkey,
+    authority: &Pubkey,
+    new_authority: &Pubkey,
+) -> Instruction {
+    build(
+        accounts::SetUnbackedLpCapAuthority {
+            authority: *authority,
+            new_authority: *new_authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::SetUnbackedLpCapAuthority {},
+    )
+}
+
+/// Zero lifts either cap
+pub fn set_deposit_caps(
+    stake_pool: &Pubkey,
+    authority: &Pubkey,
+    max_total_deposit: u64,
+    max_deposit_per_owner: u64,
+) -> Instruction {
+    build(
+        accounts::SetDepositCaps {
+            authority: *authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::SetDepositCaps {
+            max_total_deposit,
+            max_deposit_per_owner,
+        },
+    )
+}
+
+/// Zero lifts the minimum
+pub fn set_min_deposit_amount(
+    stake_pool: &Pubkey,
+    authority: &Pubkey,
+    min_deposit_amount: u64,
+) -> Instruction {
+    build(
+        accounts::SetMinDepositAmount {
+            authority: *authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::SetMinDepositAmount { min_deposit_amount },
+    )
+}
+
+/// An all zero `root` lifts the allowlist, see `crate::allowlist::Allowlist::root`
+pub fn set_deposit_allowlist(
+    stake_pool: &Pubkey,
+    authority: &Pubkey,
+    root: [u8; 32],
+) -> Instruction {
+    build(
+        accounts::SetDepositAllowlist {
+            authority: *authority,
+            stake_pool: *stake_pool,
+        },
+        instruction::SetDepositAllowlist { root },
+    )
+}
+
+/// Mints LP tokens backed by no receipt, within the pool's `unbacked_lp_cap`. `reason` is
+/// recorded in the event log.
+pub fn dangerously_mint_lp(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    payer: &Pubkey,
+    destination: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+    reason: String,
+) -> Instruction {
+    build(
+        accounts::DangerouslyMintLp {
+            payer: *payer,
+            authority: pool.authority,
+            stake_mint: pool.stake_mint,
+            destination: *destination,
+            stake_pool: *stake_pool,
+            token_program: pool.stake_token_program(token_program),
+        },
+        instruction::DangerouslyMintLp { amount, reason },
+    )
+}
+
+/// Burns unbacked LP tokens from `from`, held by the pool authority
+pub fn burn_unbacked_lp(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    from: &Pubkey,
+    token_program: &Pubkey,
+    amount: u64,
+    reason: String,
+) -> Instruction {
+    build(
+        accounts::BurnUnbackedLp {
+            authority: pool.authority,
+            stake_mint: pool.stake_mint,
+            from: *from,
+            stake_pool: *stake_pool,
+            token_program: pool.stake_token_program(token_program),
+        },
+        instruction::BurnUnbackedLp { amount, reason },
+    )
+}
+
+pub fn add_reward_pool(
+    stake_pool: &Pubkey,
+    payer: &Pubkey,
+    authority: &Pubkey,
+    reward_mint: &RewardMint,
+    index: u8,
+) -> Instruction {
+    build(
+        accounts::AddRewardPool {
+            payer: *payer,
+            authority: *authority,
+            reward_mint: reward_mint.mint,
+            stake_pool: *stake_pool,
+            reward_vault: find_reward_vault_address(stake_pool, &reward_mint.mint).0,
+            token_program: reward_mint.token_program,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::AddRewardPool { index },
+    )
+}
+
+pub fn fund_reward_pool(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    funder: &Pubkey,
+    from: &Pubkey,
+    reward_mint: &RewardMint,
+    index: u8,
+    amount: u64,
+) -> Instruction {
+    build(
+        accounts::FundRewardPool {
+            funder: *funder,
+            from: *from,
+            stake_pool: *stake_pool,
+            reward_vault: pool.reward_pools[usize::from(index)].reward_vault,
+            reward_mint: reward_mint.mint,
+            token_program: reward_mint.token_program,
+        },
+        instruction::FundRewardPool { index, amount },
+    )
+}
+
+pub fn set_reward_pool_policy(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    undistributed_recipient: Option<&Pubkey>,
+    index: u8,
+    policy: u8,
+    release_period: u64,
+) -> Instruction {
+    build(
+        accounts::SetRewardPoolPolicy {
+            authority: pool.authority,
+            stake_pool: *stake_pool,
+            reward_vault: pool.reward_pools[usize::from(index)].reward_vault,
+            undistributed_recipient: undistributed_recipient.copied(),
+        },
+        instruction::SetRewardPoolPolicy {
+            index,
+            policy,
+            release_period,
+        },
+    )
+}
+
+pub fn sweep_undistributed(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    reward_mint: &RewardMint,
+    index: u8,
+) -> Instruction {
+    let reward_pool = &pool.reward_pools[usize::from(index)];
+    build(
+        accounts::SweepUndistributed {
+            authority: pool.authority,
+            stake_pool: *stake_pool,
+            reward_vault: reward_pool.reward_vault,
+            undistributed_recipient: reward_pool.undistributed_recipient,
+            reward_mint: reward_mint.mint,
+            token_program: reward_mint.token_program,
+        },
+        instruction::SweepUndistributed { index },
+    )
+}
+
+pub fn sweep_reward_dust(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    destination: &Pubkey,
+    reward_mint: &RewardMint,
+    index: u8,
+) -> Instruction {
+    build(
+        accounts::SweepRewardDust {
+            authority: pool.authority,
+            stake_pool: *stake_pool,
+            reward_vault: pool.reward_pools[usize::from(index)].reward_vault,
+            destination: *destination,
+            reward_mint: reward_mint.mint,
+            token_program: reward_mint.token_program,
+        },
+        instruction::SweepRewardDust { index },
+    )
+}
+
+pub struct DepositArgs {
+    pub nonce: u32,
+    pub amount: u64,
+    pub lockup_duration: u64,
+    /// The owner's proof into the pool's deposit allowlist, see `crate::allowlist`. Passing
+    /// one also passes the owner's `DepositAllowance`.
+    pub allowlist_proof: Option<AllowlistProof>,
+}
+
+/// `from` is debited with `payer` as its authority and the LP tokens are minted to
+/// `destination`, which must be owned by `owner`. With `token_authority`, that signer
+/// debits `from` instead, as `owner` or as its delegate, and `payer` only covers fees and
+/// rent.
+#[allow(clippy::too_many_arguments)]
+pub fn deposit(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    payer: &Pubkey,
+    owner: &Pubkey,
+    token_authority: Option<&Pubkey>,
+    from: &Pubkey,
+    destination: &Pubkey,
+    token_program: &Pubkey,
+    args: DepositArgs,
+) -> Instruction {
+    let mut ix = build(
+        accounts::Deposit {
+            payer: *payer,
+            owner: *owner,
+            from: *from,
+            vault: pool.vault,
+            mint: pool.mint,
+            stake_mint: pool.stake_mint,
+            destination: *destination,
+            stake_pool: *stake_pool,
+            stake_deposit_receipt: find_stake_deposit_receipt_address(
+                owner, stake_pool, args.nonce,
+            )
+            .0,
+            user_stake_index: find_user_stake_index_address(owner, stake_pool).0,
+            token_program: *token_program,
+            stake_token_program: pool.stake_token_program(token_program),
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+            token_authority: token_authority.copied(),
+            deposit_allowance: args
+                .allowlist_proof
+                .as_ref()
+                .map(|_| find_deposit_allowance_address(owner, stake_pool).0),
+        },
+        instruction::Deposit {
+            nonce: args.nonce,
+            amount: args.amount,
+            lockup_duration: args.lockup_duration,
+            allowlist_proof: args.allowlist_proof,
+        },
+    );
+    ix.accounts.extend(reward_vault_metas(pool));
+    ix
+}
+
+fn claim_base(
+    stake_pool: &Pubkey,
+    owner: &Pubkey,
+    stake_deposit_receipt: &Pubkey,
+    token_program: &Pubkey,
+) -> accounts::ClaimBase {
+    accounts::ClaimBase {
+        owner: *owner,
+        stake_pool: *stake_pool,
+        stake_deposit_receipt: *stake_deposit_receipt,
+        token_program: *token_program,
+    }
+}
+
+/// `reward_mints` and `reward_recipients` hold one entry per active reward pool, in pool
+/// order, else the builder returns a `RewardAccountsError`.
+pub fn claim_all(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    owner: &Pubkey,
+    stake_deposit_receipt: &Pubkey,
+    token_program: &Pubkey,
+    reward_mints: &[RewardMint],
+    reward_recipients: &[Pubkey],
+) -> std::result::Result<Instruction, RewardAccountsError> {
+    let mut ix = build(
+        accounts::ClaimAll {
+            claim_base: claim_base(stake_pool, owner, stake_deposit_receipt, token_program),
+        },
+        instruction::ClaimAll {},
+    );
+    ix.accounts
+        .extend(reward_payout_metas(pool, reward_mints, reward_recipients)?);
+    Ok(ix)
+}
+
+/// `claim_all` over several of `owner`'s receipts, each reward pool paying once.
+/// `reward_mints` and `reward_recipients` are as for `claim_all`.
+pub fn claim_many(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    owner: &Pubkey,
+    stake_deposit_receipts: &[Pubkey],
+    reward_mints: &[RewardMint],
+    reward_recipients: &[Pubkey],
+) -> std::result::Result<Instruction, RewardAccountsError> {
+    let mut ix = build(
+        accounts::ClaimMany {
+            owner: *owner,
+            stake_pool: *stake_pool,
+        },
+        instruction::ClaimMany {},
+    );
+    ix.accounts
+        .extend(reward_payout_metas(pool, reward_mints, reward_recipients)?);
+    ix.accounts.extend(receipt_metas(stake_deposit_receipts));
+    Ok(ix)
+}
+
+/// Read-only, simulate it and pass the return data to `decode_claimable`
+pub fn get_claimable(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    stake_deposit_receipt: &Pubkey,
+) -> Instruction {
+    let mut ix = build(
+        accounts::GetClaimable {
+            stake_pool: *stake_pool,
+            stake_deposit_receipt: *stake_deposit_receipt,
+        },
+        instruction::GetClaimable {},
+    );
+    ix.accounts.extend(reward_vault_metas(pool));
+    ix
+}
+
+/// Claimable amounts by reward pool index, from the return data of `get_claimable`
+pub fn decode_claimable(return_data: &[u8]) -> Result<[u64; MAX_REWARD_POOLS]> {
+    AnchorDeserialize::try_from_slice(return_data)
+        .map_err(|_| error::ErrorCode::InstructionDidNotDeserialize.into())
+}
+
+/// Burns the receipt's LP tokens from `from` and returns the principal to `destination`.
+/// `from` is held by the owner, or by `lp_authority` which then signs as well.
+/// `rent_recipient` is `StakePool::rent_recipient` of the receipt.
+/// `reward_mints` and `reward_recipients` hold one entry per active reward pool, in pool
+/// order, else the builder returns a `RewardAccountsError`.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    owner: &Pubkey,
+    stake_deposit_receipt: &Pubkey,
+    from: &Pubkey,
+    lp_authority: Option<&Pubkey>,
+    destination: &Pubkey,
+    rent_recipient: &Pubkey,
+    token_program: &Pubkey,
+    reward_mints: &[RewardMint],
+    reward_recipients: &[Pubkey],
+) -> std::result::Result<Instruction, RewardAccountsError> {
+    let mut ix = build(
+        accounts::Withdraw {
+            claim_base: claim_base(stake_pool, owner, stake_deposit_receipt, token_program),
+            vault: pool.vault,
+            mint: pool.mint,
+            stake_mint: pool.stake_mint,
+            from: *from,
+            destination: *destination,
+            stake_token_program: pool.stake_token_program(token_program),
+            user_stake_index: find_user_stake_index_address(owner, stake_pool).0,
+            rent_recipient: *rent_recipient,
+            lp_authority: lp_authority.copied(),
+        },
+        instruction::Withdraw {},
+    );
+    ix.accounts
+        .extend(reward_payout_metas(pool, reward_mints, reward_recipients)?);
+    Ok(ix)
+}
+
+/// `withdraw` over several of `owner`'s unlocked receipts, all refunding their rent to
+/// `rent_recipient`. The other arguments are as for `withdraw`.
+#[allow(clippy::too_many_arguments)]
+pub fn withdraw_many(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    owner: &Pubkey,
+    stake_deposit_receipts: &[Pubkey],
+    from: &Pubkey,
+    lp_authority: Option<&Pubkey>,
+    destination: &Pubkey,
+    rent_recipient: &Pubkey,
+    token_program: &Pubkey,
+    reward_mints: &[RewardMint],
+    reward_recipients: &[Pubkey],
+) -> std::result::Result<Instruction, RewardAccountsError> {
+    let mut ix = build(
+        accounts::WithdrawMany {
+            owner: *owner,
+            stake_pool: *stake_pool,
+            vault: pool.vault,
+            mint: pool.mint,
+            stake_mint: pool.stake_mint,
+            from: *from,
+            destination: *destination,
+            token_program: *token_program,
+            stake_token_program: pool.stake_token_program(token_program),
+            user_stake_index: find_user_stake_index_address(owner, stake_pool).0,
+            rent_recipient: *rent_recipient,
+            lp_authority: lp_authority.copied(),
+        },
+        instruction::WithdrawMany {},
+    );
+    ix.accounts
+        .extend(reward_payout_metas(pool, reward_mints, reward_recipients)?);
+    ix.accounts.extend(receipt_metas(stake_deposit_receipts));
+    Ok(ix)
+}
+
+/// Writes the Metaplex metadata of the LP mint, or the metadata held by the LP mint itself
+/// when it is soulbound
+pub fn update_token_meta(
+    stake_pool: &Pubkey,
+    pool: &StakePool,
+    name: String,
+    symbol: String,
+    uri: String,
+) -> Instruction {
+    let (metadata_account, metadata_program) = if pool.stake_mint_is_soulbound() {
+        (pool.stake_mint, token_2022::ID)
+    } else {
+        (
+            find_stake_mint_metadata_address(&pool.stake_mint).0,
+            mpl_token_metadata::ID,
+        )
+    };
+    build(
+        accounts::UpdateTokenMeta {
+            authority: pool.authority,
+            metadata_account,
+            stake_pool: *stake_pool,
+            stake_mint: pool.stake_mint,
+            metadata_program,
+            rent: sysvar::rent::ID,
+            system_program: system_program::ID,
+        },
+        instruction::UpdateTokenMeta { name, symbol, uri },
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use alris_staking::state::RewardPool;
+    use bytemuck::Zeroable;
+
+    use super::*;
+
+    fn stake_pool_with_reward_vaults(reward_vaults: &[(usize, Pubkey)]) -> StakePool {
+        let mut pool = StakePool::zeroed();
+        pool.authority = Pubkey::new_unique();
+        pool.vault = Pubkey::new_unique();
+        pool.stake_mint = Pubkey::new_unique();
+        for (index, reward_vault) in reward_vaults {
+            pool.reward_pools[*index] = RewardPool::new(reward_vault);
+        }
+        pool
+    }
+
+    #[test]
+    fn deposit_appends_reward_vaults_only() {
+        let reward_vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let pool = stake_pool_with_reward_vaults(&[(0, reward_vaults[0]), (3, reward_vaults[1])]);
+        let stake_pool = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let ix = deposit(
+            &stake_pool,
+            &pool,
+            &owner,
+            &owner,
+            None,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &anchor_spl::token::ID,
+            DepositArgs {
+                nonce: 7,
+                amount: 1,
+                lockup_duration: 0,
+                allowlist_proof: None,
+            },
+        );
+
+        let remaining: Vec<Pubkey> = ix.accounts[16..].iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(remaining, reward_vaults);
+        assert_eq!(
+            ix.accounts[8].pubkey,
+            find_stake_deposit_receipt_address(&owner, &stake_pool, 7).0
+        );
+    }
+
+    #[test]
+    fn decode_claimable_round_trip() {
+        let mut amounts = [0u64; MAX_REWARD_POOLS];
+        amounts[0] = 7;
+        amounts[9] = u64::MAX;
+        let data = amounts.try_to_vec().unwrap();
+        assert_eq!(decode_claimable(&data).unwrap(), amounts);
+        assert!(decode_claimable(&data[1..]).is_err());
+    }
+
+    #[test]
+    fn withdraw_appends_reward_payout_accounts() {
+        let reward_vaults = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let recipients = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let reward_mints = [
+            RewardMint {
+                mint: Pubkey::new_unique(),
+                token_program: anchor_spl::token::ID,
+            },
+            RewardMint {
+                mint: Pubkey::new_unique(),
+                token_program: anchor_spl::token_2022::ID,
+            },
+        ];
+        let pool = stake_pool_with_reward_vaults(&[(1, reward_vaults[0]), (2, reward_vaults[1])]);
+        let ix = withdraw(
+            &Pubkey::new_unique(),
+            &pool,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            None,
+            &Pubkey::new_unique(),
+            &Pubkey::new_unique(),
+            &anchor_spl::token::ID,
+            &reward_mints,
+            &recipients,
+        )
+        .unwrap();
+
+        let remaining: Vec<Pubkey> = ix.accounts[13..].iter().map(|meta| meta.pubkey).collect();
+        assert_eq!(
+            remaining,
+            [
+                reward_vaults[0],
+                recipients[0],
+                reward_mints[0].mint,
+                reward_mints[0].token_program,
+                reward_vaults[1],
+                recipients[1],
+                reward_mints[1].mint,
+                reward_mints[1].token_program,
+            ]
+        );
+        let writable: Vec<bool> = ix.accounts[13..]
+            .iter()
+            .map(|meta| meta.is_writable)
+            .collect();
+        assert_eq!(
+            writable,
+            [true, true, false, false, true, true, false, false]
+        );
+    }
+
+    #[test]
+    fn claim_all_rejects_missing_reward_recipient() {
+        let pool =
+            stake_pool_with_reward_vaults(&[(0, Pubkey::new_unique()), (1, Pubkey::new_unique())]);
+        let reward_mint = RewardMint {
+            mint: Pubkey::new_unique(),
+            token_program: anchor_spl::token::ID,
+        };
+        let claim = |reward_mints: &[RewardMint], reward_recipients: &[Pubkey]| {
+            claim_all(
+                &Pubkey::new_unique(),
+                &pool,
+                &Pubkey::new_unique(),
+                &Pubkey::new_unique(),
+                &anchor_spl::token::ID,
+                reward_mints,
+                reward_recipients,
+            )
+        };
+        assert_eq!(
+            claim(&[reward_mint, reward_mint], &[Pubkey::new_unique()]),
+            Err(RewardAccountsError::RewardRecipientCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+        assert_eq!(
+            claim(&[reward_mint], &[Pubkey::new_unique(); 2]),
+            Err(RewardAccountsError::RewardMintCount {
+                expected: 2,
+                actual: 1
+            })
+        );
+    }
+}
@@ -0,0 +1,10 @@
+//! Client helpers for the Alris staking program: PDA derivation, instruction builders and
+//! account deserializers.
+
+pub mod accounts;
+pub mod allowlist;
+pub mod instructions;
+pub mod pda;
+pub mod simulator;
+
+pub use alris_staking::{self, ID as PROGRAM_ID};
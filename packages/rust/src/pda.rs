@@ -0,0 +1,75 @@
+//! PDA derivation mirroring the seeds used by the program's account constraints.
+
+use anchor_lang::prelude::Pubkey;
+use anchor_spl::metadata::mpl_token_metadata;
+
+use crate::PROGRAM_ID;
+
+/// `StakePool` of `InitializeStakePool`
+pub fn find_stake_pool_address(nonce: u8, mint: &Pubkey, authority: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            &nonce.to_le_bytes(),
+            mint.as_ref(),
+            authority.as_ref(),
+            b"stakePool",
+        ],
+        &PROGRAM_ID,
+    )
+}
+
+/// LP mint of `InitializeStakePool`
+pub fn find_stake_mint_address(stake_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[stake_pool.as_ref(), b"stakeMint"], &PROGRAM_ID)
+}
+
+/// Principal vault of `InitializeStakePool`
+pub fn find_vault_address(stake_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(&[stake_pool.as_ref(), b"vault"], &PROGRAM_ID)
+}
+
+/// Reward vault of `AddRewardPool`
+pub fn find_reward_vault_address(stake_pool: &Pubkey, reward_mint: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[stake_pool.as_ref(), reward_mint.as_ref(), b"rewardVault"],
+        &PROGRAM_ID,
+    )
+}
+
+/// `StakeDepositReceipt` of `Deposit`
+pub fn find_stake_deposit_receipt_address(
+    owner: &Pubkey,
+    stake_pool: &Pubkey,
+    nonce: u32,
+) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[
+            owner.as_ref(),
+            stake_pool.as_ref(),
+            &nonce.to_le_bytes(),
+            b"stakeDepositReceipt",
+        ],
+        &PROGRAM_ID,
+    )
+}
+
+/// `UserStakeIndex` of `owner` in `stake_pool`
+pub fn find_user_stake_index_address(owner: &Pubkey, stake_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), stake_pool.as_ref(), b"userStakeIndex"],
+        &PROGRAM_ID,
+    )
+}
+
+/// `DepositAllowance` of `owner` in `stake_pool`
+pub fn find_deposit_allowance_address(owner: &Pubkey, stake_pool: &Pubkey) -> (Pubkey, u8) {
+    Pubkey::find_program_address(
+        &[owner.as_ref(), stake_pool.as_ref(), b"depositAllowance"],
+        &PROGRAM_ID,
+    )
+}
+
+/// Metaplex metadata account of the LP mint, as passed to `UpdateTokenMeta`
+pub fn find_stake_mint_metadata_address(stake_mint: &Pubkey) -> (Pubkey, u8) {
+    mpl_token_metadata::accounts::Metadata::find_pda(stake_mint)
+}
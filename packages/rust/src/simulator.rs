@@ -0,0 +1,336 @@
+//! Off-chain model of a stake pool. Every step runs the same `StakePool`, `RewardPool` and
+//! `StakeDepositReceipt` bookkeeping the instruction handlers do, with token balances tracked
+//! in memory, so payouts match the program to the last base unit.
+
+use alris_staking::state::{u128, RewardPool, StakeDepositReceipt, StakePool, MAX_REWARD_POOLS};
+use anchor_lang::prelude::*;
+use bytemuck::Zeroable;
+
+pub struct PoolConfig {
+    pub max_weight: u64,
+    pub min_duration: u64,
+    pub max_duration: u64,
+    /// Reward pools to add, as in `add_reward_pool`. Each gets its own simulated vault.
+    pub reward_pool_indexes: Vec<u8>,
+    pub flags: u8,
+    /// As set by `set_min_deposit_amount`
+    pub min_deposit_amount: u64,
+}
+
+#[derive(Debug)]
+pub enum Action {
+    /// Opens the next receipt, as in `deposit`
+    Deposit { amount: u64, lockup_duration: u64 },
+    /// As in `claim_all`, `receipt` is the position of the receipt's deposit
+    Claim { receipt: usize },
+    /// As in `withdraw`, `receipt` is the position of the receipt's deposit
+    Withdraw { receipt: usize },
+    /// As in `fund_reward_pool`
+    FundRewardPool { index: u8, amount: u64 },
+    /// Plain token transfer into a reward vault, booked on the next accrual
+    TransferRewards { index: u8, amount: u64 },
+}
+
+#[derive(Debug)]
+pub struct Step {
+    pub timestamp: i64,
+    pub action: Action,
+}
+
+#[derive(Clone)]
+pub struct SimulatedReceipt {
+    pub receipt: StakeDepositReceipt,
+    pub weight: u64,
+    /// LP tokens minted on deposit
+    pub lp_amount: u64,
+    /// Rewards paid out so far, by reward pool index
+    pub claimed: [u64; MAX_REWARD_POOLS],
+    pub withdrawn_at: Option<i64>,
+}
+
+pub struct Simulation {
+    pub stake_pool: StakePool,
+    /// Simulated token balance of each reward pool's vault, by reward pool index
+    pub vault_amounts: [u64; MAX_REWARD_POOLS],
+    pub receipts: Vec<SimulatedReceipt>,
+}
+
+impl Simulation {
+    pub fn new(config: &PoolConfig) -> Result<Self> {
+        let mut stake_pool = StakePool::zeroed();
+        stake_pool.configure(config.max_weight, config.min_duration, config.max_duration)?;
+        stake_pool.flags = config.flags;
+        stake_pool.min_deposit_amount = config.min_deposit_amount;
+        for index in &config.reward_pool_indexes {
+            let reward_pool = stake_pool
+                .reward_pools
+                .get_mut(usize::from(*index))
+                .ok_or(alris_staking::errors::ErrorCode::InvalidRewardPoolIndex)?;
+            // any key other than the default marks the reward pool as active
+            *reward_pool = RewardPool::new(&Pubkey::new_from_array([index + 1; 32]));
+        }
+        Ok(Self {
+            stake_pool,
+            vault_amounts: [0; MAX_REWARD_POOLS],
+            receipts: vec![],
+        })
+    }
+
+    /// Applies `steps` in order, stopping at the first one the program would reject
+    pub fn run(&mut self, steps: &[Step]) -> Result<()> {
+        for step in steps {
+            self.apply(step)?;
+        }
+        Ok(())
+    }
+
+    pub fn apply(&mut self, step: &Step) -> Result<()> {
+        let now = step.timestamp;
+        match step.action {
+            Action::Deposit {
+                amount,
+                lockup_duration,
+            } => self.deposit(now, amount, lockup_duration).map(|_| ()),
+            Action::Claim { receipt } => self.claim(now, receipt).map(|_| ()),
+            Action::Withdraw { receipt } => self.withdraw(now, receipt).map(|_| ()),
+            Action::FundRewardPool { index, amount } => self.fund_reward_pool(now, index, amount),
+            Action::TransferRewards { index, amount } => self.transfer_rewards(index, amount),
+        }
+    }
+
+    /// Returns the position of the new receipt
+    pub fn deposit(&mut self, now: i64, amount: u64, lockup_duration: u64) -> Result<usize> {
+        self.transact(|simulation| simulation.process_deposit(now, amount, lockup_duration))
+    }
+
+    /// Returns the amounts paid out, by reward pool index
+    pub fn claim(&mut self, now: i64, receipt: usize) -> Result<[u64; MAX_REWARD_POOLS]> {
+        self.transact(|simulation| {
+            simulation.open_receipt(receipt)?;
+            simulation.accrue(now)?;
+            simulation.settle(receipt)
+        })
+    }
+
+    /// Returns the rewards paid out along with the principal, by reward pool index
+    pub fn withdraw(&mut self, now: i64, receipt: usize) -> Result<[u64; MAX_REWARD_POOLS]> {
+        self.transact(|simulation| simulation.process_withdraw(now, receipt))
+    }
+
+    pub fn fund_reward_pool(&mut self, now: i64, index: u8, amount: u64) -> Result<()> {
+        self.transact(|simulation| {
+            let vault_amount = simulation.credit_vault(index, amount)?;
+            simulation
+                .stake_pool
+                .record_funding(usize::from(index), vault_amount, amount, now)
+        })
+    }
+
+    pub fn transfer_rewards(&mut self, index: u8, amount: u64) -> Result<()> {
+        self.transact(|simulation| simulation.credit_vault(index, amount).map(|_| ()))
+    }
+
+    /// Rewards `receipt` would be paid if it claimed at `now`, by reward pool index
+    pub fn get_pending_rewards(&self, now: i64, receipt: usize) -> Result<[u64; MAX_REWARD_POOLS]> {
+        self.open_receipt(receipt)?;
+        self.stake_pool.preview_claimable_amounts(
+            &self.receipts[receipt].receipt,
+            &self.active_vault_amounts(),
+            now,
+        )
+    }
+
+    /// Runs `process` against the simulation, rolling every change back if it fails the way
+    /// a failed transaction would
+    fn transact<T>(&mut self, process: impl FnOnce(&mut Self) -> Result<T>) -> Result<T> {
+        let stake_pool = self.stake_pool;
+        let vault_amounts = self.vault_amounts;
+        let receipts = self.receipts.clone();
+        process(self).inspect_err(|_| {
+            self.stake_pool = stake_pool;
+            self.vault_amounts = vault_amounts;
+            self.receipts = receipts;
+        })
+    }
+
+    fn process_deposit(&mut self, now: i64, amount: u64, lockup_duration: u64) -> Result<usize> {
+        self.accrue(now)?;
+        let mut receipt = StakeDepositReceipt {
+            owner: Pubkey::default(),
+            payer: Pubkey::default(),
+            stake_pool: Pubkey::default(),
+            lockup_duration: 0,
+            deposit_timestamp: 0,
+            deposit_amount: 0,
+            effective_stake: u128::default(),
+            claimed_amounts: [u128::default(); MAX_REWARD_POOLS],
+        };
+        let weight = self
+            .stake_pool
+            .record_deposit(&mut receipt, amount, lockup_duration, now)?;
+        let lp_amount = StakeDepositReceipt::get_token_amount_from_stake(
+            receipt.effective_stake_u128(),
+            self.stake_pool.max_weight,
+        )?;
+        self.receipts.push(SimulatedReceipt {
+            receipt,
+            weight,
+            lp_amount,
+            claimed: [0; MAX_REWARD_POOLS],
+            withdrawn_at: None,
+        });
+        Ok(self.receipts.len() - 1)
+    }
+
+    fn process_withdraw(&mut self, now: i64, receipt: usize) -> Result<[u64; MAX_REWARD_POOLS]> {
+        self.open_receipt(receipt)?;
+        self.accrue(now)?;
+        self.stake_pool
+            .record_withdrawal(&self.receipts[receipt].receipt)?;
+        if !self.stake_pool.escape_hatch_enabled() {
+            self.receipts[receipt].receipt.validate_unlocked(now)?;
+        }
+        let claimed = self.settle(receipt)?;
+        self.receipts[receipt].withdrawn_at = Some(now);
+        Ok(claimed)
+    }
+
+    /// Vault balances of the active reward pools, in pool order
+    fn active_vault_amounts(&self) -> Vec<u64> {
+        self.stake_pool
+            .reward_pools
+            .iter()
+            .zip(self.vault_amounts)
+            .filter(|(reward_pool, _)| !reward_pool.is_empty())
+            .map(|(_, vault_amount)| vault_amount)
+            .collect()
+    }
+
+    fn accrue(&mut self, now: i64) -> Result<()> {
+        let vault_amounts = self.active_vault_amounts();
+        self.stake_pool.accrue_reward_pools(&vault_amounts, now)
+    }
+
+    fn settle(&mut self, receipt: usize) -> Result<[u64; MAX_REWARD_POOLS]> {
+        let simulated = &mut self.receipts[receipt];
+        let claimed = self.stake_pool.settle_claims(&mut simulated.receipt)?;
+        for (index, amount) in claimed.iter().enumerate() {
+            self.vault_amounts[index] = self.vault_amounts[index]
+                .checked_sub(*amount)
+                .ok_or(alris_staking::errors::ErrorCode::MathUnderflow)?;
+            simulated.claimed[index] += amount;
+        }
+        Ok(claimed)
+    }
+
+    fn credit_vault(&mut self, index: u8, amount: u64) -> Result<u64> {
+        let vault_amount = self
+            .vault_amounts
+            .get_mut(usize::from(index))
+            .filter(|_| !self.stake_pool.reward_pools[usize::from(index)].is_empty())
+            .ok_or(alris_staking::errors::ErrorCode::InvalidRewardPoolIndex)?;
+        *vault_amount = vault_amount
+            .checked_add(amount)
+            .ok_or(alris_staking::errors::ErrorCode::MathOverflow)?;
+        Ok(*vault_amount)
+    }
+
+    fn open_receipt(&self, receipt: usize) -> Result<()> {
+        match self.receipts.get(receipt) {
+            Some(simulated) if simulated.withdrawn_at.is_none() => Ok(()),
+            // the receipt account does not exist
+            _ => err!(anchor_lang::error::ErrorCode::AccountNotInitialized),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alris_staking::{errors::ErrorCode, state::SCALE_FACTOR_BASE};
+
+    use super::*;
+
+    fn config() -> PoolConfig {
+        PoolConfig {
+            max_weight: 2 * SCALE_FACTOR_BASE,
+            min_duration: 0,
+            max_duration: 100,
+            reward_pool_indexes: vec![0, 3],
+            flags: 0,
+            min_deposit_amount: 0,
+        }
+    }
+
+    #[test]
+    fn rewards_split_by_weight() {
+        let mut simulation = Simulation::new(&config()).unwrap();
+        simulation
+            .run(&[
+                Step {
+                    timestamp: 0,
+                    action: Action::Deposit {
+                        amount: 1_000,
+                        lockup_duration: 0,
+                    },
+                },
+                Step {
+                    timestamp: 0,
+                    action: Action::Deposit {
+                        amount: 1_000,
+                        lockup_duration: 100,
+                    },
+                },
+                Step {
+                    timestamp: 10,
+                    action: Action::FundRewardPool {
+                        index: 3,
+                        amount: 3_000,
+                    },
+                },
+                Step {
+                    timestamp: 100,
+                    action: Action::Withdraw { receipt: 0 },
+                },
+                Step {
+                    timestamp: 100,
+                    action: Action::Withdraw { receipt: 1 },
+                },
+            ])
+            .unwrap();
+
+        assert_eq!(simulation.receipts[0].claimed[3], 1_000);
+        assert_eq!(simulation.receipts[1].claimed[3], 2_000);
+        assert_eq!(simulation.receipts[1].weight, 2 * SCALE_FACTOR_BASE);
+        assert_eq!(simulation.stake_pool.total_weighted_stake_u128(), 0);
+        assert_eq!(simulation.vault_amounts[3], 0);
+    }
+
+    #[test]
+    fn late_deposit_misses_earlier_rewards() {
+        let mut simulation = Simulation::new(&config()).unwrap();
+        simulation.deposit(0, 1_000, 0).unwrap();
+        simulation.fund_reward_pool(5, 0, 500).unwrap();
+        simulation.deposit(6, 1_000, 0).unwrap();
+        simulation.transfer_rewards(0, 500).unwrap();
+
+        assert_eq!(simulation.get_pending_rewards(7, 0).unwrap()[0], 750);
+        assert_eq!(simulation.get_pending_rewards(7, 1).unwrap()[0], 250);
+        assert_eq!(simulation.claim(7, 1).unwrap()[0], 250);
+        assert_eq!(simulation.get_pending_rewards(7, 1).unwrap()[0], 0);
+    }
+
+    #[test]
+    fn withdraw_before_unlock_rejected() {
+        let mut simulation = Simulation::new(&config()).unwrap();
+        simulation.deposit(0, 1_000, 50).unwrap();
+        assert_eq!(
+            simulation.withdraw(49, 0).err(),
+            Some(ErrorCode::StakeStillLocked.into())
+        );
+        simulation.withdraw(50, 0).unwrap();
+        assert_eq!(
+            simulation.claim(51, 0).err(),
+            Some(anchor_lang::error::ErrorCode::AccountNotInitialized.into())
+        );
+    }
+}
@@ -0,0 +1,199 @@
+//! Random sequences of deposits, reward top-ups, claims and withdrawals run through the
+//! simulator, checking the reward accounting invariants after every step.
+
+use alris_staking::{
+    errors::ErrorCode,
+    state::{MAX_REWARD_POOLS, SCALE_FACTOR_BASE},
+};
+use alris_staking_client::simulator::{Action, PoolConfig, Simulation, Step};
+use anchor_lang::error::Error;
+use proptest::prelude::*;
+
+const REWARD_POOL_INDEXES: [u8; 2] = [0, 3];
+const MIN_DURATION: u64 = 10;
+const MAX_DURATION: u64 = 1_000;
+/// Keeps the cumulative amounts far from `u64::MAX` so overflows point at a real bug
+const MAX_AMOUNT: u64 = 1_000_000_000_000;
+/// A few times the amount the digit shift of `config` rounds down to no LP at base weight
+const MAX_DUST_AMOUNT: u64 = 30;
+
+fn config(min_deposit_amount: u64) -> PoolConfig {
+    PoolConfig {
+        max_weight: 4 * SCALE_FACTOR_BASE,
+        min_duration: MIN_DURATION,
+        max_duration: MAX_DURATION,
+        reward_pool_indexes: REWARD_POOL_INDEXES.to_vec(),
+        flags: 0,
+        min_deposit_amount,
+    }
+}
+
+/// No minimum half the time, otherwise one above the amounts `deposit_amount` draws as dust
+fn min_deposit_amount() -> impl Strategy<Value = u64> {
+    prop_oneof![Just(0), 1..=2 * MAX_DUST_AMOUNT]
+}
+
+/// Mostly anything up to `MAX_AMOUNT`, sometimes dust the 4x max weight's digit shift
+/// rounds down to no LP
+fn deposit_amount() -> impl Strategy<Value = u64> {
+    prop_oneof![
+        3 => 1..=MAX_AMOUNT,
+        1 => 1..=MAX_DUST_AMOUNT,
+    ]
+}
+
+fn reward_pool_index() -> impl Strategy<Value = u8> {
+    prop::sample::select(REWARD_POOL_INDEXES.to_vec())
+}
+
+fn action() -> impl Strategy<Value = Action> {
+    prop_oneof![
+        3 => (deposit_amount(), 0..=2 * MAX_DURATION).prop_map(|(amount, lockup_duration)| {
+            Action::Deposit {
+                amount,
+                lockup_duration,
+            }
+        }),
+        2 => (0usize..16).prop_map(|receipt| Action::Claim { receipt }),
+        2 => (0usize..16).prop_map(|receipt| Action::Withdraw { receipt }),
+        2 => (reward_pool_index(), 1..=MAX_AMOUNT)
+            .prop_map(|(index, amount)| Action::FundRewardPool { index, amount }),
+        1 => (reward_pool_index(), 1..=MAX_AMOUNT)
+            .prop_map(|(index, amount)| Action::TransferRewards { index, amount }),
+    ]
+}
+
+/// Actions with non-decreasing timestamps, several may land in the same second
+fn steps() -> impl Strategy<Value = Vec<Step>> {
+    prop::collection::vec((0i64..=200, action()), 1..64).prop_map(|actions| {
+        let mut timestamp = 0;
+        actions
+            .into_iter()
+            .map(|(elapsed, action)| {
+                timestamp += elapsed;
+                Step { timestamp, action }
+            })
+            .collect()
+    })
+}
+
+/// Rejections a valid pool may legitimately return for a generated step
+fn is_expected_rejection(error: &Error) -> bool {
+    [
+        ErrorCode::DurationTooShort.into(),
+        ErrorCode::StakeStillLocked.into(),
+        ErrorCode::NoStakeToReward.into(),
+        ErrorCode::DepositTooSmall.into(),
+        ErrorCode::ZeroLpDeposit.into(),
+        anchor_lang::error::ErrorCode::AccountNotInitialized.into(),
+    ]
+    .contains(error)
+}
+
+fn check_invariants(
+    simulation: &Simulation,
+    funded: &[u64; MAX_REWARD_POOLS],
+) -> std::result::Result<(), TestCaseError> {
+    let live_effective_stake: u128 = simulation
+        .receipts
+        .iter()
+        .filter(|simulated| simulated.withdrawn_at.is_none())
+        .map(|simulated| simulated.receipt.effective_stake_u128())
+        .sum();
+    prop_assert_eq!(
+        simulation.stake_pool.total_weighted_stake_u128(),
+        live_effective_stake
+    );
+    let live_deposit_amount: u64 = simulation
+        .receipts
+        .iter()
+        .filter(|simulated| simulated.withdrawn_at.is_none())
+        .map(|simulated| simulated.receipt.deposit_amount)
+        .sum();
+    prop_assert_eq!(
+        simulation.stake_pool.total_deposit_amount,
+        live_deposit_amount
+    );
+
+    for index in REWARD_POOL_INDEXES.map(usize::from) {
+        let claimed: u64 = simulation
+            .receipts
+            .iter()
+            .map(|simulated| simulated.claimed[index])
+            .sum();
+        prop_assert!(
+            claimed <= funded[index],
+            "reward pool {} paid {} of {} funded",
+            index,
+            claimed,
+            funded[index]
+        );
+        let last_amount = simulation.stake_pool.reward_pools[index].last_amount;
+        prop_assert!(
+            last_amount <= simulation.vault_amounts[index],
+            "reward pool {} booked {} with {} in the vault",
+            index,
+            last_amount,
+            simulation.vault_amounts[index]
+        );
+    }
+    Ok(())
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(512))]
+
+    #[test]
+    fn reward_accounting_invariants(
+        min_deposit_amount in min_deposit_amount(),
+        steps in steps(),
+    ) {
+        let mut simulation = Simulation::new(&config(min_deposit_amount)).unwrap();
+        let mut funded = [0u64; MAX_REWARD_POOLS];
+
+        for step in &steps {
+            // what `get_claimable` would report is what the claim pays
+            let pending = match step.action {
+                Action::Claim { receipt } => simulation
+                    .get_pending_rewards(step.timestamp, receipt)
+                    .ok()
+                    .map(|pending| (receipt, pending, simulation.receipts[receipt].claimed)),
+                _ => None,
+            };
+            match simulation.apply(step) {
+                Ok(()) => {
+                    if let Some((receipt, pending, claimed)) = pending {
+                        for index in REWARD_POOL_INDEXES.map(usize::from) {
+                            prop_assert_eq!(
+                                simulation.receipts[receipt].claimed[index] - claimed[index],
+                                pending[index]
+                            );
+                        }
+                    }
+                    if let Action::FundRewardPool { index, amount }
+                    | Action::TransferRewards { index, amount } = step.action
+                    {
+                        funded[usize::from(index)] += amount;
+                    }
+                }
+                Err(error) => prop_assert!(
+                    is_expected_rejection(&error),
+                    "unexpected error at {}: {}",
+                    step.timestamp,
+                    error
+                ),
+            }
+            check_invariants(&simulation, &funded)?;
+        }
+
+        // once everything unlocks every staker can leave
+        let end = steps.last().unwrap().timestamp + i64::try_from(MAX_DURATION).unwrap();
+        for receipt in 0..simulation.receipts.len() {
+            if simulation.receipts[receipt].withdrawn_at.is_none() {
+                simulation.withdraw(end, receipt).unwrap();
+            }
+        }
+        check_invariants(&simulation, &funded)?;
+        prop_assert_eq!(simulation.stake_pool.total_weighted_stake_u128(), 0);
+    }
+}
@@ -0,0 +1,687 @@
+//! Runs arbitrary sequences of the program's instructions as an attacker holding its own
+//! deposits and token accounts in two pools next to a victim's. Any non-signer account of
+//! an instruction may be swapped for another account of the setup and accounts may be
+//! dropped off the end, which covers wrong vaults in the remaining accounts, foreign stake
+//! mints and receipts of the other pool. The first pool mints soulbound Token-2022 LP tokens
+//! against a classic mint, the second pool stakes a Token-2022 mint with a transfer fee.
+//! After every step the principal and reward vaults must still cover what the
+//! program owes and the victim's claimable rewards must not shrink.
+//!
+//! Runs offline once the dependencies are fetched. The program is safe Rust so the address
+//! sanitizer only slows it down, and every slot the bank moves through stays in memory, so
+//! fork mode restarts the workers as they fill up:
+//!
+//!     cargo +nightly fuzz run -s none instruction_sequences -- -fork=1
+
+#![no_main]
+
+#[path = "../../tests/common/mod.rs"]
+mod common;
+
+use alris_staking::state::{StakeDepositReceipt, MAX_REWARD_POOLS, SOULBOUND_STAKE_MINT};
+use alris_staking_client::{
+    instructions::{self, DepositArgs, RewardMint},
+    pda::{find_stake_deposit_receipt_address, find_user_stake_index_address},
+};
+use anchor_lang::{prelude::Pubkey, AccountDeserialize};
+use anchor_spl::{token::spl_token, token_2022::spl_token_2022};
+use arbitrary::Arbitrary;
+use common::*;
+use libfuzzer_sys::fuzz_target;
+use solana_sdk::{
+    instruction::Instruction,
+    signature::{Keypair, Signer},
+};
+use std::cell::RefCell;
+use tokio::runtime::{Builder, Runtime};
+
+const MAX_STEPS: usize = 16;
+const MAX_SUBSTITUTIONS: usize = 4;
+/// Deposits of the attacker in each pool use nonces below this
+const ATTACKER_NONCES: u8 = 2;
+const STAKE: u64 = 1_000_000_000;
+const REWARDS: u64 = 1_000_000;
+/// Transfer fee of the second pool's mint
+const TRANSFER_FEE: MintExtension = MintExtension::TransferFee {
+    basis_points: 250,
+    maximum_fee: 10_000_000,
+};
+
+thread_local! {
+    /// A bank leaks its memory when dropped, so every input runs in this one
+    static HARNESS: RefCell<Option<(Runtime, TestContext)>> = const { RefCell::new(None) };
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum Pool {
+    A,
+    B,
+}
+
+#[derive(Arbitrary, Debug, Clone, Copy)]
+enum Receipt {
+    Victim(Pool),
+    Attacker(Pool, u8),
+}
+
+#[derive(Arbitrary, Debug)]
+enum Op {
+    Deposit {
+        pool: Pool,
+        nonce: u8,
+        amount: u32,
+        lockup_duration: u16,
+        /// Signs for the tokens as this pool's attacker, the payer then only pays
+        token_authority: Option<Pool>,
+    },
+    ClaimAll {
+        pool: Pool,
+        receipt: Receipt,
+    },
+    ClaimMany {
+        pool: Pool,
+        receipts: Vec<Receipt>,
+    },
+    Withdraw {
+        pool: Pool,
+        receipt: Receipt,
+        /// Burns the LP tokens of this pool's attacker, signing as `lp_authority`
+        lp_from: Option<Pool>,
+    },
+    WithdrawMany {
+        pool: Pool,
+        receipts: Vec<Receipt>,
+    },
+    FundRewardPool {
+        pool: Pool,
+        index: u8,
+        amount: u32,
+    },
+    AddRewardPool {
+        pool: Pool,
+        index: u8,
+    },
+    SetFlags {
+        pool: Pool,
+        flags: u8,
+    },
+    TransferAuthority {
+        pool: Pool,
+    },
+    DangerouslyMintLp {
+        pool: Pool,
+        amount: u32,
+    },
+    BurnUnbackedLp {
+        pool: Pool,
+        amount: u32,
+    },
+    SetUnbackedLpCap {
+        pool: Pool,
+        cap: u32,
+    },
+    SetUnbackedLpCapAuthority {
+        pool: Pool,
+    },
+    SetRewardPoolPolicy {
+        pool: Pool,
+        index: u8,
+        policy: u8,
+        release_period: u16,
+    },
+    SweepUndistributed {
+        pool: Pool,
+        index: u8,
+    },
+    SweepRewardDust {
+        pool: Pool,
+        index: u8,
+    },
+    UpdateTokenMeta {
+        pool: Pool,
+    },
+}
+
+#[derive(Arbitrary, Debug)]
+struct Substitution {
+    /// Position in the instruction's accounts, signers are left alone
+    account: u8,
+    /// Position in `World::known_accounts`
+    replacement: u8,
+}
+
+#[derive(Arbitrary, Debug)]
+struct Step {
+    op: Op,
+    substitutions: Vec<Substitution>,
+    /// Accounts to drop off the end
+    truncate: u8,
+    elapsed: u16,
+}
+
+struct World<'a> {
+    test: &'a mut TestContext,
+    pools: [Pubkey; 2],
+    /// Token program of each pool's mint
+    token_programs: [Pubkey; 2],
+    /// Active reward pools of each pool as set up, in pool order
+    reward_mints: [Vec<RewardMint>; 2],
+    victim_receipts: [Pubkey; 2],
+    /// One signer per pool, both controlled by the attacker
+    attackers: [Staker; 2],
+    known_accounts: Vec<Pubkey>,
+    /// Victim claimable amounts after the previous step, by pool
+    victim_claimable: [[u64; MAX_REWARD_POOLS]; 2],
+}
+
+impl<'a> World<'a> {
+    /// Sets up fresh pools and stakers in `test`
+    async fn new(test: &'a mut TestContext) -> Self {
+        let mint_a = test.create_mint(9).await;
+        let pool_a = test
+            .create_stake_pool_with_options(&mint_a, &spl_token::ID, SOULBOUND_STAKE_MINT)
+            .await
+            .unwrap();
+        let mint_b = test.create_mint_2022(9, &[TRANSFER_FEE]).await;
+        let pool_b = test.create_stake_pool_for(&mint_b).await.unwrap();
+        test.add_reward_pool(&pool_a, 0).await;
+        test.add_reward_pool(&pool_a, 1).await;
+        test.add_reward_pool(&pool_b, 0).await;
+        let pools = [pool_a, pool_b];
+        let token_programs = [spl_token::ID, spl_token_2022::ID];
+        let reward_mints = [
+            test.reward_mints(&pool_a).await,
+            test.reward_mints(&pool_b).await,
+        ];
+
+        let mut victims = vec![];
+        let mut victim_receipts = [Pubkey::default(); 2];
+        let mut attackers = vec![];
+        for (pool, mint) in [(pool_a, mint_a), (pool_b, mint_b)] {
+            let victim = test.new_staker(&mint, &pool, STAKE).await;
+            let receipt = test
+                .deposit(&pool, &victim, 0, STAKE, MAX_DURATION)
+                .await
+                .unwrap();
+            victim_receipts[usize::from(pool == pool_b)] = receipt;
+            victims.push(victim);
+
+            let attacker = test.new_staker(&mint, &pool, STAKE).await;
+            let reward_mints = attacker.reward_accounts.clone();
+            for reward_account in reward_mints {
+                let reward_mint = test.token_mint(&reward_account).await;
+                test.mint_to(&reward_mint, &reward_account, REWARDS).await;
+            }
+            attackers.push(attacker);
+        }
+        test.fund_reward_pool(&pool_a, 0, REWARDS).await;
+        test.fund_reward_pool(&pool_a, 1, REWARDS).await;
+        test.fund_reward_pool(&pool_b, 0, REWARDS).await;
+
+        let mut known_accounts = vec![pool_a, pool_b, mint_a, mint_b];
+        known_accounts.extend(token_programs);
+        known_accounts.extend(
+            reward_mints
+                .iter()
+                .flatten()
+                .map(|reward_mint| reward_mint.mint),
+        );
+        for pool in pools {
+            let state = test.stake_pool(&pool).await;
+            known_accounts.extend([state.vault, state.stake_mint]);
+            known_accounts.extend(
+                state
+                    .reward_pools
+                    .iter()
+                    .filter(|reward_pool| !reward_pool.is_empty())
+                    .map(|reward_pool| reward_pool.reward_vault),
+            );
+        }
+        for staker in victims.iter().chain(&attackers) {
+            known_accounts.extend([staker.tokens, staker.lp]);
+            known_accounts.extend(&staker.reward_accounts);
+            known_accounts.extend(
+                pools
+                    .iter()
+                    .map(|pool| find_user_stake_index_address(&staker.owner.pubkey(), pool).0),
+            );
+        }
+        known_accounts.extend(victim_receipts);
+        let attackers: [Staker; 2] = attackers.try_into().ok().unwrap();
+        for (pool, attacker) in pools.iter().zip(&attackers) {
+            known_accounts.extend((0..ATTACKER_NONCES).map(|nonce| {
+                find_stake_deposit_receipt_address(&attacker.owner.pubkey(), pool, u32::from(nonce))
+                    .0
+            }));
+        }
+
+        let mut world = Self {
+            test,
+            pools,
+            token_programs,
+            reward_mints,
+            victim_receipts,
+            attackers,
+            known_accounts,
+            victim_claimable: [[0; MAX_REWARD_POOLS]; 2],
+        };
+        world.check_invariants().await;
+        world
+    }
+
+    fn pool(&self, pool: Pool) -> Pubkey {
+        self.pools[pool as usize]
+    }
+
+    fn attacker(&self, pool: Pool) -> &Staker {
+        &self.attackers[pool as usize]
+    }
+
+    fn token_program(&self, pool: Pool) -> &Pubkey {
+        &self.token_programs[pool as usize]
+    }
+
+    fn reward_mints(&self, pool: Pool) -> &[RewardMint] {
+        &self.reward_mints[pool as usize]
+    }
+
+    fn receipt(&self, receipt: Receipt) -> Pubkey {
+        match receipt {
+            Receipt::Victim(pool) => self.victim_receipts[pool as usize],
+            Receipt::Attacker(pool, nonce) => {
+                find_stake_deposit_receipt_address(
+                    &self.attacker(pool).owner.pubkey(),
+                    &self.pool(pool),
+                    u32::from(nonce % ATTACKER_NONCES),
+                )
+                .0
+            }
+        }
+    }
+
+    async fn instruction(&mut self, op: &Op) -> Instruction {
+        let attacker = self.attackers[0].owner.pubkey();
+        let reward_index = |index: &u8| index % MAX_REWARD_POOLS as u8;
+        match op {
+            Op::Deposit {
+                pool,
+                nonce,
+                amount,
+                lockup_duration,
+                token_authority,
+            } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                let staker = self.attacker(*pool);
+                let owner = staker.owner.pubkey();
+                let token_authority = token_authority
+                    .map(|token_authority| self.attacker(token_authority).owner.pubkey());
+                instructions::deposit(
+                    &self.pool(*pool),
+                    &state,
+                    &owner,
+                    &owner,
+                    token_authority.as_ref(),
+                    &staker.tokens,
+                    &staker.lp,
+                    self.token_program(*pool),
+                    DepositArgs {
+                        nonce: u32::from(nonce % ATTACKER_NONCES),
+                        amount: u64::from(*amount),
+                        lockup_duration: u64::from(*lockup_duration),
+                        allowlist_proof: None,
+                    },
+                )
+            }
+            Op::ClaimAll { pool, receipt } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                let staker = self.attacker(*pool);
+                instructions::claim_all(
+                    &self.pool(*pool),
+                    &state,
+                    &staker.owner.pubkey(),
+                    &self.receipt(*receipt),
+                    self.token_program(*pool),
+                    self.reward_mints(*pool),
+                    &staker.reward_accounts,
+                )
+                .unwrap()
+            }
+            Op::ClaimMany { pool, receipts } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                let staker = self.attacker(*pool);
+                let receipts: Vec<Pubkey> = receipts
+                    .iter()
+                    .map(|receipt| self.receipt(*receipt))
+                    .collect();
+                instructions::claim_many(
+                    &self.pool(*pool),
+                    &state,
+                    &staker.owner.pubkey(),
+                    &receipts,
+                    self.reward_mints(*pool),
+                    &staker.reward_accounts,
+                )
+                .unwrap()
+            }
+            Op::Withdraw {
+                pool,
+                receipt,
+                lp_from,
+            } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                let staker = self.attacker(*pool);
+                let lp_holder = lp_from.map(|lp_from| self.attacker(lp_from));
+                instructions::withdraw(
+                    &self.pool(*pool),
+                    &state,
+                    &staker.owner.pubkey(),
+                    &self.receipt(*receipt),
+                    &lp_holder.unwrap_or(staker).lp,
+                    lp_holder.map(|lp_holder| lp_holder.owner.pubkey()).as_ref(),
+                    &staker.tokens,
+                    &staker.owner.pubkey(),
+                    self.token_program(*pool),
+                    self.reward_mints(*pool),
+                    &staker.reward_accounts,
+                )
+                .unwrap()
+            }
+            Op::WithdrawMany { pool, receipts } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                let staker = self.attacker(*pool);
+                let receipts: Vec<Pubkey> = receipts
+                    .iter()
+                    .map(|receipt| self.receipt(*receipt))
+                    .collect();
+                instructions::withdraw_many(
+                    &self.pool(*pool),
+                    &state,
+                    &staker.owner.pubkey(),
+                    &receipts,
+                    &staker.lp,
+                    None,
+                    &staker.tokens,
+                    &staker.owner.pubkey(),
+                    self.token_program(*pool),
+                    self.reward_mints(*pool),
+                    &staker.reward_accounts,
+                )
+                .unwrap()
+            }
+            Op::FundRewardPool {
+                pool,
+                index,
+                amount,
+            } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                let staker = self.attacker(*pool);
+                instructions::fund_reward_pool(
+                    &self.pool(*pool),
+                    &state,
+                    &staker.owner.pubkey(),
+                    &staker.reward_accounts[0],
+                    &self.reward_mints(*pool)[0],
+                    reward_index(index),
+                    u64::from(*amount),
+                )
+            }
+            Op::AddRewardPool { pool, index } => instructions::add_reward_pool(
+                &self.pool(*pool),
+                &attacker,
+                &attacker,
+                &self.reward_mints[0][1],
+                reward_index(index),
+            ),
+            Op::SetFlags { pool, flags } => {
+                instructions::set_flags(&self.pool(*pool), &attacker, *flags)
+            }
+            Op::TransferAuthority { pool } => {
+                instructions::transfer_authority(&self.pool(*pool), &attacker, &attacker)
+            }
+            Op::DangerouslyMintLp { pool, amount } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                instructions::dangerously_mint_lp(
+                    &self.pool(*pool),
+                    &state,
+                    &attacker,
+                    &self.attacker(*pool).lp,
+                    self.token_program(*pool),
+                    u64::from(*amount),
+                    "fuzz".to_string(),
+                )
+            }
+            Op::BurnUnbackedLp { pool, amount } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                instructions::burn_unbacked_lp(
+                    &self.pool(*pool),
+                    &state,
+                    &self.attacker(*pool).lp,
+                    self.token_program(*pool),
+                    u64::from(*amount),
+                    "fuzz".to_string(),
+                )
+            }
+            Op::SetUnbackedLpCap { pool, cap } => {
+                instructions::set_unbacked_lp_cap(&self.pool(*pool), &attacker, u64::from(*cap))
+            }
+            Op::SetUnbackedLpCapAuthority { pool } => {
+                instructions::set_unbacked_lp_cap_authority(&self.pool(*pool), &attacker, &attacker)
+            }
+            Op::SetRewardPoolPolicy {
+                pool,
+                index,
+                policy,
+                release_period,
+            } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                instructions::set_reward_pool_policy(
+                    &self.pool(*pool),
+                    &state,
+                    Some(&self.attacker(*pool).reward_accounts[0]),
+                    reward_index(index),
+                    *policy,
+                    u64::from(*release_period),
+                )
+            }
+            Op::SweepUndistributed { pool, index } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                instructions::sweep_undistributed(
+                    &self.pool(*pool),
+                    &state,
+                    &self.reward_mints(*pool)[0],
+                    reward_index(index),
+                )
+            }
+            Op::SweepRewardDust { pool, index } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                instructions::sweep_reward_dust(
+                    &self.pool(*pool),
+                    &state,
+                    &self.attacker(*pool).reward_accounts[0],
+                    &self.reward_mints(*pool)[0],
+                    reward_index(index),
+                )
+            }
+            Op::UpdateTokenMeta { pool } => {
+                let state = self.test.stake_pool(&self.pool(*pool)).await;
+                instructions::update_token_meta(
+                    &self.pool(*pool),
+                    &state,
+                    "Staked".to_string(),
+                    "STK".to_string(),
+                    String::new(),
+                )
+            }
+        }
+    }
+
+    async fn apply(&mut self, step: &Step) {
+        let mut ix = self.instruction(&step.op).await;
+        for substitution in step.substitutions.iter().take(MAX_SUBSTITUTIONS) {
+            let account = usize::from(substitution.account) % ix.accounts.len();
+            if !ix.accounts[account].is_signer {
+                ix.accounts[account].pubkey = self.known_accounts
+                    [usize::from(substitution.replacement) % self.known_accounts.len()];
+            }
+        }
+        let keep = ix
+            .accounts
+            .len()
+            .saturating_sub(usize::from(step.truncate % 4));
+        ix.accounts.truncate(keep);
+
+        // the attacker signs in place of any authority it does not hold
+        let payer = self.test.payer();
+        let attacker_keys = self
+            .attackers
+            .each_ref()
+            .map(|staker| staker.owner.pubkey());
+        for meta in ix.accounts.iter_mut().filter(|meta| meta.is_signer) {
+            if meta.pubkey != payer && !attacker_keys.contains(&meta.pubkey) {
+                meta.pubkey = attacker_keys[0];
+            }
+        }
+        let signers: Vec<Keypair> = self
+            .attackers
+            .iter()
+            .filter(|staker| {
+                ix.accounts
+                    .iter()
+                    .any(|meta| meta.is_signer && meta.pubkey == staker.owner.pubkey())
+            })
+            .map(|staker| staker.owner.insecure_clone())
+            .collect();
+
+        self.test.advance_clock(i64::from(step.elapsed));
+        // rejections are expected, only the state they leave behind matters
+        let _ = self
+            .test
+            .process(&[ix], &signers.iter().collect::<Vec<_>>())
+            .await;
+        self.check_invariants().await;
+    }
+
+    /// Receipts that currently exist in `pool`
+    async fn live_receipts(&mut self, pool: Pubkey) -> Vec<(Pubkey, StakeDepositReceipt)> {
+        let mut candidates = self.victim_receipts.to_vec();
+        for nonce in 0..ATTACKER_NONCES {
+            for side in [Pool::A, Pool::B] {
+                candidates.push(self.receipt(Receipt::Attacker(side, nonce)));
+            }
+        }
+        let mut receipts = vec![];
+        for address in candidates {
+            let Some(data) = self.test.account_data(&address).await else {
+                continue;
+            };
+            if let Ok(receipt) = StakeDepositReceipt::try_deserialize(&mut &data[..]) {
+                if receipt.stake_pool == pool {
+                    receipts.push((address, receipt));
+                }
+            }
+        }
+        receipts
+    }
+
+    async fn claimable(&mut self, pool: Pubkey, receipt: Pubkey) -> [u64; MAX_REWARD_POOLS] {
+        let state = self.test.stake_pool(&pool).await;
+        let return_data = self
+            .test
+            .simulate_return_data(&[instructions::get_claimable(&pool, &state, &receipt)])
+            .await;
+        instructions::decode_claimable(&return_data).unwrap()
+    }
+
+    async fn check_invariants(&mut self) {
+        for (side, pool) in self.pools.into_iter().enumerate() {
+            let state = self.test.stake_pool(&pool).await;
+            let receipts = self.live_receipts(pool).await;
+
+            let principal: u64 = receipts
+                .iter()
+                .map(|(_, receipt)| receipt.deposit_amount)
+                .sum();
+            let vault_amount = self.test.token_balance(&state.vault).await;
+            assert!(
+                vault_amount >= principal,
+                "vault of pool {side} holds {vault_amount} of {principal} deposited"
+            );
+            assert_eq!(
+                state.total_deposit_amount, principal,
+                "pool {side} tracks {} deposited over receipts holding {principal}",
+                state.total_deposit_amount
+            );
+
+            let attacker_deposits: u64 = receipts
+                .iter()
+                .filter(|(_, receipt)| receipt.owner == self.attackers[side].owner.pubkey())
+                .map(|(_, receipt)| receipt.deposit_amount)
+                .sum();
+            let attacker_tokens = self.test.token_balance(&self.attackers[side].tokens).await;
+            assert!(
+                attacker_tokens + attacker_deposits <= STAKE,
+                "attacker holds {attacker_tokens} and deposited {attacker_deposits} of {STAKE} in pool {side}"
+            );
+
+            let mut owed = [0u64; MAX_REWARD_POOLS];
+            for (address, _) in &receipts {
+                let claimable = self.claimable(pool, *address).await;
+                for (owed, claimable) in owed.iter_mut().zip(claimable) {
+                    *owed += claimable;
+                }
+                if *address == self.victim_receipts[side] {
+                    for (index, (before, now)) in self.victim_claimable[side]
+                        .iter()
+                        .zip(claimable)
+                        .enumerate()
+                    {
+                        assert!(
+                            now >= *before,
+                            "victim claimable on reward pool {index} of pool {side} fell from {before} to {now}"
+                        );
+                    }
+                    self.victim_claimable[side] = claimable;
+                }
+            }
+            assert!(
+                receipts
+                    .iter()
+                    .any(|(address, _)| *address == self.victim_receipts[side]),
+                "victim receipt of pool {side} is gone"
+            );
+            for (index, reward_pool) in state.reward_pools.iter().enumerate() {
+                if reward_pool.is_empty() {
+                    continue;
+                }
+                let vault_amount = self.test.token_balance(&reward_pool.reward_vault).await;
+                assert!(
+                    vault_amount >= owed[index],
+                    "reward pool {index} of pool {side} holds {vault_amount} of {} owed",
+                    owed[index]
+                );
+            }
+        }
+    }
+}
+
+fuzz_target!(
+    init: {
+        // program-test logs every instruction and warp otherwise
+        std::env::set_var("RUST_LOG", "off");
+    },
+    |steps: Vec<Step>| {
+        HARNESS.with_borrow_mut(|harness| {
+            let (runtime, test) = harness.get_or_insert_with(|| {
+                let runtime = Builder::new_current_thread().enable_all().build().unwrap();
+                let test = runtime.block_on(TestContext::new());
+                (runtime, test)
+            });
+            runtime.block_on(async {
+                let mut world = World::new(test).await;
+                for step in steps.iter().take(MAX_STEPS) {
+                    world.apply(step).await;
+                }
+            });
+        });
+    }
+);
@@ -0,0 +1,270 @@
+//! Pure stake and reward math of the program: stake weights, effective stake, LP amounts,
+//! reward per effective stake accrual and claims. It works on plain integers, uses nothing
+//! beyond `core` and the wide integers of `math`, and fails with `AccountingError` rather
+//! than `ErrorCode`, so it builds without Anchor or `std`. `state` applies it to the
+//! accounts, mapping `AccountingError` onto `ErrorCode`, and off-chain code reaches it
+//! through those same methods.
+
+use core::result;
+
+use crate::math::{U192, U256};
+
+pub const SCALE_FACTOR_BASE: u64 = 1_000_000_000;
+pub const SCALE_FACTOR_BASE_SQUARED: u64 = 1_000_000_000_000_000_000;
+
+/// Failures of the stake and reward math, each raised by the program as the `ErrorCode`
+/// of the same name
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AccountingError {
+    MathOverflow,
+    MathUnderflow,
+    PrecisionMath,
+    ConversionFailure,
+    DurationTooShort,
+    InvalidStakePoolDuration,
+    InvalidStakePoolWeight,
+}
+
+pub type Result<T> = result::Result<T, AccountingError>;
+
+/// Applies a `checked_*` arithmetic method, mapping `None` to the given `AccountingError`
+macro_rules! checked {
+    ($lhs:expr, $method:ident($rhs:expr), $error:ident) => {
+        $lhs.$method($rhs).ok_or(AccountingError::$error)
+    };
+}
+
+fn cast<S, T: TryFrom<S>>(value: S) -> Result<T> {
+    T::try_from(value).map_err(|_| AccountingError::ConversionFailure)
+}
+
+/// Weight of a lockup of `duration` seconds, rising linearly from `base_weight` at
+/// `min_duration` to `max_weight` at `max_duration`
+pub fn get_stake_weight(
+    base_weight: u64,
+    max_weight: u64,
+    min_duration: u64,
+    max_duration: u64,
+    duration: u64,
+) -> Result<u64> {
+    let duration_exceeding_min = checked!(duration, checked_sub(min_duration), DurationTooShort)?;
+
+    let duration_span = checked!(
+        max_duration,
+        checked_sub(min_duration),
+        InvalidStakePoolDuration
+    )?;
+    if duration_span == 0 {
+        return Ok(base_weight);
+    }
+
+    let duration_exceeding_min = u64::min(duration_exceeding_min, duration_span);
+
+    let scaled_duration = checked!(
+        U192::from(duration_exceeding_min),
+        checked_mul(U192::from(SCALE_FACTOR_BASE)),
+        MathOverflow
+    )?;
+    let normalized_weight = checked!(
+        scaled_duration,
+        checked_div(U192::from(duration_span)),
+        PrecisionMath
+    )?;
+    let weight_diff = checked!(
+        U192::from(max_weight),
+        checked_sub(U192::from(base_weight)),
+        InvalidStakePoolWeight
+    )?;
+    let scaled_weight_increase =
+        checked!(normalized_weight, checked_mul(weight_diff), MathOverflow)?;
+    let weight_increase = checked!(
+        scaled_weight_increase,
+        checked_div(U192::from(SCALE_FACTOR_BASE)),
+        PrecisionMath
+    )?;
+    let calculated_weight = checked!(
+        U192::from(base_weight),
+        checked_add(weight_increase),
+        MathOverflow
+    )?;
+
+    Ok(u64::max(cast(calculated_weight)?, base_weight))
+}
+
+pub fn get_effective_stake_amount(weight: u64, amount: u64) -> Result<u128> {
+    checked!(
+        u128::from(amount),
+        checked_mul(u128::from(weight)),
+        MathOverflow
+    )
+}
+
+/// Decimal digits LP amounts are shifted down by so the largest effective stake under
+/// `max_weight` still fits a `u64`
+pub fn get_digit_shift_by_max_scalar(max_weight: u64) -> Result<u8> {
+    let max_scaled_stake = checked!(
+        u128::from(max_weight),
+        checked_mul(u128::from(u64::MAX)),
+        MathOverflow
+    )?;
+    let max_stake_tokens = checked!(
+        max_scaled_stake,
+        checked_div(u128::from(SCALE_FACTOR_BASE)),
+        PrecisionMath
+    )?;
+    let mut digit_shift = 0u32;
+    while checked!(
+        max_stake_tokens,
+        checked_div(checked!(10u128, checked_pow(digit_shift), MathOverflow)?),
+        PrecisionMath
+    )? > u128::from(u64::MAX)
+    {
+        digit_shift += 1;
+    }
+    cast(digit_shift)
+}
+
+/// LP amount minted for, and burned against, `effective_stake`
+pub fn get_token_amount_from_stake(effective_stake: u128, max_weight: u64) -> Result<u64> {
+    let digit_shift = get_digit_shift_by_max_scalar(max_weight)?;
+    let stake_tokens = checked!(
+        effective_stake,
+        checked_div(u128::from(SCALE_FACTOR_BASE)),
+        PrecisionMath
+    )?;
+    let shifted_stake_tokens = checked!(
+        stake_tokens,
+        checked_div(checked!(
+            10u128,
+            checked_pow(u32::from(digit_shift)),
+            MathOverflow
+        )?),
+        PrecisionMath
+    )?;
+    cast(shifted_stake_tokens)
+}
+
+/// Spreads `amount` over `total_weighted_stake`, carrying the scaled `remainder` of earlier
+/// accruals. Returns the new rewards per effective stake and remainder.
+pub fn accrue_rewards_per_effective_stake(
+    rewards_per_effective_stake: u128,
+    remainder: u128,
+    amount: u64,
+    total_weighted_stake: u128,
+) -> Result<(u128, u128)> {
+    let scaled_amount = checked!(
+        u128::from(amount),
+        checked_mul(u128::from(SCALE_FACTOR_BASE_SQUARED)),
+        MathOverflow
+    )?;
+    let scaled_amount = checked!(scaled_amount, checked_add(remainder), MathOverflow)?;
+
+    let additional_rewards_per_effective_stake = checked!(
+        scaled_amount,
+        checked_div(total_weighted_stake),
+        PrecisionMath
+    )?;
+    let remainder = checked!(
+        scaled_amount,
+        checked_rem(total_weighted_stake),
+        PrecisionMath
+    )?;
+    let rewards_per_effective_stake = checked!(
+        rewards_per_effective_stake,
+        checked_add(additional_rewards_per_effective_stake),
+        MathOverflow
+    )?;
+    Ok((rewards_per_effective_stake, remainder))
+}
+
+/// Rewards `effective_stake` can claim between `claimed_rewards_per_effective_stake` and
+/// `rewards_per_effective_stake`, along with the scaled dust the claim floors away
+pub fn get_claimable(
+    rewards_per_effective_stake: u128,
+    claimed_rewards_per_effective_stake: u128,
+    effective_stake: u128,
+) -> Result<(u64, u128)> {
+    let claimable_per_effective_stake = checked!(
+        rewards_per_effective_stake,
+        checked_sub(claimed_rewards_per_effective_stake),
+        MathUnderflow
+    )?;
+    // Note: Cannot overflow, 2^128 * 2^128 < 2^256
+    let scaled_claimable = checked!(
+        U256::from(claimable_per_effective_stake),
+        checked_mul(U256::from(effective_stake)),
+        MathOverflow
+    )?;
+    let scale = U256::from(SCALE_FACTOR_BASE_SQUARED);
+    let claimable = checked!(scaled_claimable, checked_div(scale), PrecisionMath)?;
+    let dust = checked!(scaled_claimable, checked_rem(scale), PrecisionMath)?;
+    Ok((cast(claimable)?, cast(dust)?))
+}
+
+/// Portion of `release_amount` unlocked `elapsed` seconds into a linear release over
+/// `release_period` seconds
+pub fn get_unlocked_amount(release_amount: u64, elapsed: u64, release_period: u64) -> Result<u64> {
+    if elapsed >= release_period {
+        return Ok(release_amount);
+    }
+    let scaled_unlocked = checked!(
+        u128::from(release_amount),
+        checked_mul(u128::from(elapsed)),
+        MathOverflow
+    )?;
+    let unlocked = checked!(
+        scaled_unlocked,
+        checked_div(u128::from(release_period)),
+        PrecisionMath
+    )?;
+    cast(unlocked)
+}
+
+/// Whole tokens of the scaled dust accumulated by claims
+pub fn get_whole_tokens(scaled_amount: u128) -> Result<u64> {
+    cast(scaled_amount / u128::from(SCALE_FACTOR_BASE_SQUARED))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn get_stake_weight_errors() {
+        let base_weight = SCALE_FACTOR_BASE;
+        assert_eq!(
+            get_stake_weight(base_weight, 2 * base_weight, 100, 200, 99),
+            Err(AccountingError::DurationTooShort)
+        );
+        assert_eq!(
+            get_stake_weight(base_weight, 2 * base_weight, 200, 100, 200),
+            Err(AccountingError::InvalidStakePoolDuration)
+        );
+        assert_eq!(
+            get_stake_weight(base_weight, base_weight - 1, 100, 200, 150),
+            Err(AccountingError::InvalidStakePoolWeight)
+        );
+    }
+
+    #[test]
+    fn accrue_rewards_per_effective_stake_carries_remainder() {
+        let scale = u128::from(SCALE_FACTOR_BASE_SQUARED);
+        let (rewards_per_effective_stake, remainder) =
+            accrue_rewards_per_effective_stake(0, 0, 1, 3).unwrap();
+        assert_eq!(rewards_per_effective_stake, scale / 3);
+        assert_eq!(remainder, scale % 3);
+
+        let (rewards_per_effective_stake, remainder) =
+            accrue_rewards_per_effective_stake(rewards_per_effective_stake, remainder, 2, 3)
+                .unwrap();
+        assert_eq!(rewards_per_effective_stake, scale);
+        assert_eq!(remainder, 0);
+    }
+
+    #[test]
+    fn get_claimable_floors_into_dust() {
+        let scale = u128::from(SCALE_FACTOR_BASE_SQUARED);
+        assert_eq!(get_claimable(scale / 3, 0, 3).unwrap(), (0, scale - 1));
+        assert_eq!(get_claimable(0, 1, 1), Err(AccountingError::MathUnderflow));
+    }
+}
@@ -0,0 +1,143 @@
+//! Merkle allowlist of the owners who may deposit into a StakePool. Each leaf commits to an
+//! owner and the most it may deposit, parents hash their children in sorted order so a proof
+//! is just the sibling hashes from the leaf up. Leaves and parents are hashed with distinct
+//! prefixes so a parent can never pass for a leaf.
+
+use anchor_lang::prelude::*;
+use anchor_lang::solana_program::keccak;
+
+use crate::{errors::ErrorCode, state::StakePool};
+
+const LEAF_PREFIX: &[u8] = &[0];
+const NODE_PREFIX: &[u8] = &[1];
+
+/// Proof passed to `deposit` that the owner is in the StakePool's allowlist
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Debug, Default, PartialEq, Eq)]
+pub struct AllowlistProof {
+    /// `max_amount` of the owner's leaf, the most its deposits may add up to, zero for no
+    /// limit
+    pub max_amount: u64,
+    /// Sibling hashes from the owner's leaf up to the root
+    pub proof: Vec<[u8; 32]>,
+}
+
+impl AllowlistProof {
+    /// Root the proof hashes up to for `owner`
+    pub fn root(&self, owner: &Pubkey) -> [u8; 32] {
+        self.proof
+            .iter()
+            .fold(allowlist_leaf(owner, self.max_amount), |node, sibling| {
+                allowlist_node(&node, sibling)
+            })
+    }
+}
+
+pub fn allowlist_leaf(owner: &Pubkey, max_amount: u64) -> [u8; 32] {
+    keccak::hashv(&[LEAF_PREFIX, owner.as_ref(), &max_amount.to_le_bytes()]).to_bytes()
+}
+
+pub fn allowlist_node(a: &[u8; 32], b: &[u8; 32]) -> [u8; 32] {
+    let (left, right) = if a <= b { (a, b) } else { (b, a) };
+    keccak::hashv(&[NODE_PREFIX, left, right]).to_bytes()
+}
+
+impl StakePool {
+    pub fn has_deposit_allowlist(&self) -> bool {
+        self.deposit_allowlist_root != [0; 32]
+    }
+
+    /// Checks `owner` against the deposit allowlist, returning the `max_amount` of its leaf,
+    /// or `None` when the pool has no allowlist and any owner may deposit. The owner must sign
+    /// allowlisted deposits, nobody else may draw down its allowance.
+    pub fn validate_deposit_allowlist(
+        &self,
+        owner: &Pubkey,
+        owner_signed: bool,
+        proof: Option<&AllowlistProof>,
+    ) -> Result<Option<u64>> {
+        if !self.has_deposit_allowlist() {
+            return Ok(None);
+        }
+        require!(owner_signed, ErrorCode::MissingOwnerSignature);
+        match proof {
+            Some(proof) if proof.root(owner) == self.deposit_allowlist_root => {
+                Ok(Some(proof.max_amount))
+            }
+            _ => err!(ErrorCode::DepositNotAllowlisted),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bytemuck::Zeroable;
+
+    #[test]
+    fn allowlist_proof_root() {
+        let owners: Vec<Pubkey> = (0..3).map(|_| Pubkey::new_unique()).collect();
+        let leaves: Vec<[u8; 32]> = owners
+            .iter()
+            .zip([0, 100, 200])
+            .map(|(owner, max_amount)| allowlist_leaf(owner, max_amount))
+            .collect();
+        // the odd leaf is carried up unpaired
+        let root = allowlist_node(&allowlist_node(&leaves[0], &leaves[1]), &leaves[2]);
+
+        let proof = AllowlistProof {
+            max_amount: 100,
+            proof: vec![leaves[0], leaves[2]],
+        };
+        assert_eq!(proof.root(&owners[1]), root);
+        let proof = AllowlistProof {
+            max_amount: 200,
+            proof: vec![allowlist_node(&leaves[1], &leaves[0])],
+        };
+        assert_eq!(proof.root(&owners[2]), root);
+
+        // a different amount or owner hashes to another root
+        let proof = AllowlistProof {
+            max_amount: 101,
+            proof: vec![leaves[0], leaves[2]],
+        };
+        assert_ne!(proof.root(&owners[1]), root);
+        let proof = AllowlistProof {
+            max_amount: 100,
+            proof: vec![leaves[0], leaves[2]],
+        };
+        assert_ne!(proof.root(&owners[0]), root);
+    }
+
+    #[test]
+    fn validate_deposit_allowlist() {
+        let owner = Pubkey::new_unique();
+        let other = Pubkey::new_unique();
+        let mut stake_pool = StakePool::zeroed();
+        assert_eq!(
+            stake_pool.validate_deposit_allowlist(&owner, true, None),
+            Ok(None)
+        );
+
+        let proof = AllowlistProof {
+            max_amount: 50,
+            proof: vec![allowlist_leaf(&other, 0)],
+        };
+        stake_pool.deposit_allowlist_root = proof.root(&owner);
+        assert_eq!(
+            stake_pool.validate_deposit_allowlist(&owner, true, Some(&proof)),
+            Ok(Some(50))
+        );
+        assert_eq!(
+            stake_pool.validate_deposit_allowlist(&owner, true, None),
+            Err(ErrorCode::DepositNotAllowlisted.into())
+        );
+        assert_eq!(
+            stake_pool.validate_deposit_allowlist(&other, true, Some(&proof)),
+            Err(ErrorCode::DepositNotAllowlisted.into())
+        );
+        assert_eq!(
+            stake_pool.validate_deposit_allowlist(&owner, false, Some(&proof)),
+            Err(ErrorCode::MissingOwnerSignature.into())
+        );
+    }
+}
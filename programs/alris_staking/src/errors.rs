@@ -1,4 +1,6 @@
-use anchor_lang::error_code;
+use anchor_lang::{error, error_code};
+
+use crate::accounting::AccountingError;
 
 #[error_code]
 pub enum ErrorCode {
@@ -29,5 +31,109 @@ pub enum ErrorCode {
     #[msg("Duration too short")]
     DurationTooShort, // 6012
     #[msg("Deposits disabled by administrator")]
-    DepositsDisabled, // TODO
+    DepositsDisabled, // 6013
+    #[msg("RewardPool index is out of range or empty")]
+    InvalidRewardPoolIndex, // 6014
+    #[msg("Cannot fund rewards while nothing is staked")]
+    NoStakeToReward, // 6015
+    #[msg("Amount must be greater than zero")]
+    InvalidAmount, // 6016
+    #[msg("Unknown undistributed rewards policy")]
+    InvalidUndistributedPolicy, // 6017
+    #[msg("Invalid undistributed rewards recipient")]
+    InvalidUndistributedRecipient, // 6018
+    #[msg("Math overflow")]
+    MathOverflow, // 6019
+    #[msg("Math underflow")]
+    MathUnderflow, // 6020
+    #[msg("Number conversion failed")]
+    ConversionFailure, // 6021
+    #[msg("Reward vault balance is below its last recorded amount")]
+    RewardVaultBalanceDecreased, // 6022
+    #[msg("Token account mint does not match the StakePool mint")]
+    InvalidStakeTokenMint, // 6023
+    #[msg("Token account mint does not match the StakePool lp mint")]
+    InvalidLpTokenMint, // 6024
+    #[msg("Lp token account is not owned by the StakeDepositReceipt owner or LP authority")]
+    InvalidLpTokenOwner, // 6025
+    #[msg("Reward recipient mint does not match the RewardPool vault")]
+    InvalidRewardRecipientMint, // 6026
+    #[msg("Reward recipient is not owned by the StakeDepositReceipt owner")]
+    InvalidRewardRecipientOwner, // 6027
+    #[msg("Mint has a token extension the StakePool does not support")]
+    UnsupportedMintExtension, // 6028
+    #[msg("Token program does not own the mint or token account")]
+    InvalidTokenProgram, // 6029
+    #[msg("Reward mint does not match the RewardPool vault")]
+    InvalidRewardMint, // 6030
+    #[msg("Unknown StakePool option")]
+    InvalidStakePoolOptions, // 6031
+    #[msg("Metadata program or account does not match the stake mint")]
+    InvalidMetadataAccount, // 6032
+    #[msg("Minting would exceed the StakePool unbacked LP cap")]
+    UnbackedLpCapExceeded, // 6033
+    #[msg("Burn exceeds the unbacked LP tokens outstanding")]
+    UnbackedLpBurnExceedsMinted, // 6034
+    #[msg("Reason must be between 1 and 200 bytes")]
+    InvalidUnbackedLpReason, // 6035
+    #[msg("No StakeDepositReceipt was passed")]
+    MissingStakeDepositReceipts, // 6036
+    #[msg("StakeDepositReceipt was passed more than once")]
+    DuplicateStakeDepositReceipt, // 6037
+    #[msg("Rent recipient is not the account the StakeDepositReceipt rent is refunded to")]
+    InvalidRentRecipient, // 6038
+    #[msg("Deposit token account is not held by the StakeDepositReceipt owner")]
+    InvalidDepositSourceOwner, // 6039
+    #[msg("Deposit would exceed the StakePool total deposit cap")]
+    TotalDepositCapExceeded, // 6040
+    #[msg("Deposit would exceed the StakePool per owner deposit cap")]
+    OwnerDepositCapExceeded, // 6041
+    #[msg("Deposit is below the StakePool minimum deposit amount")]
+    DepositTooSmall, // 6042
+    #[msg("Deposit is too small to mint any LP tokens")]
+    ZeroLpDeposit, // 6043
+    #[msg("Owner is not in the StakePool deposit allowlist")]
+    DepositNotAllowlisted, // 6044
+    #[msg("DepositAllowance is required while the StakePool has a deposit allowlist")]
+    MissingDepositAllowance, // 6045
+    #[msg("Deposit would exceed the owner's allowlisted amount")]
+    DepositAllowanceExceeded, // 6046
+    #[msg("StakePool is not in the legacy layout")]
+    StakePoolAlreadyMigrated, // 6047
+    #[msg("UserStakeIndex holds as many receipts as it can list")]
+    UserStakeIndexFull, // 6048
+    #[msg("Owner must sign deposits while the StakePool has a deposit allowlist")]
+    MissingOwnerSignature, // 6049
+    #[msg("Owner must authorize deposits while the StakePool has a per owner deposit cap")]
+    MissingOwnerAuthorization, // 6050
+    #[msg("Signer is not the StakePool's unbacked LP cap authority")]
+    InvalidUnbackedLpCapAuthority, // 6051
+}
+
+impl From<AccountingError> for ErrorCode {
+    fn from(error: AccountingError) -> Self {
+        match error {
+            AccountingError::MathOverflow => ErrorCode::MathOverflow,
+            AccountingError::MathUnderflow => ErrorCode::MathUnderflow,
+            AccountingError::PrecisionMath => ErrorCode::PrecisionMath,
+            AccountingError::ConversionFailure => ErrorCode::ConversionFailure,
+            AccountingError::DurationTooShort => ErrorCode::DurationTooShort,
+            AccountingError::InvalidStakePoolDuration => ErrorCode::InvalidStakePoolDuration,
+            AccountingError::InvalidStakePoolWeight => ErrorCode::InvalidStakePoolWeight,
+        }
+    }
+}
+
+impl AccountingError {
+    /// Raises the error as its `ErrorCode` with the inputs that caused it logged, the way
+    /// `checked_math!` logs its operands
+    pub fn with_values(self, values: (impl ToString, impl ToString)) -> anchor_lang::error::Error {
+        error!(ErrorCode::from(self)).with_values(values)
+    }
+}
+
+impl From<AccountingError> for anchor_lang::error::Error {
+    fn from(error: AccountingError) -> Self {
+        error!(ErrorCode::from(error))
+    }
 }
@@ -0,0 +1,196 @@
+use anchor_lang::prelude::*;
+
+#[event]
+pub struct StakePoolInitialized {
+    pub stake_pool: Pubkey,
+    pub creator: Pubkey,
+    pub authority: Pubkey,
+    pub mint: Pubkey,
+    pub stake_mint: Pubkey,
+    pub vault: Pubkey,
+    pub nonce: u8,
+    pub base_weight: u64,
+    pub max_weight: u64,
+    pub min_duration: u64,
+    pub max_duration: u64,
+    pub options: u8,
+}
+
+#[event]
+pub struct StakePoolMigrated {
+    pub stake_pool: Pubkey,
+    pub authority: Pubkey,
+    pub previous_len: u64,
+    pub len: u64,
+}
+
+#[event]
+pub struct RewardPoolAdded {
+    pub stake_pool: Pubkey,
+    pub index: u8,
+    pub reward_mint: Pubkey,
+    pub reward_vault: Pubkey,
+}
+
+#[event]
+pub struct RewardPoolFunded {
+    pub stake_pool: Pubkey,
+    pub index: u8,
+    pub funder: Pubkey,
+    pub amount: u64,
+    pub total_funded: u64,
+    pub rewards_per_effective_stake: u128,
+}
+
+#[event]
+pub struct RewardPoolPolicyUpdated {
+    pub stake_pool: Pubkey,
+    pub index: u8,
+    pub policy: u8,
+    pub release_period: u64,
+    pub undistributed_recipient: Pubkey,
+}
+
+#[event]
+pub struct UndistributedSwept {
+    pub stake_pool: Pubkey,
+    pub index: u8,
+    pub recipient: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct RewardDustSwept {
+    pub stake_pool: Pubkey,
+    pub index: u8,
+    pub destination: Pubkey,
+    pub amount: u64,
+}
+
+#[event]
+pub struct FlagsUpdated {
+    pub stake_pool: Pubkey,
+    pub previous_flags: u8,
+    pub flags: u8,
+}
+
+#[event]
+pub struct AuthorityTransferred {
+    pub stake_pool: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct LpMinted {
+    pub stake_pool: Pubkey,
+    pub stake_mint: Pubkey,
+    pub destination: Pubkey,
+    pub amount: u64,
+    pub minted_unbacked: u64,
+    pub reason: String,
+}
+
+#[event]
+pub struct UnbackedLpBurned {
+    pub stake_pool: Pubkey,
+    pub stake_mint: Pubkey,
+    pub from: Pubkey,
+    pub amount: u64,
+    pub minted_unbacked: u64,
+    pub reason: String,
+}
+
+#[event]
+pub struct UnbackedLpCapUpdated {
+    pub stake_pool: Pubkey,
+    pub previous_cap: u64,
+    pub cap: u64,
+}
+
+#[event]
+pub struct UnbackedLpCapAuthorityUpdated {
+    pub stake_pool: Pubkey,
+    pub previous_authority: Pubkey,
+    pub new_authority: Pubkey,
+}
+
+#[event]
+pub struct DepositCapsUpdated {
+    pub stake_pool: Pubkey,
+    pub previous_max_total_deposit: u64,
+    pub max_total_deposit: u64,
+    pub previous_max_deposit_per_owner: u64,
+    pub max_deposit_per_owner: u64,
+}
+
+#[event]
+pub struct MinDepositAmountUpdated {
+    pub stake_pool: Pubkey,
+    pub previous_min_deposit_amount: u64,
+    pub min_deposit_amount: u64,
+}
+
+#[event]
+pub struct DepositAllowlistUpdated {
+    pub stake_pool: Pubkey,
+    pub previous_root: [u8; 32],
+    pub root: [u8; 32],
+}
+
+#[event]
+pub struct TokenMetaUpdated {
+    pub stake_pool: Pubkey,
+    pub stake_mint: Pubkey,
+    pub metadata_account: Pubkey,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+    /// `true` when the metadata account was created by this instruction
+    pub created: bool,
+}
+
+#[event]
+pub struct Deposited {
+    pub stake_pool: Pubkey,
+    pub stake_deposit_receipt: Pubkey,
+    pub owner: Pubkey,
+    pub payer: Pubkey,
+    /// Signed the transfer of `amount`, the payer unless the owner or its delegate did
+    pub token_authority: Pubkey,
+    pub amount: u64,
+    pub weight: u64,
+    pub effective_stake: u128,
+    pub lockup_duration: u64,
+    pub deposit_timestamp: i64,
+    /// Stake mint tokens minted to the owner, zero when `DEPOSIT_IGNORES_LP` is set
+    pub lp_minted: u64,
+    pub total_weighted_stake: u128,
+}
+
+#[event]
+pub struct Withdrawn {
+    pub stake_pool: Pubkey,
+    pub stake_deposit_receipt: Pubkey,
+    pub owner: Pubkey,
+    /// Holder of the burned LP tokens, the owner unless another wallet signed for them
+    pub lp_authority: Pubkey,
+    /// Refunded the receipt's rent, see `StakePool::rent_recipient`
+    pub rent_recipient: Pubkey,
+    pub amount: u64,
+    pub effective_stake: u128,
+    /// Stake mint tokens burned from `lp_authority`, zero when `WITHDRAW_IGNORES_LP` is set
+    pub lp_burned: u64,
+    pub escape_hatch_enabled: bool,
+    pub total_weighted_stake: u128,
+}
+
+#[event]
+pub struct RewardsClaimed {
+    pub stake_pool: Pubkey,
+    pub stake_deposit_receipt: Pubkey,
+    pub owner: Pubkey,
+    pub reward_pool_index: u8,
+    pub reward_vault: Pubkey,
+    pub amount: u64,
+}
@@ -1,8 +1,10 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_spl::token_interface::{Mint, TokenAccount, TokenInterface};
 
 use crate::state::{RewardPool, StakePool};
 use crate::errors::ErrorCode;
+use crate::events::RewardPoolAdded;
+use crate::token_extensions::validate_reward_mint_extensions;
 
 #[derive(Accounts)]
 #[instruction(index: u8)]
@@ -10,7 +12,7 @@ pub struct AddRewardPool<'info> {
   #[account(mut)]
   pub payer: Signer<'info>,
   pub authority: Signer<'info>,
-  pub reward_mint: Account<'info, Mint>,
+  pub reward_mint: InterfaceAccount<'info, Mint>,
   #[account(
     mut, 
     has_one = authority @ ErrorCode::InvalidAuthority,
@@ -25,18 +27,29 @@ pub struct AddRewardPool<'info> {
     payer = payer,
     token::mint = reward_mint,
     token::authority = stake_pool,
+    token::token_program = token_program,
   )]
-  pub reward_vault: Account<'info, TokenAccount>,
+  pub reward_vault: InterfaceAccount<'info, TokenAccount>,
 
-  pub token_program: Program<'info, Token>,
+  /// Token program of `reward_mint`, the vault initialization fails if it does not own the mint
+  pub token_program: Interface<'info, TokenInterface>,
   pub rent: Sysvar<'info, Rent>,
   pub system_program: Program<'info, System>,
 }
 
 pub fn handler(ctx: Context<AddRewardPool>, index: u8) -> Result<()> {
+  validate_reward_mint_extensions(&ctx.accounts.reward_mint.to_account_info())?;
+
   let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
   let reward_pool = RewardPool::new(&ctx.accounts.reward_vault.key());
   stake_pool.reward_pools[usize::from(index)] = reward_pool;
 
+  emit!(RewardPoolAdded {
+    stake_pool: ctx.accounts.stake_pool.key(),
+    index,
+    reward_mint: ctx.accounts.reward_mint.key(),
+    reward_vault: ctx.accounts.reward_vault.key(),
+  });
+
   Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,68 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Burn, Mint, TokenAccount, TokenInterface};
+
+use crate::{errors::ErrorCode, events::UnbackedLpBurned, state::StakePool};
+
+use super::dangerously_mint_lp::validate_unbacked_lp_reason;
+
+#[derive(Accounts)]
+pub struct BurnUnbackedLp<'info> {
+    /// Authority of the StakePool, holds `from`
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub stake_mint: InterfaceAccount<'info, Mint>,
+
+    #[account(mut)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+      has_one = stake_mint @ ErrorCode::InvalidStakeMint,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+
+    /// Token program of `stake_mint`
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> BurnUnbackedLp<'info> {
+    pub fn burn_from_authority(&self, amount: u64) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            Burn {
+                mint: self.stake_mint.to_account_info(),
+                from: self.from.to_account_info(),
+                authority: self.authority.to_account_info(),
+            },
+        );
+        token_interface::burn(cpi_ctx, amount)
+    }
+}
+
+/// Burns LP tokens minted by `dangerously_mint_lp`, returning the LP supply towards what
+/// the receipts minted. LP tokens are fungible, so nothing ties `from` to the unbacked mints:
+/// burning receipt backed LP also lowers `minted_unbacked` and frees headroom under
+/// `unbacked_lp_cap`. The LP supply still never exceeds what the open receipts minted plus
+/// the cap, since the burn takes the same amount out of the supply.
+pub fn handler(ctx: Context<BurnUnbackedLp>, amount: u64, reason: String) -> Result<()> {
+    validate_unbacked_lp_reason(&reason)?;
+    let minted_unbacked = ctx
+        .accounts
+        .stake_pool
+        .load_mut()?
+        .record_unbacked_burn(amount)?;
+
+    ctx.accounts.burn_from_authority(amount)?;
+
+    emit!(UnbackedLpBurned {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        stake_mint: ctx.accounts.stake_mint.key(),
+        from: ctx.accounts.from.key(),
+        amount,
+        minted_unbacked,
+        reason,
+    });
+    Ok(())
+}
@@ -10,17 +10,16 @@ pub struct ClaimAll<'info> {
 pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimAll<'info>>) -> Result<()> {
     {
         let mut stake_pool = ctx.accounts.claim_base.stake_pool.load_mut()?;
-        stake_pool.recalculate_rewards_per_effective_stake(&ctx.remaining_accounts, 2usize)?;
+        stake_pool.recalculate_rewards_per_effective_stake(
+            ctx.remaining_accounts,
+            CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL,
+        )?;
     }
 
-    let claimed_amounts = ctx
-        .accounts
-        .claim_base
-        .transfer_all_claimable_rewards(&ctx.remaining_accounts)?;
-
+    let claimed_amounts = ctx.accounts.claim_base.settle_claimable_rewards()?;
     ctx.accounts
         .claim_base
-        .update_reward_pools_last_amount(claimed_amounts)?;
+        .transfer_claimed_rewards(ctx.remaining_accounts, claimed_amounts)?;
 
     Ok(())
 }
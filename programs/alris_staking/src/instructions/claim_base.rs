@@ -1,10 +1,14 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Token, Transfer};
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
 
 use crate::errors::ErrorCode;
-use crate::math::U256;
+use crate::events::RewardsClaimed;
 use crate::stake_pool_signer_seeds;
-use crate::state::{StakeDepositReceipt, StakePool, MAX_REWARD_POOLS, SCALE_FACTOR_BASE_SQUARED};
+use crate::state::{StakeDepositReceipt, StakePool, MAX_REWARD_POOLS};
+
+/// Remaining accounts passed per active reward pool to `claim_all` and `withdraw`: reward
+/// vault, owner's recipient token account, reward mint and the reward mint's token program.
+pub const CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL: usize = 4;
 
 #[derive(Accounts)]
 pub struct ClaimBase<'info> {
@@ -19,111 +23,181 @@ pub struct ClaimBase<'info> {
     )]
     pub stake_deposit_receipt: Account<'info, StakeDepositReceipt>,
 
-    pub token_program: Program<'info, Token>,
+    /// Token program of the StakePool mint. Rewards are paid through the token program
+    /// passed with each reward vault.
+    pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> ClaimBase<'info> {
-    pub fn transfer_reward_from_pool_to_owner(
-        &self,
-        reward_vault_info: AccountInfo<'info>,
-        owner_reward_account_info: AccountInfo<'info>,
-        amount: u64,
-    ) -> Result<()> {
-        let stake_pool = self.stake_pool.load()?;
-        let cpi_ctx = CpiContext {
-            program: self.token_program.to_account_info(),
-            accounts: Transfer {
-                from: reward_vault_info,
-                to: owner_reward_account_info,
-                authority: self.stake_pool.to_account_info(),
-            },
-            remaining_accounts: Vec::new(),
-            signer_seeds: &[stake_pool_signer_seeds!(stake_pool)],
-        };
-        token::transfer(cpi_ctx, amount)
+    /// Settles the receipt against every active reward pool, see `StakePool::settle_claims`
+    pub fn settle_claimable_rewards(&mut self) -> Result<[u64; MAX_REWARD_POOLS]> {
+        let mut stake_pool = self.stake_pool.load_mut()?;
+        stake_pool.settle_claims(&mut self.stake_deposit_receipt)
     }
 
-    pub fn transfer_all_claimable_rewards(
+    pub fn transfer_claimed_rewards(
         &self,
-        remaining_accounts: &[AccountInfo<'info>],
-    ) -> Result<[u64; MAX_REWARD_POOLS]> {
+        remaining_accounts: &'info [AccountInfo<'info>],
+        claimed_amounts: [u64; MAX_REWARD_POOLS],
+    ) -> Result<()> {
+        transfer_claimed_rewards(
+            &self.owner.key(),
+            &self.stake_pool,
+            remaining_accounts,
+            claimed_amounts,
+        )?;
         let stake_pool = self.stake_pool.load()?;
-        let mut remaining_accounts_index: usize = 0;
-        let mut claimed_amounts = [0u64; MAX_REWARD_POOLS];
-        for (index, reward_pool) in stake_pool.reward_pools.iter().enumerate() {
-            if reward_pool.is_empty() {
-                continue;
-            }
-            // indexes for the relevant remaining accounts
-            let reward_vault_account_index = remaining_accounts_index * 2;
-            let owner_account_index = reward_vault_account_index + 1;
-            let len = remaining_accounts.len();
-            if reward_vault_account_index >= len || owner_account_index >= len {
-                msg!(
-                    "Missing at least one reward vault account or owner account. Failed at index {:?}",
-                    remaining_accounts_index
-                );
-                return err!(ErrorCode::InvalidRewardPoolVaultIndex);
-            }
-
-            let reward_vault_info = &remaining_accounts[reward_vault_account_index];
-            let owner_reward_account_info = &remaining_accounts[owner_account_index];
-            // assert that the remaining account indexes and reward pool
-            // indexes line up.
-            if reward_pool.reward_vault != reward_vault_info.key() {
-                msg!(
-                    "expected pool: {:?} but got {:?}",
-                    reward_pool.reward_vault,
-                    reward_vault_info.key()
-                );
-                return err!(ErrorCode::InvalidRewardPoolVault);
-            }
-
-            let claimable_per_effective_stake = reward_pool
-                .rewards_per_effective_stake_u128()
-                .checked_sub(self.stake_deposit_receipt.claimed_amounts[index].as_u128())
-                .unwrap();
-            // Note: Cannot overflow, 2^128 * 2^128 < 2^256
-            let total_claimable = U256::from(claimable_per_effective_stake)
-                .checked_mul(U256::from(
-                    self.stake_deposit_receipt.effective_stake_u128(),
-                ))
-                .unwrap()
-                .checked_div(U256::from(SCALE_FACTOR_BASE_SQUARED))
-                .unwrap()
-                .as_u64();
-            if total_claimable == 0 {
-                remaining_accounts_index += 1;
-                continue;
-            }
+        emit_rewards_claimed(
+            &stake_pool,
+            self.stake_pool.key(),
+            self.stake_deposit_receipt.key(),
+            self.owner.key(),
+            claimed_amounts,
+        );
+        Ok(())
+    }
+}
 
-            self.transfer_reward_from_pool_to_owner(
-                reward_vault_info.to_account_info(),
-                owner_reward_account_info.to_account_info(),
-                total_claimable,
-            )?;
+/// Emits `RewardsClaimed` for every reward pool paying `receipt` a non zero amount
+pub fn emit_rewards_claimed(
+    stake_pool: &StakePool,
+    stake_pool_key: Pubkey,
+    stake_deposit_receipt: Pubkey,
+    owner: Pubkey,
+    claimed_amounts: [u64; MAX_REWARD_POOLS],
+) {
+    for (index, reward_pool) in stake_pool.reward_pools.iter().enumerate() {
+        if reward_pool.is_empty() || claimed_amounts[index] == 0 {
+            continue;
+        }
+        emit!(RewardsClaimed {
+            stake_pool: stake_pool_key,
+            stake_deposit_receipt,
+            owner,
+            reward_pool_index: index as u8,
+            reward_vault: reward_pool.reward_vault,
+            amount: claimed_amounts[index],
+        });
+    }
+}
 
-            claimed_amounts[index] = total_claimable;
+/// Pays `claimed_amounts` out of every active reward pool to `owner`, one transfer per
+/// reward pool. The reward accounts lead `remaining_accounts`, see
+/// `CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL`.
+pub fn transfer_claimed_rewards<'info>(
+    owner: &Pubkey,
+    stake_pool_loader: &AccountLoader<'info, StakePool>,
+    remaining_accounts: &'info [AccountInfo<'info>],
+    claimed_amounts: [u64; MAX_REWARD_POOLS],
+) -> Result<()> {
+    let stake_pool = stake_pool_loader.load()?;
+    let mut remaining_accounts_index: usize = 0;
+    for (index, reward_pool) in stake_pool.reward_pools.iter().enumerate() {
+        if reward_pool.is_empty() {
+            continue;
+        }
+        // indexes for the relevant remaining accounts
+        let reward_vault_account_index =
+            remaining_accounts_index * CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL;
+        let Some(reward_account_infos) = remaining_accounts.get(
+            reward_vault_account_index
+                ..reward_vault_account_index + CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL,
+        ) else {
+            msg!(
+                "Missing at least one reward vault, owner, mint or token program account. Failed at index {:?}",
+                remaining_accounts_index
+            );
+            return err!(ErrorCode::InvalidRewardPoolVaultIndex);
+        };
 
-            remaining_accounts_index += 1;
+        let reward_vault_info = &reward_account_infos[0];
+        // assert that the remaining account indexes and reward pool
+        // indexes line up.
+        if reward_pool.reward_vault != reward_vault_info.key() {
+            msg!(
+                "expected pool: {:?} but got {:?}",
+                reward_pool.reward_vault,
+                reward_vault_info.key()
+            );
+            return err!(ErrorCode::InvalidRewardPoolVault);
         }
-        Ok(claimed_amounts)
-    }
+        let reward_accounts = validate_reward_accounts(owner, reward_account_infos)?;
+        remaining_accounts_index += 1;
 
-    pub fn update_reward_pools_last_amount(
-        &mut self,
-        claimed_amounts: [u64; MAX_REWARD_POOLS],
-    ) -> Result<()> {
-        let mut stake_pool = self.stake_pool.load_mut()?;
-        for (index, reward_pool) in stake_pool.reward_pools.iter_mut().enumerate() {
-            if reward_pool.is_empty() {
-                continue;
-            }
-            let claimed = claimed_amounts[index];
-            reward_pool.last_amount = reward_pool.last_amount.checked_sub(claimed).unwrap();
-            self.stake_deposit_receipt.claimed_amounts[index] =
-                reward_pool.rewards_per_effective_stake;
+        let total_claimable = claimed_amounts[index];
+        if total_claimable == 0 {
+            continue;
         }
-        Ok(())
+        transfer_reward_from_pool_to_owner(
+            stake_pool_loader,
+            &stake_pool,
+            &reward_accounts,
+            total_claimable,
+        )?;
     }
+    Ok(())
+}
+
+fn transfer_reward_from_pool_to_owner<'info>(
+    stake_pool_loader: &AccountLoader<'info, StakePool>,
+    stake_pool: &StakePool,
+    reward_accounts: &RewardAccounts<'info>,
+    amount: u64,
+) -> Result<()> {
+    let cpi_ctx = CpiContext {
+        program: reward_accounts.token_program.to_account_info(),
+        accounts: TransferChecked {
+            from: reward_accounts.reward_vault.to_account_info(),
+            mint: reward_accounts.reward_mint.to_account_info(),
+            to: reward_accounts.recipient.to_account_info(),
+            authority: stake_pool_loader.to_account_info(),
+        },
+        remaining_accounts: Vec::new(),
+        signer_seeds: &[stake_pool_signer_seeds!(stake_pool)],
+    };
+    token_interface::transfer_checked(cpi_ctx, amount, reward_accounts.reward_mint.decimals)
+}
+
+/// Rewards may only be paid to a token account of the reward vault's mint held by the
+/// owner, through the token program that owns the vault
+fn validate_reward_accounts<'info>(
+    owner: &Pubkey,
+    reward_account_infos: &'info [AccountInfo<'info>],
+) -> Result<RewardAccounts<'info>> {
+    let reward_vault = InterfaceAccount::<TokenAccount>::try_from(&reward_account_infos[0])?;
+    let recipient = InterfaceAccount::<TokenAccount>::try_from(&reward_account_infos[1])?;
+    let reward_mint = InterfaceAccount::<Mint>::try_from(&reward_account_infos[2])?;
+    let token_program = &reward_account_infos[3];
+    require!(
+        recipient.mint == reward_vault.mint,
+        ErrorCode::InvalidRewardRecipientMint
+    );
+    require!(
+        recipient.owner == *owner,
+        ErrorCode::InvalidRewardRecipientOwner
+    );
+    require_keys_eq!(
+        reward_mint.key(),
+        reward_vault.mint,
+        ErrorCode::InvalidRewardMint
+    );
+    require_keys_eq!(
+        token_program.key(),
+        *reward_account_infos[0].owner,
+        ErrorCode::InvalidTokenProgram
+    );
+    Ok(RewardAccounts {
+        reward_vault,
+        recipient,
+        reward_mint,
+        token_program,
+    })
+}
+
+/// Accounts paying out one reward pool, see `CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL`
+pub struct RewardAccounts<'info> {
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+    pub recipient: InterfaceAccount<'info, TokenAccount>,
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+    pub token_program: &'info AccountInfo<'info>,
 }
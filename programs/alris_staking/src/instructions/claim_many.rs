@@ -0,0 +1,92 @@
+use anchor_lang::prelude::*;
+
+use crate::checked_math;
+use crate::errors::ErrorCode;
+use crate::state::{StakeDepositReceipt, StakePool, MAX_REWARD_POOLS};
+
+use super::claim_base::*;
+
+#[derive(Accounts)]
+pub struct ClaimMany<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+}
+
+impl<'info> ClaimMany<'info> {
+    /// Settles one of the owner's receipts and writes it back, so a receipt passed twice
+    /// has nothing left to claim the second time
+    pub fn settle_receipt(
+        &self,
+        receipt_info: &'info AccountInfo<'info>,
+    ) -> Result<[u64; MAX_REWARD_POOLS]> {
+        require!(
+            receipt_info.is_writable,
+            anchor_lang::error::ErrorCode::ConstraintMut
+        );
+        let mut stake_deposit_receipt = Account::<StakeDepositReceipt>::try_from(receipt_info)?;
+        require_keys_eq!(
+            stake_deposit_receipt.owner,
+            self.owner.key(),
+            ErrorCode::InvalidOwner
+        );
+        require_keys_eq!(
+            stake_deposit_receipt.stake_pool,
+            self.stake_pool.key(),
+            ErrorCode::InvalidStakePool
+        );
+
+        let mut stake_pool = self.stake_pool.load_mut()?;
+        let claimed_amounts = stake_pool.settle_claims(&mut stake_deposit_receipt)?;
+        emit_rewards_claimed(
+            &stake_pool,
+            self.stake_pool.key(),
+            stake_deposit_receipt.key(),
+            self.owner.key(),
+            claimed_amounts,
+        );
+        stake_deposit_receipt.exit(&crate::ID)?;
+        Ok(claimed_amounts)
+    }
+}
+
+/// `claim_all` over several receipts of one owner. The remaining accounts are the reward
+/// accounts of every active reward pool, as for `claim_all`, followed by the receipts.
+/// Each reward pool pays the receipts' sum in a single transfer.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+    let reward_accounts_len = {
+        let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+        stake_pool.recalculate_rewards_per_effective_stake(
+            ctx.remaining_accounts,
+            CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL,
+        )?;
+        let active_reward_pools = stake_pool
+            .reward_pools
+            .iter()
+            .filter(|reward_pool| !reward_pool.is_empty())
+            .count();
+        active_reward_pools * CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL
+    };
+    let Some(receipt_infos) = ctx.remaining_accounts.get(reward_accounts_len..) else {
+        return err!(ErrorCode::InvalidRewardPoolVaultIndex);
+    };
+    require!(
+        !receipt_infos.is_empty(),
+        ErrorCode::MissingStakeDepositReceipts
+    );
+
+    let mut claimed_amounts = [0u64; MAX_REWARD_POOLS];
+    for receipt_info in receipt_infos {
+        let receipt_claimed_amounts = ctx.accounts.settle_receipt(receipt_info)?;
+        for (total, amount) in claimed_amounts.iter_mut().zip(receipt_claimed_amounts) {
+            *total = checked_math!(*total, checked_add(amount), ErrorCode::MathOverflow)?;
+        }
+    }
+    transfer_claimed_rewards(
+        &ctx.accounts.owner.key(),
+        &ctx.accounts.stake_pool,
+        &ctx.remaining_accounts[..reward_accounts_len],
+        claimed_amounts,
+    )
+}
@@ -1,12 +1,22 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount};
+use anchor_spl::token_interface::{self, Mint, MintTo, TokenAccount, TokenInterface};
 
 use crate::{
     errors::ErrorCode,
+    events::LpMinted,
     stake_pool_signer_seeds,
-    state::StakePool,
+    state::{StakePool, MAX_UNBACKED_LP_REASON_LEN},
 };
 
+/// Every mint and burn of unbacked LP tokens states its reason in the event log
+pub fn validate_unbacked_lp_reason(reason: &str) -> Result<()> {
+    require!(
+        !reason.is_empty() && reason.len() <= MAX_UNBACKED_LP_REASON_LEN,
+        ErrorCode::InvalidUnbackedLpReason
+    );
+    Ok(())
+}
+
 #[derive(Accounts)]
 pub struct DangerouslyMintLp<'info> {
     /// Payer of rent
@@ -17,10 +27,10 @@ pub struct DangerouslyMintLp<'info> {
   pub authority: Signer<'info>,
 
   #[account(mut)]
-  pub stake_mint: Account<'info, Mint>,
+  pub stake_mint: InterfaceAccount<'info, Mint>,
 
   #[account(mut)]
-  pub destination: Account<'info, TokenAccount>,
+  pub destination: InterfaceAccount<'info, TokenAccount>,
 
   /// StakePool of the `stake_mint` to be minted
   #[account(
@@ -30,7 +40,8 @@ pub struct DangerouslyMintLp<'info> {
   )]
   pub stake_pool: AccountLoader<'info, StakePool>,
 
-  pub token_program: Program<'info, Token>,
+  /// Token program of `stake_mint`
+  pub token_program: Interface<'info, TokenInterface>,
 }
 
 impl<'info> DangerouslyMintLp<'info> {
@@ -57,14 +68,30 @@ impl<'info> DangerouslyMintLp<'info> {
             signer_seeds,
         );
 
-        token::mint_to(cpi_ctx, amount)
+        token_interface::mint_to(cpi_ctx, amount)
     }
 }
 
-pub fn handler(ctx: Context<DangerouslyMintLp>, amount: u64) -> Result<()> {
+/// Mints LP tokens backed by no receipt, up to the StakePool's `unbacked_lp_cap`
+pub fn handler(ctx: Context<DangerouslyMintLp>, amount: u64, reason: String) -> Result<()> {
     // #[cfg(not(feature = "localnet"))]
     // ctx.accounts.validate_stake_pool()?;
+    validate_unbacked_lp_reason(&reason)?;
+    let minted_unbacked = ctx
+        .accounts
+        .stake_pool
+        .load_mut()?
+        .record_unbacked_mint(amount)?;
 
     ctx.accounts.mint_staked_token_to_destination(amount)?;
+
+    emit!(LpMinted {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        stake_mint: ctx.accounts.stake_mint.key(),
+        destination: ctx.accounts.destination.key(),
+        amount,
+        minted_unbacked,
+        reason,
+    });
     Ok(())
 }
@@ -1,10 +1,15 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
+use anchor_spl::token_interface::{
+    self, Mint, MintTo, TokenAccount, TokenInterface, TransferChecked,
+};
 
+use crate::allowlist::AllowlistProof;
+use crate::checked_math;
 use crate::errors::ErrorCode;
+use crate::events::Deposited;
+use crate::pda::{create_pda_account, fund_account};
 use crate::stake_pool_signer_seeds;
-use crate::state::u128;
-use crate::state::{StakeDepositReceipt, StakePool};
+use crate::state::{DepositAllowance, StakeDepositReceipt, StakePool, UserStakeIndex};
 
 #[derive(Accounts)]
 #[instruction(nonce: u32)]
@@ -13,22 +18,30 @@ pub struct Deposit<'info> {
     pub payer: Signer<'info>,
     /// CHECK:
     pub owner: UncheckedAccount<'info>,
+    #[account(
+      mut,
+      // the vault is checked against the StakePool, so its mint is the StakePool mint
+      constraint = from.mint == vault.mint @ ErrorCode::InvalidStakeTokenMint
+    )]
+    pub from: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub from: Account<'info, TokenAccount>,
-    #[account(mut)]
-    pub vault: Account<'info, TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// StakePool mint, passed for `transfer_checked`
+    pub mint: InterfaceAccount<'info, Mint>,
 
     #[account(mut)]
-    pub stake_mint: Account<'info, Mint>,
+    pub stake_mint: InterfaceAccount<'info, Mint>,
     #[account(
       mut,
-      has_one = owner @ ErrorCode::InvalidAuthority
+      has_one = owner @ ErrorCode::InvalidLpTokenOwner,
+      constraint = destination.mint == stake_mint.key() @ ErrorCode::InvalidLpTokenMint
     )]
-    pub destination: Account<'info, TokenAccount>,
+    pub destination: InterfaceAccount<'info, TokenAccount>,
     #[account(
       mut,
       has_one = vault @ ErrorCode::InvalidStakePoolVault,
-      has_one = stake_mint @ ErrorCode::InvalidAuthority,
+      has_one = mint @ ErrorCode::InvalidStakeTokenMint,
+      has_one = stake_mint @ ErrorCode::InvalidStakeMint,
     )]
     pub stake_pool: AccountLoader<'info, StakePool>,
 
@@ -46,29 +59,203 @@ pub struct Deposit<'info> {
     )]
     pub stake_deposit_receipt: Account<'info, StakeDepositReceipt>,
 
-    pub token_program: Program<'info, Token>,
+    /// CHECK: The owner's `UserStakeIndex`, created on the owner's first deposit of its own
+    /// tokens and grown as receipts are added. Left alone unless `is_owner_funded`.
+    #[account(
+      mut,
+      seeds = [
+        owner.key().as_ref(),
+        stake_pool.key().as_ref(),
+        b"userStakeIndex",
+      ],
+      bump,
+    )]
+    pub user_stake_index: UncheckedAccount<'info>,
+
+    /// Token program of `mint`
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program of `stake_mint`, see `StakePool::stake_token_program`
+    pub stake_token_program: Interface<'info, TokenInterface>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
+
+    /// Signs the transfer out of `from` in place of `payer`, as the owner or as a delegate
+    /// the owner approved on `from`. A relayer then only pays fees and rent, and the tokens
+    /// must be the owner's.
+    #[account(
+      constraint = from.owner == owner.key() @ ErrorCode::InvalidDepositSourceOwner
+    )]
+    pub token_authority: Option<Signer<'info>>,
+
+    /// CHECK: The owner's `DepositAllowance`, required while the StakePool has a deposit
+    /// allowlist and created on the owner's first allowlisted deposit. Only `owner` signing
+    /// can draw it down.
+    #[account(
+      mut,
+      seeds = [
+        owner.key().as_ref(),
+        stake_pool.key().as_ref(),
+        b"depositAllowance",
+      ],
+      bump,
+    )]
+    pub deposit_allowance: Option<UncheckedAccount<'info>>,
 }
 
 impl<'info> Deposit<'info> {
-    pub fn transfer_from_user_to_stake_vault(&self, amount: u64) -> Result<()> {
+    /// Authority of the transfer out of `from`, `payer` unless `token_authority` is passed
+    pub fn token_authority(&self) -> AccountInfo<'info> {
+        match &self.token_authority {
+            Some(token_authority) => token_authority.to_account_info(),
+            None => self.payer.to_account_info(),
+        }
+    }
+
+    /// Whether the owner authorized the deposit, by signing or through `token_authority`
+    /// drawing on the owner's `from`. Others may deposit their own tokens for the owner.
+    pub fn is_owner_funded(&self) -> bool {
+        self.owner.is_signer || self.token_authority.is_some()
+    }
+
+    /// Returns the amount the vault received, less than `amount` when the mint charges a
+    /// transfer fee
+    pub fn transfer_from_user_to_stake_vault(&mut self, amount: u64) -> Result<u64> {
+        let vault_amount_before = self.vault.amount;
         let cpi_ctx = CpiContext::new(
             self.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: self.from.to_account_info(),
+                mint: self.mint.to_account_info(),
                 to: self.vault.to_account_info(),
-                authority: self.payer.to_account_info(),
+                authority: self.token_authority(),
             },
         );
-        token::transfer(cpi_ctx, amount)
+        token_interface::transfer_checked(cpi_ctx, amount, self.mint.decimals)?;
+        self.vault.reload()?;
+        checked_math!(
+            self.vault.amount,
+            checked_sub(vault_amount_before),
+            ErrorCode::MathUnderflow
+        )
+    }
+
+    /// Lists the new receipt in the owner's `UserStakeIndex`, creating the index or growing
+    /// it by one receipt at `payer`'s expense as needed. Returns the owner's total deposit.
+    pub fn record_in_user_stake_index(&self, nonce: u32, bump: u8) -> Result<u64> {
+        let index_info = self.user_stake_index.to_account_info();
+        let mut user_stake_index = if *index_info.owner == crate::ID {
+            UserStakeIndex::try_deserialize(&mut &index_info.try_borrow_data()?[..])?
+        } else {
+            self.create_user_stake_index(bump)?;
+            UserStakeIndex {
+                owner: self.owner.key(),
+                stake_pool: self.stake_pool.key(),
+                ..Default::default()
+            }
+        };
+
+        let space = UserStakeIndex::space(user_stake_index.receipts.len() + 1);
+        if index_info.data_len() < space {
+            self.fund_account(&index_info, space)?;
+            index_info.realloc(space, false)?;
+        }
+        user_stake_index.record_deposit(
+            &self.stake_deposit_receipt.key(),
+            nonce,
+            &self.stake_deposit_receipt,
+        )?;
+        let mut data = index_info.try_borrow_mut_data()?;
+        user_stake_index.try_serialize(&mut &mut data[..])?;
+        Ok(user_stake_index.total_deposit_amount)
+    }
+
+    /// Counts `amount` against the owner's allowlisted `max_amount`, creating its
+    /// `DepositAllowance` at `payer`'s expense on its first allowlisted deposit
+    pub fn record_in_deposit_allowance(
+        &self,
+        amount: u64,
+        max_amount: u64,
+        bump: u8,
+    ) -> Result<()> {
+        let Some(deposit_allowance) = &self.deposit_allowance else {
+            return err!(ErrorCode::MissingDepositAllowance);
+        };
+        let allowance_info = deposit_allowance.to_account_info();
+        let mut allowance = if *allowance_info.owner == crate::ID {
+            DepositAllowance::try_deserialize(&mut &allowance_info.try_borrow_data()?[..])?
+        } else {
+            let owner_key = self.owner.key();
+            let stake_pool_key = self.stake_pool.key();
+            self.create_program_account(
+                allowance_info.clone(),
+                8 + DepositAllowance::LEN,
+                &[
+                    owner_key.as_ref(),
+                    stake_pool_key.as_ref(),
+                    b"depositAllowance",
+                    &[bump],
+                ],
+            )?;
+            DepositAllowance {
+                owner: owner_key,
+                stake_pool: stake_pool_key,
+                ..Default::default()
+            }
+        };
+        allowance.record_deposit(amount, max_amount)?;
+        let mut data = allowance_info.try_borrow_mut_data()?;
+        allowance.try_serialize(&mut &mut data[..])
+    }
+
+    fn create_user_stake_index(&self, bump: u8) -> Result<()> {
+        let owner_key = self.owner.key();
+        let stake_pool_key = self.stake_pool.key();
+        self.create_program_account(
+            self.user_stake_index.to_account_info(),
+            UserStakeIndex::space(1),
+            &[
+                owner_key.as_ref(),
+                stake_pool_key.as_ref(),
+                b"userStakeIndex",
+                &[bump],
+            ],
+        )
+    }
+
+    /// Creates the PDA `account` owned by the program with `payer` funding its rent
+    fn create_program_account(
+        &self,
+        account: AccountInfo<'info>,
+        space: usize,
+        seeds: &[&[u8]],
+    ) -> Result<()> {
+        create_pda_account(
+            &self.payer.to_account_info(),
+            &account,
+            &self.system_program.to_account_info(),
+            &self.rent,
+            space,
+            &crate::ID,
+            &[seeds],
+        )
+    }
+
+    /// Tops `account` up from `payer` to the rent exempt minimum of `space` bytes
+    fn fund_account(&self, account: &AccountInfo<'info>, space: usize) -> Result<()> {
+        fund_account(
+            &self.payer.to_account_info(),
+            account,
+            &self.system_program.to_account_info(),
+            &self.rent,
+            space,
+        )
     }
 
     pub fn mint_staked_token_to_user(&self, effective_amount: u64) -> Result<()> {
         let stake_pool = self.stake_pool.load()?;
         let signer_seeds: &[&[&[u8]]] = &[stake_pool_signer_seeds!(stake_pool)];
         let cpi_ctx = CpiContext::new_with_signer(
-            self.token_program.to_account_info(),
+            self.stake_token_program.to_account_info(),
             MintTo {
                 mint: self.stake_mint.to_account_info(),
                 to: self.destination.to_account_info(),
@@ -77,64 +264,92 @@ impl<'info> Deposit<'info> {
             signer_seeds,
         );
 
-        token::mint_to(cpi_ctx, effective_amount)
+        token_interface::mint_to(cpi_ctx, effective_amount)
     }
 }
 
 pub fn handler<'info>(
     ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
-    _nonce: u32,
+    nonce: u32,
     amount: u64,
     lockup_duration: u64,
+    allowlist_proof: Option<AllowlistProof>,
 ) -> Result<()> {
-    ctx.accounts.transfer_from_user_to_stake_vault(amount)?;
+    let allowlist_max_amount = {
+        let stake_pool = ctx.accounts.stake_pool.load()?;
+        stake_pool.validate_owner_funded(ctx.accounts.is_owner_funded())?;
+        stake_pool.validate_deposit_allowlist(
+            &ctx.accounts.owner.key(),
+            ctx.accounts.owner.is_signer,
+            allowlist_proof.as_ref(),
+        )?
+    };
+    let amount = ctx.accounts.transfer_from_user_to_stake_vault(amount)?;
 
+    let weight: u64;
     {
         let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
-        if stake_pool.deposits_disabled() {
-            return err!(ErrorCode::DepositsDisabled);
-        }
-        if lockup_duration < stake_pool.min_duration {
-            return err!(ErrorCode::DurationTooShort);
-        }
-        // clamp lockup duration to the max
-        let lockup_duration = u64::min(lockup_duration, stake_pool.max_duration);
         let stake_deposit_receipt = &mut ctx.accounts.stake_deposit_receipt;
 
-        stake_pool.recalculate_rewards_per_effective_stake(&ctx.remaining_accounts, 1usize)?;
-        let weight = stake_pool.get_stake_weight(lockup_duration);
-        let effect_amount_staked = StakeDepositReceipt::get_effective_stake_amount(weight, amount);
+        stake_pool.recalculate_rewards_per_effective_stake(ctx.remaining_accounts, 1usize)?;
+        weight = stake_pool.record_deposit(
+            stake_deposit_receipt,
+            amount,
+            lockup_duration,
+            Clock::get()?.unix_timestamp,
+        )?;
 
         stake_deposit_receipt.stake_pool = ctx.accounts.stake_pool.key();
         stake_deposit_receipt.owner = ctx.accounts.owner.key();
         stake_deposit_receipt.payer = ctx.accounts.payer.key();
-        stake_deposit_receipt.deposit_amount = amount;
-        stake_deposit_receipt.effective_stake = u128(effect_amount_staked.to_le_bytes());
-        stake_deposit_receipt.lockup_duration = lockup_duration;
-        stake_deposit_receipt.deposit_timestamp = Clock::get()?.unix_timestamp;
-
-        // iterate over reward pools setting the initial "claimed" amount based on `rewards_per_effective_stake`.
-        //  Setting these claimed amounts to the current rewards per effective stake, marks where this
-        //  deposit receipt can start accumulating rewards. Now any more rewards added to a reward pool will
-        //  be claimable, on a pro-rated basis, by this stake receipt.
-        stake_deposit_receipt.claimed_amounts = stake_pool.get_claimed_amounts_of_reward_pools();
-
-        let total_staked = stake_pool
-            .total_weighted_stake_u128()
-            .checked_add(effect_amount_staked)
-            .unwrap();
-        stake_pool.total_weighted_stake = u128(total_staked.to_le_bytes());
+    }
+    // anyone may deposit for an owner, but only deposits the owner authorized are listed so
+    // nobody else can grow its index
+    let owner_deposit_amount = if ctx.accounts.is_owner_funded() {
+        Some(
+            ctx.accounts
+                .record_in_user_stake_index(nonce, ctx.bumps.user_stake_index)?,
+        )
+    } else {
+        None
+    };
+    if let Some(max_amount) = allowlist_max_amount {
+        ctx.accounts.record_in_deposit_allowance(
+            amount,
+            max_amount,
+            ctx.bumps.deposit_allowance,
+        )?;
     }
     let stake_pool = ctx.accounts.stake_pool.load()?;
+    if let Some(owner_deposit_amount) = owner_deposit_amount {
+        stake_pool.validate_owner_deposit_amount(owner_deposit_amount)?;
+    }
     let effect_amount_staked_tokens = StakeDepositReceipt::get_token_amount_from_stake(
         ctx.accounts.stake_deposit_receipt.effective_stake_u128(),
         stake_pool.max_weight,
-    );
+    )?;
 
+    let mut lp_minted = 0;
     if !stake_pool.deposits_ignores_lp() {
         ctx.accounts
             .mint_staked_token_to_user(effect_amount_staked_tokens)?;
+        lp_minted = effect_amount_staked_tokens;
     }
 
+    let stake_deposit_receipt = &ctx.accounts.stake_deposit_receipt;
+    emit!(Deposited {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        stake_deposit_receipt: stake_deposit_receipt.key(),
+        owner: stake_deposit_receipt.owner,
+        payer: stake_deposit_receipt.payer,
+        token_authority: ctx.accounts.token_authority().key(),
+        amount,
+        weight,
+        effective_stake: stake_deposit_receipt.effective_stake_u128(),
+        lockup_duration: stake_deposit_receipt.lockup_duration,
+        deposit_timestamp: stake_deposit_receipt.deposit_timestamp,
+        lp_minted,
+        total_weighted_stake: stake_pool.total_weighted_stake_u128(),
+    });
     Ok(())
 }
@@ -0,0 +1,84 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{errors::ErrorCode, events::RewardPoolFunded, state::StakePool};
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct FundRewardPool<'info> {
+    /// Owner of the `from` token account
+    pub funder: Signer<'info>,
+
+    #[account(mut)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(mut)]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+
+    /// Vault of the RewardPool at `index`
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+      constraint = reward_mint.key() == reward_vault.mint @ ErrorCode::InvalidRewardMint,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token program of `reward_mint`
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> FundRewardPool<'info> {
+    pub fn validate_reward_pool(&self, index: u8) -> Result<()> {
+        let stake_pool = self.stake_pool.load()?;
+        let reward_pool = stake_pool
+            .reward_pools
+            .get(usize::from(index))
+            .filter(|reward_pool| !reward_pool.is_empty())
+            .ok_or(ErrorCode::InvalidRewardPoolIndex)?;
+        require_keys_eq!(
+            reward_pool.reward_vault,
+            self.reward_vault.key(),
+            ErrorCode::InvalidRewardPoolVault
+        );
+        Ok(())
+    }
+
+    pub fn transfer_from_funder_to_reward_vault(&self, amount: u64) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.from.to_account_info(),
+                mint: self.reward_mint.to_account_info(),
+                to: self.reward_vault.to_account_info(),
+                authority: self.funder.to_account_info(),
+            },
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, self.reward_mint.decimals)
+    }
+}
+
+pub fn handler(ctx: Context<FundRewardPool>, index: u8, amount: u64) -> Result<()> {
+    ctx.accounts.validate_reward_pool(index)?;
+    ctx.accounts.transfer_from_funder_to_reward_vault(amount)?;
+    ctx.accounts.reward_vault.reload()?;
+
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    stake_pool.record_funding(
+        usize::from(index),
+        ctx.accounts.reward_vault.amount,
+        amount,
+        Clock::get()?.unix_timestamp,
+    )?;
+    let reward_pool = &stake_pool.reward_pools[usize::from(index)];
+
+    emit!(RewardPoolFunded {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        index,
+        funder: ctx.accounts.funder.key(),
+        amount,
+        total_funded: reward_pool.total_funded,
+        rewards_per_effective_stake: reward_pool.rewards_per_effective_stake_u128(),
+    });
+    Ok(())
+}
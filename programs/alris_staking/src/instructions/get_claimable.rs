@@ -0,0 +1,27 @@
+use anchor_lang::prelude::*;
+
+use crate::errors::ErrorCode;
+use crate::state::{StakeDepositReceipt, StakePool, MAX_REWARD_POOLS};
+
+#[derive(Accounts)]
+pub struct GetClaimable<'info> {
+    pub stake_pool: AccountLoader<'info, StakePool>,
+
+    #[account(has_one = stake_pool @ ErrorCode::InvalidStakePool)]
+    pub stake_deposit_receipt: Account<'info, StakeDepositReceipt>,
+}
+
+/// Rewards the receipt would receive from `claim_all` right now, by reward pool index.
+/// The accrual runs on copies of the reward pools so nothing is written.
+pub fn handler<'info>(
+    ctx: Context<'_, '_, 'info, 'info, GetClaimable<'info>>,
+) -> Result<[u64; MAX_REWARD_POOLS]> {
+    let stake_pool = ctx.accounts.stake_pool.load()?;
+    let (vault_amounts, active_reward_pools) =
+        stake_pool.reward_vault_amounts(ctx.remaining_accounts, 1usize)?;
+    stake_pool.preview_claimable_amounts(
+        &ctx.accounts.stake_deposit_receipt,
+        &vault_amounts[..active_reward_pools],
+        Clock::get()?.unix_timestamp,
+    )
+}
@@ -1,11 +1,20 @@
 use std::str::FromStr;
 
-use anchor_lang::prelude::*;
-use anchor_spl::token::{Mint, Token, TokenAccount};
+use anchor_lang::{prelude::*, solana_program::program::invoke};
+use anchor_spl::{
+    token_2022::spl_token_2022::{
+        self,
+        extension::{metadata_pointer, ExtensionType},
+    },
+    token_interface::{self, InitializeMint2, Mint, TokenAccount, TokenInterface},
+};
 
 use crate::{
     errors::ErrorCode,
-    state::{get_digit_shift_by_max_scalar, StakePool, SCALE_FACTOR_BASE},
+    events::StakePoolInitialized,
+    pda::create_pda_account,
+    state::{get_digit_shift_by_max_scalar, StakePool, SOULBOUND_STAKE_MINT},
+    token_extensions::{validate_stake_mint_extensions, SOULBOUND_STAKE_MINT_EXTENSIONS},
 };
 
 #[derive(Accounts)]
@@ -14,15 +23,16 @@ use crate::{
   max_weight: u64,
   min_duration: u64,
   max_duration: u64,
+  options: u8,
 )]
 pub struct InitializeStakePool<'info> {
     #[account(mut)]
     pub payer: Signer<'info>,
 
-    #[account(constraint = authority.key() == Pubkey::from_str("BEs6Lh6NbDVtxt3FPKVkLN9fz22Byk6EvEp4GRUW7mqm").unwrap() @ErrorCode::InvalidAuthority)]
+    #[account(constraint = is_pool_creator(&authority.key()) @ErrorCode::InvalidAuthority)]
     pub authority: Signer<'info>,
 
-    pub mint: Box<Account<'info, Mint>>,
+    pub mint: Box<InterfaceAccount<'info, Mint>>,
 
     #[account(
       init,
@@ -38,15 +48,13 @@ pub struct InitializeStakePool<'info> {
     )]
     pub stake_pool: AccountLoader<'info, StakePool>,
 
+    /// CHECK: Created by the handler under `stake_token_program`
     #[account(
-      init,
+      mut,
       seeds = [&stake_pool.key().to_bytes()[..], b"stakeMint"],
       bump,
-      payer = payer,
-      mint::decimals = mint.decimals.checked_sub(get_digit_shift_by_max_scalar(max_weight)).unwrap_or_default(),
-      mint::authority = stake_pool,
     )]
-    pub stake_mint: Box<Account<'info, Mint>>,
+    pub stake_mint: UncheckedAccount<'info>,
 
     #[account(
       init,
@@ -55,38 +63,142 @@ pub struct InitializeStakePool<'info> {
       payer = payer,
       token::mint = mint,
       token::authority = stake_pool,
+      token::token_program = token_program,
     )]
-    pub vault: Box<Account<'info, TokenAccount>>,
+    pub vault: Box<InterfaceAccount<'info, TokenAccount>>,
 
-    pub token_program: Program<'info, Token>,
+    /// Token program of `mint`, the LP mint and vault are created under it as well. The
+    /// vault initialization fails if it does not own `mint`.
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program the LP mint is created under, Token-2022 for a `SOULBOUND_STAKE_MINT`
+    /// StakePool and `token_program` otherwise
+    pub stake_token_program: Interface<'info, TokenInterface>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
 }
 
+/// Only the Alris admin may create stake pools. `localnet` builds accept any authority.
+fn is_pool_creator(authority: &Pubkey) -> bool {
+    cfg!(feature = "localnet")
+        || *authority == Pubkey::from_str("BEs6Lh6NbDVtxt3FPKVkLN9fz22Byk6EvEp4GRUW7mqm").unwrap()
+}
+
+impl<'info> InitializeStakePool<'info> {
+    pub fn validate_stake_token_program(&self, options: u8) -> Result<()> {
+        require!(
+            options & !SOULBOUND_STAKE_MINT == 0,
+            ErrorCode::InvalidStakePoolOptions
+        );
+        let expected = if options & SOULBOUND_STAKE_MINT != 0 {
+            spl_token_2022::ID
+        } else {
+            self.token_program.key()
+        };
+        require_keys_eq!(
+            self.stake_token_program.key(),
+            expected,
+            ErrorCode::InvalidTokenProgram
+        );
+        Ok(())
+    }
+
+    /// Creates the LP mint at its PDA, with the soulbound extensions initialized ahead of
+    /// the mint itself when `soulbound` is set.
+    pub fn create_stake_mint(&self, decimals: u8, soulbound: bool, bump: u8) -> Result<()> {
+        let extensions: &[ExtensionType] = if soulbound {
+            &SOULBOUND_STAKE_MINT_EXTENSIONS
+        } else {
+            &[]
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(extensions)?;
+        let stake_pool_key = self.stake_pool.key();
+        let signer_seeds: &[&[&[u8]]] = &[&[stake_pool_key.as_ref(), b"stakeMint", &[bump]]];
+        let stake_mint = self.stake_mint.to_account_info();
+        create_pda_account(
+            &self.payer.to_account_info(),
+            &stake_mint,
+            &self.system_program.to_account_info(),
+            &self.rent,
+            space,
+            self.stake_token_program.key,
+            signer_seeds,
+        )?;
+
+        if soulbound {
+            invoke(
+                &spl_token_2022::instruction::initialize_non_transferable_mint(
+                    self.stake_token_program.key,
+                    stake_mint.key,
+                )?,
+                std::slice::from_ref(&stake_mint),
+            )?;
+            invoke(
+                &metadata_pointer::instruction::initialize(
+                    self.stake_token_program.key,
+                    stake_mint.key,
+                    Some(stake_pool_key),
+                    Some(*stake_mint.key),
+                )?,
+                std::slice::from_ref(&stake_mint),
+            )?;
+        }
+
+        token_interface::initialize_mint2(
+            CpiContext::new(
+                self.stake_token_program.to_account_info(),
+                InitializeMint2 { mint: stake_mint },
+            ),
+            decimals,
+            &stake_pool_key,
+            None,
+        )
+    }
+}
+
 pub fn handler(
     ctx: Context<InitializeStakePool>,
     nonce: u8,
     max_weight: u64,
     min_duration: u64,
     max_duration: u64,
+    options: u8,
 ) -> Result<()> {
-    if min_duration > max_duration {
-        return Err(ErrorCode::InvalidStakePoolDuration.into());
-    }
-    if SCALE_FACTOR_BASE > max_weight {
-        return Err(ErrorCode::InvalidStakePoolWeight.into());
-    }
+    validate_stake_mint_extensions(&ctx.accounts.mint.to_account_info())?;
+    ctx.accounts.validate_stake_token_program(options)?;
+    ctx.accounts.create_stake_mint(
+        ctx.accounts
+            .mint
+            .decimals
+            .saturating_sub(get_digit_shift_by_max_scalar(max_weight)?),
+        options & SOULBOUND_STAKE_MINT != 0,
+        ctx.bumps.stake_mint,
+    )?;
+
     let mut stake_pool = ctx.accounts.stake_pool.load_init()?;
+    stake_pool.configure(max_weight, min_duration, max_duration)?;
     stake_pool.creator = ctx.accounts.authority.key();
     stake_pool.authority = ctx.accounts.authority.key();
     stake_pool.mint = ctx.accounts.mint.key();
     stake_pool.stake_mint = ctx.accounts.stake_mint.key();
     stake_pool.vault = ctx.accounts.vault.key();
-    stake_pool.base_weight = SCALE_FACTOR_BASE;
-    stake_pool.max_weight = max_weight;
-    stake_pool.min_duration = min_duration;
-    stake_pool.max_duration = max_duration;
     stake_pool.nonce = nonce;
+    stake_pool.options = options;
     stake_pool.bump_seed = ctx.bumps.stake_pool;
+
+    emit!(StakePoolInitialized {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        creator: stake_pool.creator,
+        authority: stake_pool.authority,
+        mint: stake_pool.mint,
+        stake_mint: stake_pool.stake_mint,
+        vault: stake_pool.vault,
+        nonce,
+        base_weight: stake_pool.base_weight,
+        max_weight,
+        min_duration,
+        max_duration,
+        options,
+    });
     Ok(())
 }
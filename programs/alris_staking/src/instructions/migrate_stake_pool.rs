@@ -0,0 +1,94 @@
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, Transfer},
+    Discriminator,
+};
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{
+    errors::ErrorCode,
+    events::StakePoolMigrated,
+    state::{LegacyStakePool, StakePool},
+};
+
+#[derive(Accounts)]
+pub struct MigrateStakePool<'info> {
+    /// Pays the rent of the grown StakePool
+    #[account(mut)]
+    pub payer: Signer<'info>,
+    pub authority: Signer<'info>,
+
+    /// CHECK: A StakePool in the `LegacyStakePool` layout, which `AccountLoader` cannot load.
+    /// Its discriminator, size and authority are checked by the handler.
+    #[account(
+      mut,
+      owner = crate::ID,
+    )]
+    pub stake_pool: UncheckedAccount<'info>,
+    /// The StakePool's vault, its balance seeds `total_deposit_amount`
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    pub system_program: Program<'info, System>,
+}
+
+/// Grows a StakePool created before `RewardPool` carried its undistributed reward and
+/// rounding fields into the current layout. Every other instruction fails on the pool
+/// until it is migrated.
+pub fn handler(ctx: Context<MigrateStakePool>) -> Result<()> {
+    let stake_pool_info = ctx.accounts.stake_pool.to_account_info();
+    let legacy: LegacyStakePool = {
+        let data = stake_pool_info.try_borrow_data()?;
+        require!(
+            data.len() >= 8 && data[..8] == StakePool::DISCRIMINATOR,
+            anchor_lang::error::ErrorCode::AccountDiscriminatorMismatch
+        );
+        require!(
+            data.len() == 8 + LegacyStakePool::LEN,
+            ErrorCode::StakePoolAlreadyMigrated
+        );
+        bytemuck::pod_read_unaligned(&data[8..])
+    };
+    require_keys_eq!(
+        legacy.authority,
+        ctx.accounts.authority.key(),
+        ErrorCode::InvalidAuthority
+    );
+    require_keys_eq!(
+        legacy.vault,
+        ctx.accounts.vault.key(),
+        ErrorCode::InvalidStakePoolVault
+    );
+
+    let previous_len = stake_pool_info.data_len();
+    let len = 8 + StakePool::LEN;
+    let lamports = Rent::get()?
+        .minimum_balance(len)
+        .saturating_sub(stake_pool_info.lamports());
+    if lamports > 0 {
+        system_program::transfer(
+            CpiContext::new(
+                ctx.accounts.system_program.to_account_info(),
+                Transfer {
+                    from: ctx.accounts.payer.to_account_info(),
+                    to: stake_pool_info.clone(),
+                },
+            ),
+            lamports,
+        )?;
+    }
+    stake_pool_info.realloc(len, false)?;
+    {
+        let mut data = stake_pool_info.try_borrow_mut_data()?;
+        // built in place, the StakePool is too large to assemble on the stack
+        data[8..].fill(0);
+        let stake_pool: &mut StakePool = bytemuck::from_bytes_mut(&mut data[8..]);
+        legacy.migrate_into(stake_pool, ctx.accounts.vault.amount);
+    }
+
+    emit!(StakePoolMigrated {
+        stake_pool: stake_pool_info.key(),
+        authority: legacy.authority,
+        previous_len: previous_len as u64,
+        len: len as u64,
+    });
+    Ok(())
+}
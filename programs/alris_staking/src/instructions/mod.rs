@@ -1,21 +1,51 @@
+#![allow(ambiguous_glob_reexports)]
+
 pub mod add_reward_pool;
+pub mod burn_unbacked_lp;
 pub mod claim_all;
 pub mod claim_base;
+pub mod claim_many;
 pub mod dangerously_mint_lp;
 pub mod deposit;
+pub mod fund_reward_pool;
+pub mod get_claimable;
 pub mod initialize_stake_pool;
+pub mod migrate_stake_pool;
+pub mod set_deposit_allowlist;
+pub mod set_deposit_caps;
 pub mod set_flags;
+pub mod set_min_deposit_amount;
+pub mod set_reward_pool_policy;
+pub mod set_unbacked_lp_cap;
+pub mod set_unbacked_lp_cap_authority;
+pub mod sweep_reward_dust;
+pub mod sweep_undistributed;
 pub mod transfer_authority;
-pub mod withdraw;
 pub mod update_token_meta;
+pub mod withdraw;
+pub mod withdraw_many;
 
 pub use add_reward_pool::*;
+pub use burn_unbacked_lp::*;
 pub use claim_all::*;
 pub use claim_base::*;
+pub use claim_many::*;
 pub use dangerously_mint_lp::*;
 pub use deposit::*;
+pub use fund_reward_pool::*;
+pub use get_claimable::*;
 pub use initialize_stake_pool::*;
+pub use migrate_stake_pool::*;
+pub use set_deposit_allowlist::*;
+pub use set_deposit_caps::*;
 pub use set_flags::*;
+pub use set_min_deposit_amount::*;
+pub use set_reward_pool_policy::*;
+pub use set_unbacked_lp_cap::*;
+pub use set_unbacked_lp_cap_authority::*;
+pub use sweep_reward_dust::*;
+pub use sweep_undistributed::*;
 pub use transfer_authority::*;
+pub use update_token_meta::*;
 pub use withdraw::*;
-pub use update_token_meta::*;
\ No newline at end of file
+pub use withdraw_many::*;
\ No newline at end of file
@@ -0,0 +1,29 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, events::DepositAllowlistUpdated, state::StakePool};
+
+#[derive(Accounts)]
+pub struct SetDepositAllowlist<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+}
+
+/// An all zero root opens deposits to anyone. Owners keep what they deposited under a
+/// previous root counted against the `max_amount` of their new leaf.
+pub fn handler(ctx: Context<SetDepositAllowlist>, root: [u8; 32]) -> Result<()> {
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    let previous_root = stake_pool.deposit_allowlist_root;
+    stake_pool.deposit_allowlist_root = root;
+
+    emit!(DepositAllowlistUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        previous_root,
+        root,
+    });
+    Ok(())
+}
@@ -0,0 +1,36 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, events::DepositCapsUpdated, state::StakePool};
+
+#[derive(Accounts)]
+pub struct SetDepositCaps<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+}
+
+/// Zero lifts a cap. Caps below what is already deposited only stop further deposits.
+pub fn handler(
+    ctx: Context<SetDepositCaps>,
+    max_total_deposit: u64,
+    max_deposit_per_owner: u64,
+) -> Result<()> {
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    let previous_max_total_deposit = stake_pool.max_total_deposit;
+    let previous_max_deposit_per_owner = stake_pool.max_deposit_per_owner;
+    stake_pool.max_total_deposit = max_total_deposit;
+    stake_pool.max_deposit_per_owner = max_deposit_per_owner;
+
+    emit!(DepositCapsUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        previous_max_total_deposit,
+        max_total_deposit,
+        previous_max_deposit_per_owner,
+        max_deposit_per_owner,
+    });
+    Ok(())
+}
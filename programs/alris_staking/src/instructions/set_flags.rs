@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 
 use crate::{
   errors::ErrorCode,
+  events::FlagsUpdated,
   state::StakePool,
 };
 
@@ -20,6 +21,13 @@ pub struct SetFlags<'info> {
 
 pub fn handler(ctx: Context<SetFlags>, flags: u8) -> Result<()> {
   let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+  let previous_flags = stake_pool.flags;
   stake_pool.flags = flags;
+
+  emit!(FlagsUpdated {
+    stake_pool: ctx.accounts.stake_pool.key(),
+    previous_flags,
+    flags,
+  });
   Ok(())
 }
\ No newline at end of file
@@ -0,0 +1,28 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, events::MinDepositAmountUpdated, state::StakePool};
+
+#[derive(Accounts)]
+pub struct SetMinDepositAmount<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+}
+
+/// Zero lifts the minimum. Open receipts below a new minimum are left as they are.
+pub fn handler(ctx: Context<SetMinDepositAmount>, min_deposit_amount: u64) -> Result<()> {
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    let previous_min_deposit_amount = stake_pool.min_deposit_amount;
+    stake_pool.min_deposit_amount = min_deposit_amount;
+
+    emit!(MinDepositAmountUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        previous_min_deposit_amount,
+        min_deposit_amount,
+    });
+    Ok(())
+}
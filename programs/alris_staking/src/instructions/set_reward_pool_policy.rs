@@ -0,0 +1,89 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::TokenAccount;
+
+use crate::{
+    errors::ErrorCode,
+    events::RewardPoolPolicyUpdated,
+    state::{StakePool, UNDISTRIBUTED_HOLD, UNDISTRIBUTED_SWEEP},
+};
+
+#[derive(Accounts)]
+pub struct SetRewardPoolPolicy<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Token account the undistributed rewards are swept to, the funder's or a treasury.
+    /// Required by `UNDISTRIBUTED_SWEEP`.
+    #[account(
+      constraint = undistributed_recipient.mint == reward_vault.mint
+        @ ErrorCode::InvalidUndistributedRecipient,
+    )]
+    pub undistributed_recipient: Option<InterfaceAccount<'info, TokenAccount>>,
+}
+
+pub fn handler(
+    ctx: Context<SetRewardPoolPolicy>,
+    index: u8,
+    policy: u8,
+    release_period: u64,
+) -> Result<()> {
+    let recipient = ctx
+        .accounts
+        .undistributed_recipient
+        .as_ref()
+        .map(|a| a.key());
+    match policy {
+        UNDISTRIBUTED_HOLD => {}
+        UNDISTRIBUTED_SWEEP => {
+            if recipient.is_none() {
+                return err!(ErrorCode::InvalidUndistributedRecipient);
+            }
+        }
+        _ => return err!(ErrorCode::InvalidUndistributedPolicy),
+    }
+
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    let total_weighted_stake = stake_pool.total_weighted_stake_u128();
+    let reward_pool = stake_pool
+        .reward_pools
+        .get_mut(usize::from(index))
+        .filter(|reward_pool| !reward_pool.is_empty())
+        .ok_or(ErrorCode::InvalidRewardPoolIndex)?;
+    require_keys_eq!(
+        reward_pool.reward_vault,
+        ctx.accounts.reward_vault.key(),
+        ErrorCode::InvalidRewardPoolVault
+    );
+
+    // book what the running release has unlocked for stakers under the current policy, so
+    // a switch to `UNDISTRIBUTED_SWEEP` can only sweep what is still held
+    reward_pool.accrue(
+        ctx.accounts.reward_vault.amount,
+        total_weighted_stake,
+        Clock::get()?.unix_timestamp,
+    )?;
+    if reward_pool.undistributed_policy != policy || reward_pool.release_period != release_period {
+        reward_pool.undistributed_policy = policy;
+        reward_pool.release_period = release_period;
+        reward_pool.restart_release();
+    }
+    if let Some(recipient) = recipient {
+        reward_pool.undistributed_recipient = recipient;
+    }
+
+    emit!(RewardPoolPolicyUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        index,
+        policy,
+        release_period,
+        undistributed_recipient: reward_pool.undistributed_recipient,
+    });
+    Ok(())
+}
@@ -0,0 +1,32 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, events::UnbackedLpCapUpdated, state::StakePool};
+
+#[derive(Accounts)]
+pub struct SetUnbackedLpCap<'info> {
+    /// Authority of the StakePool to lower the cap, `unbacked_lp_cap_authority` to raise or
+    /// lower it
+    pub authority: Signer<'info>,
+
+    #[account(mut)]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+}
+
+/// A cap below `minted_unbacked` stops further mints until enough is burned
+pub fn handler(ctx: Context<SetUnbackedLpCap>, cap: u64) -> Result<()> {
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    let signer = ctx.accounts.authority.key();
+    let previous_cap = stake_pool.unbacked_lp_cap;
+    if !stake_pool.is_unbacked_lp_cap_authority(&signer) {
+        require_keys_eq!(signer, stake_pool.authority, ErrorCode::InvalidAuthority);
+        require_gte!(previous_cap, cap, ErrorCode::InvalidUnbackedLpCapAuthority);
+    }
+    stake_pool.unbacked_lp_cap = cap;
+
+    emit!(UnbackedLpCapUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        previous_cap,
+        cap,
+    });
+    Ok(())
+}
@@ -0,0 +1,41 @@
+use anchor_lang::prelude::*;
+
+use crate::{errors::ErrorCode, events::UnbackedLpCapAuthorityUpdated, state::StakePool};
+
+#[derive(Accounts)]
+pub struct SetUnbackedLpCapAuthority<'info> {
+    /// Authority of the StakePool while no `unbacked_lp_cap_authority` is named,
+    /// `unbacked_lp_cap_authority` after
+    pub authority: Signer<'info>,
+
+    /// CHECK: any key, default to leave the cap with nobody who may raise it
+    pub new_authority: UncheckedAccount<'info>,
+
+    #[account(mut)]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+}
+
+/// Names the key that may raise `unbacked_lp_cap`. Once named only that key may hand it on,
+/// so it should be one the StakePool authority does not control.
+pub fn handler(ctx: Context<SetUnbackedLpCapAuthority>) -> Result<()> {
+    let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+    let signer = ctx.accounts.authority.key();
+    let previous_authority = stake_pool.unbacked_lp_cap_authority;
+    if previous_authority == Pubkey::default() {
+        require_keys_eq!(signer, stake_pool.authority, ErrorCode::InvalidAuthority);
+    } else {
+        require_keys_eq!(
+            signer,
+            previous_authority,
+            ErrorCode::InvalidUnbackedLpCapAuthority
+        );
+    }
+    stake_pool.unbacked_lp_cap_authority = ctx.accounts.new_authority.key();
+
+    emit!(UnbackedLpCapAuthorityUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        previous_authority,
+        new_authority: stake_pool.unbacked_lp_cap_authority,
+    });
+    Ok(())
+}
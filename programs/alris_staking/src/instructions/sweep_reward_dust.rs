@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+use core::primitive;
+
+use crate::{
+    checked_math,
+    errors::ErrorCode,
+    events::RewardDustSwept,
+    stake_pool_signer_seeds,
+    state::{u128, StakePool, SCALE_FACTOR_BASE_SQUARED},
+};
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct SweepRewardDust<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+
+    /// Vault of the RewardPool at `index`
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+      mut,
+      constraint = destination.mint == reward_vault.mint @ ErrorCode::InvalidRewardPoolVault,
+    )]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+      constraint = reward_mint.key() == reward_vault.mint @ ErrorCode::InvalidRewardMint,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token program of `reward_mint`
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SweepRewardDust<'info> {
+    pub fn transfer_from_reward_vault_to_destination(&self, amount: u64) -> Result<()> {
+        let stake_pool = self.stake_pool.load()?;
+        let signer_seeds: &[&[&[u8]]] = &[stake_pool_signer_seeds!(stake_pool)];
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.reward_vault.to_account_info(),
+                mint: self.reward_mint.to_account_info(),
+                to: self.destination.to_account_info(),
+                authority: self.stake_pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, self.reward_mint.decimals)
+    }
+}
+
+pub fn handler(ctx: Context<SweepRewardDust>, index: u8) -> Result<()> {
+    let amount: u64;
+    {
+        let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+        let reward_pool = stake_pool
+            .reward_pools
+            .get_mut(usize::from(index))
+            .filter(|reward_pool| !reward_pool.is_empty())
+            .ok_or(ErrorCode::InvalidRewardPoolIndex)?;
+        require_keys_eq!(
+            reward_pool.reward_vault,
+            ctx.accounts.reward_vault.key(),
+            ErrorCode::InvalidRewardPoolVault
+        );
+
+        // Only whole tokens floored away from receipt claims are taken, everything else in
+        // the vault is still owed to stakers.
+        amount = reward_pool.get_sweepable_dust()?;
+        let swept_dust = checked_math!(
+            primitive::u128::from(amount),
+            checked_mul(primitive::u128::from(SCALE_FACTOR_BASE_SQUARED)),
+            ErrorCode::MathOverflow
+        )?;
+        let unclaimable_dust = checked_math!(
+            reward_pool.unclaimable_dust.as_u128(),
+            checked_sub(swept_dust),
+            ErrorCode::MathUnderflow
+        )?;
+        reward_pool.unclaimable_dust = u128(unclaimable_dust.to_le_bytes());
+        reward_pool.last_amount = checked_math!(
+            reward_pool.last_amount,
+            checked_sub(amount),
+            ErrorCode::MathUnderflow
+        )?;
+    } // release mutable borrow of stake_pool
+
+    if amount == 0 {
+        return err!(ErrorCode::InvalidAmount);
+    }
+    ctx.accounts
+        .transfer_from_reward_vault_to_destination(amount)?;
+
+    emit!(RewardDustSwept {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        index,
+        destination: ctx.accounts.destination.key(),
+        amount,
+    });
+    Ok(())
+}
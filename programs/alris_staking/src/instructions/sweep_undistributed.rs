@@ -0,0 +1,110 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{self, Mint, TokenAccount, TokenInterface, TransferChecked};
+
+use crate::{
+    checked_math,
+    errors::ErrorCode,
+    events::UndistributedSwept,
+    stake_pool_signer_seeds,
+    state::{StakePool, UNDISTRIBUTED_HOLD},
+};
+
+#[derive(Accounts)]
+#[instruction(index: u8)]
+pub struct SweepUndistributed<'info> {
+    pub authority: Signer<'info>,
+
+    #[account(
+      mut,
+      has_one = authority @ ErrorCode::InvalidAuthority,
+    )]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+
+    /// Vault of the RewardPool at `index`
+    #[account(mut)]
+    pub reward_vault: InterfaceAccount<'info, TokenAccount>,
+
+    /// Must be the RewardPool's `undistributed_recipient`
+    #[account(mut)]
+    pub undistributed_recipient: InterfaceAccount<'info, TokenAccount>,
+
+    #[account(
+      constraint = reward_mint.key() == reward_vault.mint @ ErrorCode::InvalidRewardMint,
+    )]
+    pub reward_mint: InterfaceAccount<'info, Mint>,
+
+    /// Token program of `reward_mint`
+    pub token_program: Interface<'info, TokenInterface>,
+}
+
+impl<'info> SweepUndistributed<'info> {
+    pub fn transfer_from_reward_vault_to_recipient(&self, amount: u64) -> Result<()> {
+        let stake_pool = self.stake_pool.load()?;
+        let signer_seeds: &[&[&[u8]]] = &[stake_pool_signer_seeds!(stake_pool)];
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.reward_vault.to_account_info(),
+                mint: self.reward_mint.to_account_info(),
+                to: self.undistributed_recipient.to_account_info(),
+                authority: self.stake_pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        token_interface::transfer_checked(cpi_ctx, amount, self.reward_mint.decimals)
+    }
+}
+
+pub fn handler(ctx: Context<SweepUndistributed>, index: u8) -> Result<()> {
+    let amount: u64;
+    {
+        let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+        let total_weighted_stake = stake_pool.total_weighted_stake_u128();
+        let reward_pool = stake_pool
+            .reward_pools
+            .get_mut(usize::from(index))
+            .filter(|reward_pool| !reward_pool.is_empty())
+            .ok_or(ErrorCode::InvalidRewardPoolIndex)?;
+        require_keys_eq!(
+            reward_pool.reward_vault,
+            ctx.accounts.reward_vault.key(),
+            ErrorCode::InvalidRewardPoolVault
+        );
+        require_keys_eq!(
+            reward_pool.undistributed_recipient,
+            ctx.accounts.undistributed_recipient.key(),
+            ErrorCode::InvalidUndistributedRecipient
+        );
+        if reward_pool.undistributed_policy == UNDISTRIBUTED_HOLD {
+            return err!(ErrorCode::InvalidUndistributedPolicy);
+        }
+
+        // pick up anything sent to the vault since the last accrual
+        reward_pool.accrue(
+            ctx.accounts.reward_vault.amount,
+            total_weighted_stake,
+            Clock::get()?.unix_timestamp,
+        )?;
+        amount = reward_pool.undistributed_amount;
+        reward_pool.undistributed_amount = 0;
+        reward_pool.last_amount = checked_math!(
+            reward_pool.last_amount,
+            checked_sub(amount),
+            ErrorCode::MathUnderflow
+        )?;
+    } // release mutable borrow of stake_pool
+
+    if amount == 0 {
+        return err!(ErrorCode::InvalidAmount);
+    }
+    ctx.accounts
+        .transfer_from_reward_vault_to_recipient(amount)?;
+
+    emit!(UndistributedSwept {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        index,
+        recipient: ctx.accounts.undistributed_recipient.key(),
+        amount,
+    });
+    Ok(())
+}
@@ -2,6 +2,7 @@ use anchor_lang::prelude::*;
 
 use crate::{
   errors::ErrorCode,
+  events::AuthorityTransferred,
   state::StakePool,
 };
 
@@ -21,6 +22,13 @@ pub struct TransferAuthority<'info> {
 
 pub fn handler(ctx: Context<TransferAuthority>) -> Result<()> {
   let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+  let previous_authority = stake_pool.authority;
   stake_pool.authority = ctx.accounts.new_authority.key();
+
+  emit!(AuthorityTransferred {
+    stake_pool: ctx.accounts.stake_pool.key(),
+    previous_authority,
+    new_authority: stake_pool.authority,
+  });
   Ok(())
 }
\ No newline at end of file
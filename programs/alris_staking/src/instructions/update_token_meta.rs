@@ -1,16 +1,40 @@
-use crate::{errors::ErrorCode, stake_pool_signer_seeds, state::StakePool, ID};
-use anchor_lang::prelude::*;
-use anchor_spl::metadata::{
-    create_metadata_accounts_v3,
-    mpl_token_metadata::{self, types::DataV2},
-    update_metadata_accounts_v2, CreateMetadataAccountsV3, UpdateMetadataAccountsV2,
+use crate::{
+    errors::ErrorCode, events::TokenMetaUpdated, stake_pool_signer_seeds, state::StakePool,
+    token_extensions::SOULBOUND_STAKE_MINT_EXTENSIONS, ID,
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::program::invoke_signed,
+    system_program::{self, Transfer},
+};
+use anchor_spl::{
+    metadata::{
+        create_metadata_accounts_v3,
+        mpl_token_metadata::{self, types::DataV2},
+        update_metadata_accounts_v2, CreateMetadataAccountsV3, UpdateMetadataAccountsV2,
+    },
+    token_2022::spl_token_2022::{
+        self,
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint,
+    },
+};
+use spl_token_metadata_interface::{
+    instruction::{initialize, update_field},
+    state::{Field, TokenMetadata},
 };
 
+/// Type and length preceding each Token-2022 extension
+const EXTENSION_HEADER_LEN: usize = 4;
+
 #[derive(Accounts)]
 pub struct UpdateTokenMeta<'info> {
+    /// Pays for the metadata account when it is created, or for the metadata written into a
+    /// soulbound LP mint
+    #[account(mut)]
     pub authority: Signer<'info>,
 
-    /// CHECK: Handled by metadata program
+    /// CHECK: Handled by metadata program. The LP mint itself for a soulbound StakePool.
     #[account(mut)]
     pub metadata_account: UncheckedAccount<'info>,
 
@@ -26,15 +50,130 @@ pub struct UpdateTokenMeta<'info> {
     /// CHECK: Handled by has_one with stake_mint
     pub stake_mint: UncheckedAccount<'info>,
 
-    /// CHECK: Handled by address check
-    #[account(
-      address = mpl_token_metadata::ID
-    )]
+    /// CHECK: Metaplex, or Token-2022 for a soulbound StakePool. Checked in the handler.
     pub metadata_program: UncheckedAccount<'info>,
     pub rent: Sysvar<'info, Rent>,
     pub system_program: Program<'info, System>,
 }
 
+impl<'info> UpdateTokenMeta<'info> {
+    pub fn validate_metadata_account(&self, stake_pool: &StakePool) -> Result<()> {
+        if stake_pool.stake_mint_is_soulbound() {
+            require_keys_eq!(
+                self.metadata_account.key(),
+                self.stake_mint.key(),
+                ErrorCode::InvalidMetadataAccount
+            );
+            require_keys_eq!(
+                self.metadata_program.key(),
+                spl_token_2022::ID,
+                ErrorCode::InvalidMetadataAccount
+            );
+        } else {
+            require_keys_eq!(
+                self.metadata_program.key(),
+                mpl_token_metadata::ID,
+                ErrorCode::InvalidMetadataAccount
+            );
+        }
+        Ok(())
+    }
+
+    /// Whether the metadata has to be created rather than updated
+    pub fn metadata_is_empty(&self, stake_pool: &StakePool) -> Result<bool> {
+        if !stake_pool.stake_mint_is_soulbound() {
+            return Ok(self.metadata_account.data_is_empty());
+        }
+        let data = self.metadata_account.try_borrow_data()?;
+        let stake_mint = StateWithExtensions::<Mint>::unpack(&data)?;
+        Ok(!stake_mint
+            .get_extension_types()?
+            .contains(&ExtensionType::TokenMetadata))
+    }
+
+    /// Writes the `TokenMetadata` extension of a soulbound LP mint. The mint is reallocated
+    /// to fit it, so `authority` first tops up its rent.
+    pub fn write_mint_metadata(
+        &self,
+        stake_pool: &StakePool,
+        name: String,
+        symbol: String,
+        uri: String,
+        created: bool,
+    ) -> Result<()> {
+        // the authorities are fixed size, the defaults size the extension just the same
+        let metadata = TokenMetadata {
+            name: name.clone(),
+            symbol: symbol.clone(),
+            uri: uri.clone(),
+            ..TokenMetadata::default()
+        };
+        let space =
+            ExtensionType::try_calculate_account_len::<Mint>(&SOULBOUND_STAKE_MINT_EXTENSIONS)?
+                + EXTENSION_HEADER_LEN
+                + metadata.try_to_vec()?.len();
+        let lamports = Rent::get()?
+            .minimum_balance(space)
+            .saturating_sub(self.metadata_account.lamports());
+        if lamports > 0 {
+            system_program::transfer(
+                CpiContext::new(
+                    self.system_program.to_account_info(),
+                    Transfer {
+                        from: self.authority.to_account_info(),
+                        to: self.metadata_account.to_account_info(),
+                    },
+                ),
+                lamports,
+            )?;
+        }
+
+        let stake_mint = self.stake_mint.key();
+        let stake_pool_key = self.stake_pool.key();
+        let instructions = if created {
+            vec![initialize(
+                &spl_token_2022::ID,
+                &stake_mint,
+                &stake_pool_key,
+                &stake_mint,
+                &stake_pool_key,
+                name,
+                symbol,
+                uri,
+            )]
+        } else {
+            [
+                (Field::Name, name),
+                (Field::Symbol, symbol),
+                (Field::Uri, uri),
+            ]
+            .into_iter()
+            .map(|(field, value)| {
+                update_field(
+                    &spl_token_2022::ID,
+                    &stake_mint,
+                    &stake_pool_key,
+                    field,
+                    value,
+                )
+            })
+            .collect()
+        };
+        let account_infos = [
+            self.metadata_account.to_account_info(),
+            self.stake_pool.to_account_info(),
+        ];
+        for instruction in instructions {
+            invoke_signed(
+                &instruction,
+                &account_infos,
+                &[stake_pool_signer_seeds!(stake_pool)],
+            )?;
+        }
+        Ok(())
+    }
+}
+
 pub fn handler(
     ctx: Context<UpdateTokenMeta>,
     name: String,
@@ -42,6 +181,25 @@ pub fn handler(
     uri: String,
 ) -> Result<()> {
     let stake_pool = ctx.accounts.stake_pool.load()?;
+    ctx.accounts.validate_metadata_account(&stake_pool)?;
+    let created = ctx.accounts.metadata_is_empty(&stake_pool)?;
+
+    let event = TokenMetaUpdated {
+        stake_pool: ctx.accounts.stake_pool.key(),
+        stake_mint: ctx.accounts.stake_mint.key(),
+        metadata_account: ctx.accounts.metadata_account.key(),
+        name: name.clone(),
+        symbol: symbol.clone(),
+        uri: uri.clone(),
+        created,
+    };
+
+    if stake_pool.stake_mint_is_soulbound() {
+        ctx.accounts
+            .write_mint_metadata(&stake_pool, name, symbol, uri, created)?;
+        emit!(event);
+        return Ok(());
+    }
 
     let data = DataV2 {
         name,
@@ -53,7 +211,7 @@ pub fn handler(
         uses: None,
     };
 
-    if ctx.accounts.metadata_account.data_is_empty() {
+    if created {
         let cpi_accounts = CreateMetadataAccountsV3 {
             metadata: ctx.accounts.metadata_account.to_account_info(),
             mint: ctx.accounts.stake_mint.to_account_info(),
@@ -84,5 +242,6 @@ pub fn handler(
         update_metadata_accounts_v2(ctx, None, Some(data), None, Some(true))?;
     }
 
+    emit!(event);
     Ok(())
 }
@@ -1,129 +1,231 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::{self, Burn, Mint, TokenAccount, Transfer};
+use anchor_spl::token_interface::{
+    self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
 
-use crate::{errors::ErrorCode, stake_pool_signer_seeds, state::StakeDepositReceipt};
+use crate::{
+    errors::ErrorCode,
+    events::Withdrawn,
+    stake_pool_signer_seeds,
+    state::{StakeDepositReceipt, StakePool, UserStakeIndex},
+};
 
 use super::claim_base::*;
-use crate::state::u128;
 
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     pub claim_base: ClaimBase<'info>,
     #[account(mut)]
-    pub vault: Account<'info, TokenAccount>,
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// StakePool mint, passed for `transfer_checked`
+    pub mint: InterfaceAccount<'info, Mint>,
     #[account(mut)]
-    pub stake_mint: Account<'info, Mint>,
+    pub stake_mint: InterfaceAccount<'info, Mint>,
+    /// LP token account burned from, held by the owner or by `lp_authority`
     #[account(mut)]
-    pub from: Account<'info, TokenAccount>,
+    pub from: InterfaceAccount<'info, TokenAccount>,
     #[account(mut)]
-    pub destination: Account<'info, TokenAccount>,
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    /// Token program of `stake_mint`, see `StakePool::stake_token_program`
+    pub stake_token_program: Interface<'info, TokenInterface>,
+    /// CHECK: The owner's `UserStakeIndex`, updated when it exists. Owners who have not
+    /// deposited since the index was introduced have none.
+    #[account(
+      mut,
+      seeds = [
+        claim_base.owner.key().as_ref(),
+        claim_base.stake_pool.key().as_ref(),
+        b"userStakeIndex",
+      ],
+      bump,
+    )]
+    pub user_stake_index: UncheckedAccount<'info>,
+    /// CHECK: Receives the receipt's rent, see `StakePool::rent_recipient`
+    #[account(mut)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// Holder of `from` when the owner's LP tokens have moved to another wallet, signs for
+    /// the burn in place of the owner
+    pub lp_authority: Option<Signer<'info>>,
 }
 
 impl<'info> Withdraw<'info> {
     pub fn validate_stake_pool_and_owner(&self) -> Result<()> {
         let stake_pool = self.claim_base.stake_pool.load()?;
-        require!(
-            stake_pool.vault.key() == self.vault.key(),
-            ErrorCode::InvalidStakePoolVault
-        );
-        require!(
-            stake_pool.stake_mint.key() == self.stake_mint.key(),
-            ErrorCode::InvalidStakeMint
-        );
-        require!(
-            self.from.owner.key() == self.claim_base.owner.key(),
-            ErrorCode::InvalidAuthority
+        require_keys_eq!(
+            self.rent_recipient.key(),
+            stake_pool.rent_recipient(&self.claim_base.stake_deposit_receipt),
+            ErrorCode::InvalidRentRecipient
         );
-        Ok(())
+        validate_withdraw_accounts(
+            &stake_pool,
+            &self.vault.key(),
+            &self.mint.key(),
+            &self.stake_mint.key(),
+            &self.from,
+            &self.destination,
+            &self.lp_authority().key(),
+        )
+    }
+
+    /// Signer of the LP burn, the owner unless `lp_authority` is passed
+    pub fn lp_authority(&self) -> AccountInfo<'info> {
+        match &self.lp_authority {
+            Some(lp_authority) => lp_authority.to_account_info(),
+            None => self.claim_base.owner.to_account_info(),
+        }
     }
+
     pub fn transfer_staked_tokens_to_owner(&self) -> Result<()> {
         let stake_pool = self.claim_base.stake_pool.load()?;
         let signer_seeds: &[&[&[u8]]] = &[stake_pool_signer_seeds!(stake_pool)];
         let cpi_ctx = CpiContext::new_with_signer(
             self.claim_base.token_program.to_account_info(),
-            Transfer {
+            TransferChecked {
                 from: self.vault.to_account_info(),
+                mint: self.mint.to_account_info(),
                 to: self.destination.to_account_info(),
                 authority: self.claim_base.stake_pool.to_account_info(),
             },
             signer_seeds,
         );
-        token::transfer(
+        // any transfer fee is withheld from what the owner receives
+        token_interface::transfer_checked(
             cpi_ctx,
             self.claim_base.stake_deposit_receipt.deposit_amount,
+            self.mint.decimals,
         )
     }
 
-    pub fn burn_stake_weight_tokens_from_owner(&self) -> Result<()> {
+    pub fn burn_stake_weight_tokens_from_owner(&self) -> Result<u64> {
         let stake_pool = self.claim_base.stake_pool.load()?;
         let cpi_ctx = CpiContext::new(
-            self.claim_base.token_program.to_account_info(),
+            self.stake_token_program.to_account_info(),
             Burn {
                 mint: self.stake_mint.to_account_info(),
                 from: self.from.to_account_info(),
-                authority: self.claim_base.owner.to_account_info(),
+                authority: self.lp_authority(),
             },
         );
         let effective_stake_token_amount = StakeDepositReceipt::get_token_amount_from_stake(
             self.claim_base.stake_deposit_receipt.effective_stake_u128(),
             stake_pool.max_weight,
-        );
-        token::burn(cpi_ctx, effective_stake_token_amount)
+        )?;
+        token_interface::burn(cpi_ctx, effective_stake_token_amount)?;
+        Ok(effective_stake_token_amount)
+    }
+
+    pub fn remove_from_user_stake_index(&self) -> Result<()> {
+        remove_from_user_stake_index(
+            &self.user_stake_index,
+            std::slice::from_ref(&self.claim_base.stake_deposit_receipt),
+        )
     }
 
     pub fn close_stake_deposit_receipt(&self) -> Result<()> {
         self.claim_base
             .stake_deposit_receipt
-            .close(self.claim_base.owner.to_account_info())
+            .close(self.rent_recipient.to_account_info())
     }
 }
 
+/// Checks shared by `withdraw` and `withdraw_many` that the token accounts belong to the
+/// StakePool and that `from` is held by the signer of the LP burn
+pub fn validate_withdraw_accounts(
+    stake_pool: &StakePool,
+    vault: &Pubkey,
+    mint: &Pubkey,
+    stake_mint: &Pubkey,
+    from: &TokenAccount,
+    destination: &TokenAccount,
+    lp_authority: &Pubkey,
+) -> Result<()> {
+    require!(stake_pool.vault == *vault, ErrorCode::InvalidStakePoolVault);
+    require!(stake_pool.mint == *mint, ErrorCode::InvalidStakeTokenMint);
+    require!(
+        stake_pool.stake_mint == *stake_mint,
+        ErrorCode::InvalidStakeMint
+    );
+    require!(
+        from.mint == stake_pool.stake_mint,
+        ErrorCode::InvalidLpTokenMint
+    );
+    require!(from.owner == *lp_authority, ErrorCode::InvalidLpTokenOwner);
+    require!(
+        destination.mint == stake_pool.mint,
+        ErrorCode::InvalidStakeTokenMint
+    );
+    Ok(())
+}
+
+/// Drops withdrawn receipts from the owner's `UserStakeIndex`, when the owner has one
+pub fn remove_from_user_stake_index(
+    user_stake_index: &AccountInfo,
+    stake_deposit_receipts: &[Account<StakeDepositReceipt>],
+) -> Result<()> {
+    if *user_stake_index.owner != crate::ID {
+        return Ok(());
+    }
+    let mut index = UserStakeIndex::try_deserialize(&mut &user_stake_index.try_borrow_data()?[..])?;
+    for stake_deposit_receipt in stake_deposit_receipts {
+        index.record_withdrawal(&stake_deposit_receipt.key(), stake_deposit_receipt)?;
+    }
+    index.try_serialize(&mut &mut user_stake_index.try_borrow_mut_data()?[..])
+}
+
 pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>) -> Result<()> {
     ctx.accounts.validate_stake_pool_and_owner()?;
 
     let escape_hatch_enabled: bool;
     let withdraw_ignores_lp: bool;
+    let total_weighted_stake;
     {
         let mut stake_pool = ctx.accounts.claim_base.stake_pool.load_mut()?;
         escape_hatch_enabled = stake_pool.escape_hatch_enabled();
         withdraw_ignores_lp = stake_pool.withdraw_ignores_lp();
 
         // Recalculate rewards for stake prior, so withdrawing user can receive all rewards
-        stake_pool.recalculate_rewards_per_effective_stake(&ctx.remaining_accounts, 2usize)?;
+        stake_pool.recalculate_rewards_per_effective_stake(
+            ctx.remaining_accounts,
+            CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL,
+        )?;
         // Decrement total weighted stake for future deposit reward ownership to be calculated correctly
-        let total_staked = stake_pool
-            .total_weighted_stake_u128()
-            .checked_sub(
-                ctx.accounts
-                    .claim_base
-                    .stake_deposit_receipt
-                    .effective_stake_u128(),
-            )
-            .unwrap();
-        stake_pool.total_weighted_stake = u128(total_staked.to_le_bytes());
+        total_weighted_stake =
+            stake_pool.record_withdrawal(&ctx.accounts.claim_base.stake_deposit_receipt)?;
     } // release mutable borrow of stake_pool
 
     if !escape_hatch_enabled {
         ctx.accounts
             .claim_base
             .stake_deposit_receipt
-            .validate_unlocked()?;
+            .validate_unlocked(Clock::get()?.unix_timestamp)?;
     }
 
     ctx.accounts.transfer_staked_tokens_to_owner()?;
+    let mut lp_burned = 0;
     if !withdraw_ignores_lp {
-        ctx.accounts.burn_stake_weight_tokens_from_owner()?;
+        lp_burned = ctx.accounts.burn_stake_weight_tokens_from_owner()?;
     }
     // claim all unclaimed rewards
-    let claimed_amounts = ctx
-        .accounts
-        .claim_base
-        .transfer_all_claimable_rewards(&ctx.remaining_accounts)?;
-
+    let claimed_amounts = ctx.accounts.claim_base.settle_claimable_rewards()?;
     ctx.accounts
         .claim_base
-        .update_reward_pools_last_amount(claimed_amounts)?;
+        .transfer_claimed_rewards(ctx.remaining_accounts, claimed_amounts)?;
+
+    let stake_deposit_receipt = &ctx.accounts.claim_base.stake_deposit_receipt;
+    emit!(Withdrawn {
+        stake_pool: stake_deposit_receipt.stake_pool,
+        stake_deposit_receipt: stake_deposit_receipt.key(),
+        owner: stake_deposit_receipt.owner,
+        lp_authority: ctx.accounts.lp_authority().key(),
+        rent_recipient: ctx.accounts.rent_recipient.key(),
+        amount: stake_deposit_receipt.deposit_amount,
+        effective_stake: stake_deposit_receipt.effective_stake_u128(),
+        lp_burned,
+        escape_hatch_enabled,
+        total_weighted_stake,
+    });
 
+    ctx.accounts.remove_from_user_stake_index()?;
     ctx.accounts.close_stake_deposit_receipt()?;
     Ok(())
 }
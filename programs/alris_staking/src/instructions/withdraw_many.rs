@@ -0,0 +1,263 @@
+use anchor_lang::prelude::*;
+use anchor_spl::token_interface::{
+    self, Burn, Mint, TokenAccount, TokenInterface, TransferChecked,
+};
+
+use crate::{
+    checked_math,
+    errors::ErrorCode,
+    events::Withdrawn,
+    stake_pool_signer_seeds,
+    state::{StakeDepositReceipt, StakePool, MAX_REWARD_POOLS},
+};
+
+use super::claim_base::*;
+use super::withdraw::{remove_from_user_stake_index, validate_withdraw_accounts};
+
+#[derive(Accounts)]
+pub struct WithdrawMany<'info> {
+    #[account(mut)]
+    pub owner: Signer<'info>,
+    #[account(mut)]
+    pub stake_pool: AccountLoader<'info, StakePool>,
+    #[account(mut)]
+    pub vault: InterfaceAccount<'info, TokenAccount>,
+    /// StakePool mint, passed for `transfer_checked`
+    pub mint: InterfaceAccount<'info, Mint>,
+    #[account(mut)]
+    pub stake_mint: InterfaceAccount<'info, Mint>,
+    /// LP token account burned from, held by the owner or by `lp_authority`
+    #[account(mut)]
+    pub from: InterfaceAccount<'info, TokenAccount>,
+    #[account(mut)]
+    pub destination: InterfaceAccount<'info, TokenAccount>,
+    /// Token program of the StakePool mint
+    pub token_program: Interface<'info, TokenInterface>,
+    /// Token program of `stake_mint`, see `StakePool::stake_token_program`
+    pub stake_token_program: Interface<'info, TokenInterface>,
+    /// CHECK: The owner's `UserStakeIndex`, see `Withdraw::user_stake_index`
+    #[account(
+      mut,
+      seeds = [
+        owner.key().as_ref(),
+        stake_pool.key().as_ref(),
+        b"userStakeIndex",
+      ],
+      bump,
+    )]
+    pub user_stake_index: UncheckedAccount<'info>,
+    /// CHECK: Receives the rent of every receipt, see `StakePool::rent_recipient`. Receipts
+    /// refunding different accounts are withdrawn in separate instructions.
+    #[account(mut)]
+    pub rent_recipient: UncheckedAccount<'info>,
+    /// Holder of `from` when the owner's LP tokens have moved to another wallet, signs for
+    /// the burn in place of the owner
+    pub lp_authority: Option<Signer<'info>>,
+}
+
+impl<'info> WithdrawMany<'info> {
+    pub fn validate_stake_pool_and_owner(&self) -> Result<()> {
+        let stake_pool = self.stake_pool.load()?;
+        validate_withdraw_accounts(
+            &stake_pool,
+            &self.vault.key(),
+            &self.mint.key(),
+            &self.stake_mint.key(),
+            &self.from,
+            &self.destination,
+            &self.lp_authority().key(),
+        )
+    }
+
+    /// Signer of the LP burn, the owner unless `lp_authority` is passed
+    pub fn lp_authority(&self) -> AccountInfo<'info> {
+        match &self.lp_authority {
+            Some(lp_authority) => lp_authority.to_account_info(),
+            None => self.owner.to_account_info(),
+        }
+    }
+
+    /// Loads one of the owner's receipts of this StakePool refunding `rent_recipient`
+    pub fn load_stake_deposit_receipt(
+        &self,
+        receipt_info: &'info AccountInfo<'info>,
+    ) -> Result<Account<'info, StakeDepositReceipt>> {
+        require!(
+            receipt_info.is_writable,
+            anchor_lang::error::ErrorCode::ConstraintMut
+        );
+        let stake_deposit_receipt = Account::<StakeDepositReceipt>::try_from(receipt_info)?;
+        require_keys_eq!(
+            stake_deposit_receipt.owner,
+            self.owner.key(),
+            ErrorCode::InvalidOwner
+        );
+        require_keys_eq!(
+            stake_deposit_receipt.stake_pool,
+            self.stake_pool.key(),
+            ErrorCode::InvalidStakePool
+        );
+        require_keys_eq!(
+            self.rent_recipient.key(),
+            self.stake_pool
+                .load()?
+                .rent_recipient(&stake_deposit_receipt),
+            ErrorCode::InvalidRentRecipient
+        );
+        Ok(stake_deposit_receipt)
+    }
+
+    pub fn transfer_staked_tokens_to_owner(&self, amount: u64) -> Result<()> {
+        let stake_pool = self.stake_pool.load()?;
+        let signer_seeds: &[&[&[u8]]] = &[stake_pool_signer_seeds!(stake_pool)];
+        let cpi_ctx = CpiContext::new_with_signer(
+            self.token_program.to_account_info(),
+            TransferChecked {
+                from: self.vault.to_account_info(),
+                mint: self.mint.to_account_info(),
+                to: self.destination.to_account_info(),
+                authority: self.stake_pool.to_account_info(),
+            },
+            signer_seeds,
+        );
+        // any transfer fee is withheld from what the owner receives
+        token_interface::transfer_checked(cpi_ctx, amount, self.mint.decimals)
+    }
+
+    pub fn burn_stake_weight_tokens(&self, amount: u64) -> Result<()> {
+        let cpi_ctx = CpiContext::new(
+            self.stake_token_program.to_account_info(),
+            Burn {
+                mint: self.stake_mint.to_account_info(),
+                from: self.from.to_account_info(),
+                authority: self.lp_authority(),
+            },
+        );
+        token_interface::burn(cpi_ctx, amount)
+    }
+}
+
+/// `withdraw` over several receipts of one owner. The remaining accounts are the reward
+/// accounts of every active reward pool, as for `withdraw`, followed by the receipts.
+/// The principal, the LP burn and each reward pool's payout are a single transfer each.
+pub fn handler<'info>(ctx: Context<'_, '_, 'info, 'info, WithdrawMany<'info>>) -> Result<()> {
+    ctx.accounts.validate_stake_pool_and_owner()?;
+
+    let escape_hatch_enabled: bool;
+    let withdraw_ignores_lp: bool;
+    let reward_accounts_len: usize;
+    {
+        let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+        escape_hatch_enabled = stake_pool.escape_hatch_enabled();
+        withdraw_ignores_lp = stake_pool.withdraw_ignores_lp();
+
+        // Recalculate rewards for stake prior, so withdrawing user can receive all rewards
+        stake_pool.recalculate_rewards_per_effective_stake(
+            ctx.remaining_accounts,
+            CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL,
+        )?;
+        let active_reward_pools = stake_pool
+            .reward_pools
+            .iter()
+            .filter(|reward_pool| !reward_pool.is_empty())
+            .count();
+        reward_accounts_len = active_reward_pools * CLAIM_REMAINING_ACCOUNTS_PER_REWARD_POOL;
+    } // release mutable borrow of stake_pool
+
+    let Some(receipt_infos) = ctx.remaining_accounts.get(reward_accounts_len..) else {
+        return err!(ErrorCode::InvalidRewardPoolVaultIndex);
+    };
+    require!(
+        !receipt_infos.is_empty(),
+        ErrorCode::MissingStakeDepositReceipts
+    );
+    for (position, receipt_info) in receipt_infos.iter().enumerate() {
+        require!(
+            receipt_infos[..position]
+                .iter()
+                .all(|other| other.key != receipt_info.key),
+            ErrorCode::DuplicateStakeDepositReceipt
+        );
+    }
+    let mut stake_deposit_receipts = receipt_infos
+        .iter()
+        .map(|receipt_info| ctx.accounts.load_stake_deposit_receipt(receipt_info))
+        .collect::<Result<Vec<_>>>()?;
+
+    let now = Clock::get()?.unix_timestamp;
+    let lp_authority = ctx.accounts.lp_authority().key();
+    let mut amount: u64 = 0;
+    let mut lp_burned: u64 = 0;
+    let mut claimed_amounts = [0u64; MAX_REWARD_POOLS];
+    {
+        let mut stake_pool = ctx.accounts.stake_pool.load_mut()?;
+        for stake_deposit_receipt in &mut stake_deposit_receipts {
+            if !escape_hatch_enabled {
+                stake_deposit_receipt.validate_unlocked(now)?;
+            }
+            // Decrement total weighted stake for future deposit reward ownership to be calculated correctly
+            let total_weighted_stake = stake_pool.record_withdrawal(stake_deposit_receipt)?;
+
+            let receipt_claimed_amounts = stake_pool.settle_claims(stake_deposit_receipt)?;
+            emit_rewards_claimed(
+                &stake_pool,
+                ctx.accounts.stake_pool.key(),
+                stake_deposit_receipt.key(),
+                ctx.accounts.owner.key(),
+                receipt_claimed_amounts,
+            );
+            for (total, claimed) in claimed_amounts.iter_mut().zip(receipt_claimed_amounts) {
+                *total = checked_math!(*total, checked_add(claimed), ErrorCode::MathOverflow)?;
+            }
+
+            let receipt_lp_burned = if withdraw_ignores_lp {
+                0
+            } else {
+                StakeDepositReceipt::get_token_amount_from_stake(
+                    stake_deposit_receipt.effective_stake_u128(),
+                    stake_pool.max_weight,
+                )?
+            };
+            amount = checked_math!(
+                amount,
+                checked_add(stake_deposit_receipt.deposit_amount),
+                ErrorCode::MathOverflow
+            )?;
+            lp_burned = checked_math!(
+                lp_burned,
+                checked_add(receipt_lp_burned),
+                ErrorCode::MathOverflow
+            )?;
+
+            emit!(Withdrawn {
+                stake_pool: stake_deposit_receipt.stake_pool,
+                stake_deposit_receipt: stake_deposit_receipt.key(),
+                owner: stake_deposit_receipt.owner,
+                lp_authority,
+                rent_recipient: ctx.accounts.rent_recipient.key(),
+                amount: stake_deposit_receipt.deposit_amount,
+                effective_stake: stake_deposit_receipt.effective_stake_u128(),
+                lp_burned: receipt_lp_burned,
+                escape_hatch_enabled,
+                total_weighted_stake,
+            });
+        }
+    } // release mutable borrow of stake_pool
+
+    ctx.accounts.transfer_staked_tokens_to_owner(amount)?;
+    if !withdraw_ignores_lp {
+        ctx.accounts.burn_stake_weight_tokens(lp_burned)?;
+    }
+    transfer_claimed_rewards(
+        &ctx.accounts.owner.key(),
+        &ctx.accounts.stake_pool,
+        &ctx.remaining_accounts[..reward_accounts_len],
+        claimed_amounts,
+    )?;
+
+    remove_from_user_stake_index(&ctx.accounts.user_stake_index, &stake_deposit_receipts)?;
+    for stake_deposit_receipt in &stake_deposit_receipts {
+        stake_deposit_receipt.close(ctx.accounts.rent_recipient.to_account_info())?;
+    }
+    Ok(())
+}
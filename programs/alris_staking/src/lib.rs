@@ -1,11 +1,17 @@
 use anchor_lang::prelude::*;
 
+pub mod accounting;
+pub mod allowlist;
 pub mod errors;
+pub mod events;
 pub mod instructions;
 pub mod macros;
 pub mod math;
+pub mod pda;
 pub mod state;
+pub mod token_extensions;
 
+use crate::allowlist::AllowlistProof;
 use crate::instructions::*;
 
 declare_id!("ALRPAsu4Aqmb8VCVcnjFQo5XF2K8MCDGmTECvZw4YJVY");
@@ -20,43 +26,138 @@ pub mod alris_staking {
         max_weight: u64,
         min_duration: u64,
         max_duration: u64,
+        options: u8,
     ) -> Result<()> {
-        initialize_stake_pool::handler(ctx, nonce, max_weight, min_duration, max_duration)
+        initialize_stake_pool::handler(
+            ctx,
+            nonce,
+            max_weight,
+            min_duration,
+            max_duration,
+            options,
+        )
+    }
+
+    pub fn migrate_stake_pool(ctx: Context<MigrateStakePool>) -> Result<()> {
+        migrate_stake_pool::handler(ctx)
     }
 
     pub fn transfer_authority(ctx: Context<TransferAuthority>) -> Result<()> {
         transfer_authority::handler(ctx)
     }
 
-    pub fn dangerously_mint_lp(ctx: Context<DangerouslyMintLp>, amount: u64) -> Result<()> {
-        dangerously_mint_lp::handler(ctx, amount)
+    pub fn dangerously_mint_lp(
+        ctx: Context<DangerouslyMintLp>,
+        amount: u64,
+        reason: String,
+    ) -> Result<()> {
+        dangerously_mint_lp::handler(ctx, amount, reason)
+    }
+
+    pub fn burn_unbacked_lp(
+        ctx: Context<BurnUnbackedLp>,
+        amount: u64,
+        reason: String,
+    ) -> Result<()> {
+        burn_unbacked_lp::handler(ctx, amount, reason)
+    }
+
+    pub fn set_unbacked_lp_cap(ctx: Context<SetUnbackedLpCap>, cap: u64) -> Result<()> {
+        set_unbacked_lp_cap::handler(ctx, cap)
+    }
+
+    pub fn set_unbacked_lp_cap_authority(ctx: Context<SetUnbackedLpCapAuthority>) -> Result<()> {
+        set_unbacked_lp_cap_authority::handler(ctx)
+    }
+
+    pub fn set_deposit_caps(
+        ctx: Context<SetDepositCaps>,
+        max_total_deposit: u64,
+        max_deposit_per_owner: u64,
+    ) -> Result<()> {
+        set_deposit_caps::handler(ctx, max_total_deposit, max_deposit_per_owner)
+    }
+
+    pub fn set_min_deposit_amount(
+        ctx: Context<SetMinDepositAmount>,
+        min_deposit_amount: u64,
+    ) -> Result<()> {
+        set_min_deposit_amount::handler(ctx, min_deposit_amount)
+    }
+
+    pub fn set_deposit_allowlist(ctx: Context<SetDepositAllowlist>, root: [u8; 32]) -> Result<()> {
+        set_deposit_allowlist::handler(ctx, root)
     }
 
     pub fn add_reward_pool(ctx: Context<AddRewardPool>, index: u8) -> Result<()> {
         add_reward_pool::handler(ctx, index)
     }
 
+    pub fn fund_reward_pool(ctx: Context<FundRewardPool>, index: u8, amount: u64) -> Result<()> {
+        fund_reward_pool::handler(ctx, index, amount)
+    }
+
     pub fn set_flags(ctx: Context<SetFlags>, flags: u8) -> Result<()> {
         set_flags::handler(ctx, flags)
     }
 
+    pub fn set_reward_pool_policy(
+        ctx: Context<SetRewardPoolPolicy>,
+        index: u8,
+        policy: u8,
+        release_period: u64,
+    ) -> Result<()> {
+        set_reward_pool_policy::handler(ctx, index, policy, release_period)
+    }
+
+    pub fn sweep_undistributed(ctx: Context<SweepUndistributed>, index: u8) -> Result<()> {
+        sweep_undistributed::handler(ctx, index)
+    }
+
+    pub fn sweep_reward_dust(ctx: Context<SweepRewardDust>, index: u8) -> Result<()> {
+        sweep_reward_dust::handler(ctx, index)
+    }
+
     pub fn deposit<'info>(
         ctx: Context<'_, '_, 'info, 'info, Deposit<'info>>,
         nonce: u32,
         amount: u64,
         lockup_duration: u64,
+        allowlist_proof: Option<AllowlistProof>,
     ) -> Result<()> {
-        deposit::handler(ctx, nonce, amount, lockup_duration)
+        deposit::handler(ctx, nonce, amount, lockup_duration, allowlist_proof)
     }
 
     pub fn claim_all<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimAll<'info>>) -> Result<()> {
         claim_all::handler(ctx)
     }
 
+    /// `claim_all` over several receipts of the owner, passed after the reward accounts
+    pub fn claim_many<'info>(ctx: Context<'_, '_, 'info, 'info, ClaimMany<'info>>) -> Result<()> {
+        claim_many::handler(ctx)
+    }
+
     pub fn withdraw<'info>(ctx: Context<'_, '_, 'info, 'info, Withdraw<'info>>) -> Result<()> {
         withdraw::handler(ctx)
     }
 
+    /// `withdraw` over several unlocked receipts of the owner, passed after the reward
+    /// accounts
+    pub fn withdraw_many<'info>(
+        ctx: Context<'_, '_, 'info, 'info, WithdrawMany<'info>>,
+    ) -> Result<()> {
+        withdraw_many::handler(ctx)
+    }
+
+    /// Read-only, the claimable amounts by reward pool index are set as return data
+    // The length is `MAX_REWARD_POOLS` spelled out, the IDL parser can't resolve constants
+    // in return types
+    pub fn get_claimable<'info>(
+        ctx: Context<'_, '_, 'info, 'info, GetClaimable<'info>>,
+    ) -> Result<[u64; 10]> {
+        get_claimable::handler(ctx)
+    }
+
     pub fn update_token_meta(
         ctx: Context<UpdateTokenMeta>,
         name: String,
@@ -10,3 +10,28 @@ macro_rules! stake_pool_signer_seeds {
         ]
     };
 }
+
+/// Applies a `checked_*` arithmetic method, mapping `None` to the given error with both
+/// operands attached so they are logged with it.
+#[macro_export]
+macro_rules! checked_math {
+    ($lhs:expr, $method:ident($rhs:expr), $error:expr) => {{
+        let lhs = $lhs;
+        let rhs = $rhs;
+        lhs.$method(rhs)
+            .ok_or_else(|| anchor_lang::error!($error).with_values((lhs, rhs)))
+    }};
+}
+
+/// Converts between integer types, mapping failure to `ErrorCode::ConversionFailure` with
+/// the value and target type logged.
+#[macro_export]
+macro_rules! checked_cast {
+    ($value:expr, $ty:ty) => {{
+        let value = $value;
+        <$ty>::try_from(value).map_err(|_| {
+            anchor_lang::error!($crate::errors::ErrorCode::ConversionFailure)
+                .with_values((value, stringify!($ty)))
+        })
+    }};
+}
@@ -5,6 +5,7 @@
 #![allow(clippy::ptr_offset_with_cast)]
 #![allow(clippy::manual_range_contains)]
 #![allow(clippy::reversed_empty_ranges)]
+#![allow(clippy::manual_div_ceil)]
 
 use uint::construct_uint;
 
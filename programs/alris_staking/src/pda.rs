@@ -0,0 +1,83 @@
+//! Creation of program derived accounts that the `Accounts` structs can't `init`, either
+//! because they are created conditionally or under another program.
+
+use anchor_lang::{
+    prelude::*,
+    system_program::{self, Allocate, Assign, CreateAccount, Transfer},
+};
+
+/// Creates the PDA `account` with `space` bytes owned by `owner`, `payer` funding its rent.
+/// Same as Anchor's `init`, an address that was sent lamports up front is topped up,
+/// allocated and assigned instead.
+pub fn create_pda_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    space: usize,
+    owner: &Pubkey,
+    signer_seeds: &[&[&[u8]]],
+) -> Result<()> {
+    if account.lamports() == 0 {
+        return system_program::create_account(
+            CpiContext::new_with_signer(
+                system_program.clone(),
+                CreateAccount {
+                    from: payer.clone(),
+                    to: account.clone(),
+                },
+                signer_seeds,
+            ),
+            rent.minimum_balance(space),
+            space as u64,
+            owner,
+        );
+    }
+    fund_account(payer, account, system_program, rent, space)?;
+    system_program::allocate(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Allocate {
+                account_to_allocate: account.clone(),
+            },
+            signer_seeds,
+        ),
+        space as u64,
+    )?;
+    system_program::assign(
+        CpiContext::new_with_signer(
+            system_program.clone(),
+            Assign {
+                account_to_assign: account.clone(),
+            },
+            signer_seeds,
+        ),
+        owner,
+    )
+}
+
+/// Tops `account` up from `payer` to the rent exempt minimum of `space` bytes
+pub fn fund_account<'info>(
+    payer: &AccountInfo<'info>,
+    account: &AccountInfo<'info>,
+    system_program: &AccountInfo<'info>,
+    rent: &Rent,
+    space: usize,
+) -> Result<()> {
+    let lamports = rent
+        .minimum_balance(space)
+        .saturating_sub(account.lamports());
+    if lamports == 0 {
+        return Ok(());
+    }
+    system_program::transfer(
+        CpiContext::new(
+            system_program.clone(),
+            Transfer {
+                from: payer.clone(),
+                to: account.clone(),
+            },
+        ),
+        lamports,
+    )
+}
@@ -1,20 +1,45 @@
 use anchor_lang::prelude::*;
-use anchor_spl::token::TokenAccount;
+use anchor_spl::token_interface::TokenAccount;
 use bytemuck::{Pod, Zeroable};
 use core::primitive;
 use jet_proc_macros::assert_size;
 
-use crate::{errors::ErrorCode, math::U192};
+use crate::{
+    accounting::{self, AccountingError},
+    checked_cast, checked_math,
+    errors::ErrorCode,
+};
+
+pub use crate::accounting::{SCALE_FACTOR_BASE, SCALE_FACTOR_BASE_SQUARED};
 
 pub const MAX_REWARD_POOLS: usize = 10;
-pub const SCALE_FACTOR_BASE: u64 = 1_000_000_000;
-pub const SCALE_FACTOR_BASE_SQUARED: u64 = 1_000_000_000_000_000_000;
 pub const SECONDS_PER_DAY: u64 = 60 * 60 * 24;
 
 pub const ESCAPE_HATCH_ENABLED: u8 = 1;
 pub const DISABLE_DEPOSITS: u8 = 4;
 pub const DEPOSIT_IGNORES_LP: u8 = 8;
 pub const WITHDRAW_IGNORES_LP: u8 = 16;
+/// Withdrawals refund receipt rent to the owner rather than to the receipt's payer
+pub const REFUND_RENT_TO_OWNER: u8 = 32;
+
+/// `StakePool::options` are fixed when the StakePool is created, unlike `flags`.
+/// The LP mint is a Token-2022 non-transferable mint carrying its own metadata.
+pub const SOULBOUND_STAKE_MINT: u8 = 1;
+
+/// Longest reason `dangerously_mint_lp` and `burn_unbacked_lp` accept, in bytes
+pub const MAX_UNBACKED_LP_REASON_LEN: usize = 200;
+
+/// Most open receipts one `UserStakeIndex` lists, keeping the index that `withdraw` rewrites
+/// small
+pub const MAX_USER_STAKE_INDEX_RECEIPTS: usize = 64;
+
+/// Rewards that arrive while nothing is staked are held in the vault and released to
+/// stakers over `RewardPool::release_period`
+pub const UNDISTRIBUTED_HOLD: u8 = 0;
+/// Rewards that arrive while nothing is staked are left for the authority to sweep to the
+/// `undistributed_recipient` it set, e.g. back to the funder or to a treasury
+pub const UNDISTRIBUTED_SWEEP: u8 = 1;
+pub const DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD: u64 = 7 * SECONDS_PER_DAY;
 
 #[allow(non_camel_case_types)]
 #[derive(Copy, Clone, Default, Zeroable, AnchorDeserialize, AnchorSerialize, Pod, Debug)]
@@ -27,30 +52,42 @@ impl u128 {
     }
 }
 
-pub fn get_digit_shift_by_max_scalar(max_weight: u64) -> u8 {
-    let mut digit_shift = 0u32;
-    while primitive::u128::from(max_weight)
-        .checked_mul(primitive::u128::from(u64::MAX))
-        .unwrap()
-        .checked_div(primitive::u128::from(SCALE_FACTOR_BASE))
-        .unwrap()
-        .checked_div(primitive::u128::pow(10, digit_shift))
-        .unwrap()
-        .gt(&primitive::u128::from(u64::MAX))
-    {
-        digit_shift += 1;
-    }
-    digit_shift.try_into().unwrap()
+pub fn get_digit_shift_by_max_scalar(max_weight: u64) -> Result<u8> {
+    accounting::get_digit_shift_by_max_scalar(max_weight)
+        .map_err(|error| error.with_values((max_weight, SCALE_FACTOR_BASE)))
 }
 
-#[assert_size(64)]
-#[derive(Clone, Copy, Default, AnchorDeserialize, AnchorSerialize, Pod, Zeroable)]
+#[assert_size(192)]
+#[derive(Clone, Copy, AnchorDeserialize, AnchorSerialize, Pod, Zeroable)]
 #[repr(C)]
 pub struct RewardPool {
     pub reward_vault: Pubkey,
     pub rewards_per_effective_stake: u128,
     pub last_amount: u64,
-    _padding0: [u8; 8],
+    /// Cumulative amount transferred in through `fund_reward_pool`
+    pub total_funded: u64,
+    /// Tokens that arrived while nothing was staked and have not been credited to stakers.
+    /// Always included in `last_amount`.
+    pub undistributed_amount: u64,
+    /// Start of the running `UNDISTRIBUTED_HOLD` release, the first accrual with stakers
+    /// after the tokens were held
+    pub release_start_timestamp: i64,
+    /// Seconds it takes to release `undistributed_amount` under `UNDISTRIBUTED_HOLD`
+    pub release_period: u64,
+    /// Token account receiving `undistributed_amount` under `UNDISTRIBUTED_SWEEP`
+    pub undistributed_recipient: Pubkey,
+    pub undistributed_policy: u8,
+    _padding0: [u8; 7],
+    /// Scaled rewards left over by the integer division in the last accrual, carried into
+    /// the next one
+    pub reward_remainder: u128,
+    /// Scaled rewards floored away from receipt claims. Whole tokens of it can never be
+    /// claimed and may be swept by the authority.
+    pub unclaimable_dust: u128,
+    /// `undistributed_amount` when the running release started, zero when none is running.
+    /// What it exceeds `undistributed_amount` by has been released.
+    pub release_amount: u64,
+    _reserved0: [u8; 24],
 }
 
 impl RewardPool {
@@ -59,17 +96,137 @@ impl RewardPool {
     }
 
     pub fn new(reward_vault: &Pubkey) -> Self {
-        let mut res = Self::default();
-        res.reward_vault = *reward_vault;
-        res
+        Self {
+            reward_vault: *reward_vault,
+            release_period: DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD,
+            ..Self::zeroed()
+        }
     }
 
     pub fn rewards_per_effective_stake_u128(&self) -> primitive::u128 {
         self.rewards_per_effective_stake.as_u128()
     }
+
+    /// Books the difference between the reward vault balance and `last_amount`
+    /// into `rewards_per_effective_stake`. While nothing is staked the difference is
+    /// set aside in `undistributed_amount` instead.
+    pub fn accrue(
+        &mut self,
+        vault_amount: u64,
+        total_weighted_stake: primitive::u128,
+        now: i64,
+    ) -> Result<()> {
+        if self.last_amount != vault_amount {
+            let balance_diff = checked_math!(
+                vault_amount,
+                checked_sub(self.last_amount),
+                ErrorCode::RewardVaultBalanceDecreased
+            )?;
+            self.last_amount = vault_amount;
+            if total_weighted_stake == 0 {
+                self.undistributed_amount = checked_math!(
+                    self.undistributed_amount,
+                    checked_add(balance_diff),
+                    ErrorCode::MathOverflow
+                )?;
+            } else {
+                self.book(balance_diff, total_weighted_stake)?;
+            }
+        }
+
+        if total_weighted_stake == 0 {
+            // nothing can be released without stakers, the next ones start a new release of
+            // everything still held
+            self.release_amount = 0;
+        } else if self.undistributed_policy == UNDISTRIBUTED_HOLD {
+            if self.release_amount == 0 {
+                self.release_amount = self.undistributed_amount;
+                self.release_start_timestamp = now;
+            }
+            let released = self.get_releasable_undistributed(now)?;
+            self.undistributed_amount = checked_math!(
+                self.undistributed_amount,
+                checked_sub(released),
+                ErrorCode::MathUnderflow
+            )?;
+            self.book(released, total_weighted_stake)?;
+            if self.undistributed_amount == 0 {
+                self.release_amount = 0;
+            }
+        }
+        Ok(())
+    }
+
+    /// Portion of `undistributed_amount` the running release has unlocked by `now` and not
+    /// yet released. The release is linear, `release_amount` is fully out `release_period`
+    /// seconds after `release_start_timestamp` however often it accrues.
+    pub fn get_releasable_undistributed(&self, now: i64) -> Result<u64> {
+        // a clock behind the release start releases nothing
+        let elapsed =
+            u64::try_from(now.saturating_sub(self.release_start_timestamp)).unwrap_or_default();
+        let unlocked =
+            accounting::get_unlocked_amount(self.release_amount, elapsed, self.release_period)
+                .map_err(|error| error.with_values((self.release_amount, elapsed)))?;
+        let released = self
+            .release_amount
+            .saturating_sub(self.undistributed_amount);
+        Ok(unlocked
+            .saturating_sub(released)
+            .min(self.undistributed_amount))
+    }
+
+    /// Stops the running release, the next accrual with stakers starts a new one over
+    /// `release_period` with what is still held
+    pub fn restart_release(&mut self) {
+        self.release_amount = 0;
+    }
+
+    fn book(&mut self, amount: u64, total_weighted_stake: primitive::u128) -> Result<()> {
+        if amount == 0 {
+            return Ok(());
+        }
+        let (rewards_per_effective_stake, remainder) =
+            accounting::accrue_rewards_per_effective_stake(
+                self.rewards_per_effective_stake_u128(),
+                self.reward_remainder.as_u128(),
+                amount,
+                total_weighted_stake,
+            )
+            .map_err(|error| error.with_values((amount, total_weighted_stake)))?;
+        self.rewards_per_effective_stake = u128(rewards_per_effective_stake.to_le_bytes());
+        self.reward_remainder = u128(remainder.to_le_bytes());
+        Ok(())
+    }
+
+    /// Rewards claimable by a receipt that last claimed at `claimed_rewards_per_effective_stake`,
+    /// along with the scaled dust the claim floors away.
+    pub fn get_claimable(
+        &self,
+        claimed_rewards_per_effective_stake: primitive::u128,
+        effective_stake: primitive::u128,
+    ) -> Result<(u64, primitive::u128)> {
+        accounting::get_claimable(
+            self.rewards_per_effective_stake_u128(),
+            claimed_rewards_per_effective_stake,
+            effective_stake,
+        )
+        .map_err(|error| {
+            error.with_values((
+                self.rewards_per_effective_stake_u128(),
+                claimed_rewards_per_effective_stake,
+            ))
+        })
+    }
+
+    /// Whole tokens of `unclaimable_dust`
+    pub fn get_sweepable_dust(&self) -> Result<u64> {
+        accounting::get_whole_tokens(self.unclaimable_dust.as_u128()).map_err(|error| {
+            error.with_values((self.unclaimable_dust.as_u128(), SCALE_FACTOR_BASE_SQUARED))
+        })
+    }
 }
 
-#[assert_size(1112)]
+#[assert_size(2392)]
 #[account(zero_copy)]
 #[repr(C)]
 pub struct StakePool {
@@ -87,12 +244,38 @@ pub struct StakePool {
     pub nonce: u8,
     pub bump_seed: u8,
     pub flags: u8,
-    _padding0: [u8; 5],
-    _reserved0: [u8; 256],
+    pub options: u8,
+    _padding0: [u8; 4],
+    /// Most LP tokens `dangerously_mint_lp` may have outstanding, zero until set. The
+    /// authority may only lower it, raising it takes `unbacked_lp_cap_authority`.
+    pub unbacked_lp_cap: u64,
+    /// LP tokens minted by `dangerously_mint_lp` less those burned by `burn_unbacked_lp`.
+    /// A burn counts against it whichever LP tokens it burns, so burning receipt backed LP
+    /// frees headroom under the cap. The LP supply exceeds what the receipts minted by at
+    /// most this much.
+    pub minted_unbacked: u64,
+    /// Most the receipts may hold in deposits altogether, zero for no cap
+    pub max_total_deposit: u64,
+    /// Most one owner's receipts may hold in deposits, as summed by its `UserStakeIndex`,
+    /// zero for no cap. While it is set every deposit must be authorized by its owner so the
+    /// index lists it.
+    pub max_deposit_per_owner: u64,
+    /// Sum of `deposit_amount` over the open receipts, seeded from the vault balance for a
+    /// StakePool migrated from the legacy layout
+    pub total_deposit_amount: u64,
+    /// Smallest amount one deposit may stake, after any transfer fee, zero for no minimum
+    pub min_deposit_amount: u64,
+    /// Root of the merkle allowlist of owners who may deposit, see `allowlist`, all zero
+    /// when anyone may deposit
+    pub deposit_allowlist_root: [u8; 32],
+    /// Key allowed to raise `unbacked_lp_cap`, named once by the authority, default while
+    /// nobody may raise it
+    pub unbacked_lp_cap_authority: Pubkey,
+    _reserved0: [u8; 144],
 }
 
 impl StakePool {
-    pub const LEN: usize = std::mem::size_of::<StakePool>();
+    pub const LEN: usize = core::mem::size_of::<StakePool>();
 
     pub fn total_weighted_stake_u128(&self) -> primitive::u128 {
         self.total_weighted_stake.as_u128()
@@ -114,6 +297,62 @@ impl StakePool {
         (self.flags & 0b0001_0000) != 0
     }
 
+    pub fn refunds_rent_to_owner(&self) -> bool {
+        (self.flags & REFUND_RENT_TO_OWNER) != 0
+    }
+
+    /// Account the rent of `receipt` is refunded to when it is withdrawn, the payer of the
+    /// deposit unless `REFUND_RENT_TO_OWNER` is set
+    pub fn rent_recipient(&self, receipt: &StakeDepositReceipt) -> Pubkey {
+        if self.refunds_rent_to_owner() {
+            receipt.owner
+        } else {
+            receipt.payer
+        }
+    }
+
+    pub fn is_unbacked_lp_cap_authority(&self, key: &Pubkey) -> bool {
+        self.unbacked_lp_cap_authority != Pubkey::default()
+            && self.unbacked_lp_cap_authority == *key
+    }
+
+    /// Books `amount` LP tokens minted without a receipt against `unbacked_lp_cap`
+    pub fn record_unbacked_mint(&mut self, amount: u64) -> Result<u64> {
+        let minted_unbacked = checked_math!(
+            self.minted_unbacked,
+            checked_add(amount),
+            ErrorCode::MathOverflow
+        )?;
+        require!(
+            minted_unbacked <= self.unbacked_lp_cap,
+            ErrorCode::UnbackedLpCapExceeded
+        );
+        self.minted_unbacked = minted_unbacked;
+        Ok(minted_unbacked)
+    }
+
+    pub fn record_unbacked_burn(&mut self, amount: u64) -> Result<u64> {
+        self.minted_unbacked = checked_math!(
+            self.minted_unbacked,
+            checked_sub(amount),
+            ErrorCode::UnbackedLpBurnExceedsMinted
+        )?;
+        Ok(self.minted_unbacked)
+    }
+
+    pub fn stake_mint_is_soulbound(&self) -> bool {
+        (self.options & SOULBOUND_STAKE_MINT) != 0
+    }
+
+    /// Token program of `stake_mint`, given the token program of `mint`
+    pub fn stake_token_program(&self, token_program: &Pubkey) -> Pubkey {
+        if self.stake_mint_is_soulbound() {
+            anchor_spl::token_2022::ID
+        } else {
+            *token_program
+        }
+    }
+
     pub fn get_claimed_amounts_of_reward_pools(&self) -> [u128; MAX_REWARD_POOLS] {
         let mut ret = [u128::default(); MAX_REWARD_POOLS];
         for (index, reward_pool) in self.reward_pools.iter().enumerate() {
@@ -122,18 +361,33 @@ impl StakePool {
         ret
     }
 
+    /// Accrues every active reward pool against the reward vaults found in
+    /// `remaining_accounts`, one every `reward_vault_account_offset` accounts.
     pub fn recalculate_rewards_per_effective_stake<'info>(
         &mut self,
         remaining_accounts: &'info [AccountInfo<'info>],
         reward_vault_account_offset: usize,
     ) -> Result<()> {
-        let total_weighted_stake = self.total_weighted_stake_u128();
-        if total_weighted_stake == 0 {
-            return Ok(());
-        }
+        let (vault_amounts, active_reward_pools) =
+            self.reward_vault_amounts(remaining_accounts, reward_vault_account_offset)?;
+        self.accrue_reward_pools(
+            &vault_amounts[..active_reward_pools],
+            Clock::get()?.unix_timestamp,
+        )
+    }
 
+    /// Balances of the reward vaults found in `remaining_accounts`, one every
+    /// `reward_vault_account_offset` accounts, along with the number of active reward pools
+    /// they were read for.
+    pub fn reward_vault_amounts<'info>(
+        &self,
+        remaining_accounts: &'info [AccountInfo<'info>],
+        reward_vault_account_offset: usize,
+    ) -> Result<([u64; MAX_REWARD_POOLS], usize)> {
+        let mut vault_amounts = [0u64; MAX_REWARD_POOLS];
         let mut remaining_accounts_index: usize = 0;
-        for reward_pool in &mut self.reward_pools {
+        let mut active_reward_pools: usize = 0;
+        for reward_pool in &self.reward_pools {
             if reward_pool.is_empty() {
                 continue;
             }
@@ -156,74 +410,325 @@ impl StakePool {
                 return err!(ErrorCode::InvalidRewardPoolVault);
             }
 
-            let token_account: Account<'info, TokenAccount> =
-                Account::try_from(&account_info).map_err(|_| ErrorCode::InvalidRewardPoolVault)?;
+            let token_account: InterfaceAccount<'info, TokenAccount> =
+                InterfaceAccount::try_from(account_info)
+                    .map_err(|_| ErrorCode::InvalidRewardPoolVault)?;
             remaining_accounts_index += reward_vault_account_offset;
 
-            if reward_pool.last_amount == token_account.amount {
-                continue;
+            vault_amounts[active_reward_pools] = token_account.amount;
+            active_reward_pools += 1;
+        }
+        Ok((vault_amounts, active_reward_pools))
+    }
+
+    pub fn get_stake_weight(&self, duration: u64) -> Result<u64> {
+        accounting::get_stake_weight(
+            self.base_weight,
+            self.max_weight,
+            self.min_duration,
+            self.max_duration,
+            duration,
+        )
+        .map_err(|error| match error {
+            AccountingError::InvalidStakePoolDuration => {
+                error.with_values((self.max_duration, self.min_duration))
+            }
+            AccountingError::InvalidStakePoolWeight => {
+                error.with_values((self.max_weight, self.base_weight))
             }
+            _ => error.with_values((duration, self.min_duration)),
+        })
+    }
+
+    /// Sets the weight curve and lockup bounds of a new pool
+    pub fn configure(
+        &mut self,
+        max_weight: u64,
+        min_duration: u64,
+        max_duration: u64,
+    ) -> Result<()> {
+        if min_duration > max_duration {
+            return err!(ErrorCode::InvalidStakePoolDuration);
+        }
+        if SCALE_FACTOR_BASE > max_weight {
+            return err!(ErrorCode::InvalidStakePoolWeight);
+        }
+        self.base_weight = SCALE_FACTOR_BASE;
+        self.max_weight = max_weight;
+        self.min_duration = min_duration;
+        self.max_duration = max_duration;
+        Ok(())
+    }
+
+    /// Accrues every active reward pool. `vault_amounts` holds the vault balance of each
+    /// active reward pool, in pool order.
+    pub fn accrue_reward_pools(&mut self, vault_amounts: &[u64], now: i64) -> Result<()> {
+        let total_weighted_stake = self.total_weighted_stake_u128();
+        let mut vault_amounts = vault_amounts.iter();
+        for reward_pool in self.reward_pools.iter_mut().filter(|pool| !pool.is_empty()) {
+            let vault_amount = vault_amounts
+                .next()
+                .ok_or(ErrorCode::InvalidRewardPoolVaultIndex)?;
+            reward_pool.accrue(*vault_amount, total_weighted_stake, now)?;
+        }
+        Ok(())
+    }
+
+    /// Books `amount` just transferred into the vault of the reward pool at `index`
+    pub fn record_funding(
+        &mut self,
+        index: usize,
+        vault_amount: u64,
+        amount: u64,
+        now: i64,
+    ) -> Result<()> {
+        if amount == 0 {
+            return err!(ErrorCode::InvalidAmount);
+        }
+        let total_weighted_stake = self.total_weighted_stake_u128();
+        if total_weighted_stake == 0 {
+            return err!(ErrorCode::NoStakeToReward);
+        }
+        let reward_pool = self
+            .reward_pools
+            .get_mut(index)
+            .filter(|reward_pool| !reward_pool.is_empty())
+            .ok_or(ErrorCode::InvalidRewardPoolIndex)?;
+        // Books the funded amount, along with any plain transfers made since the
+        // last accrual, against the current stakers.
+        reward_pool.accrue(vault_amount, total_weighted_stake, now)?;
+        reward_pool.total_funded = checked_math!(
+            reward_pool.total_funded,
+            checked_add(amount),
+            ErrorCode::MathOverflow
+        )?;
+        Ok(())
+    }
 
-            let balance_diff = primitive::u128::from(
-                token_account
-                    .amount
-                    .checked_sub(reward_pool.last_amount)
-                    .unwrap(),
+    /// Fills in the stake of a new `receipt` and adds it to `total_weighted_stake`. Reward
+    /// pools must be accrued beforehand. Returns the weight applied to `amount`.
+    pub fn record_deposit(
+        &mut self,
+        receipt: &mut StakeDepositReceipt,
+        amount: u64,
+        lockup_duration: u64,
+        now: i64,
+    ) -> Result<u64> {
+        if self.deposits_disabled() {
+            return err!(ErrorCode::DepositsDisabled);
+        }
+        if lockup_duration < self.min_duration {
+            return err!(ErrorCode::DurationTooShort);
+        }
+        if amount < self.min_deposit_amount {
+            return Err(
+                error!(ErrorCode::DepositTooSmall).with_values((amount, self.min_deposit_amount))
             );
+        }
+        // clamp lockup duration to the max
+        let lockup_duration = u64::min(lockup_duration, self.max_duration);
+        let weight = self.get_stake_weight(lockup_duration)?;
+        let effective_stake = StakeDepositReceipt::get_effective_stake_amount(weight, amount)?;
+        // the digit shift can round a dust deposit down to no LP while it still adds weight
+        if StakeDepositReceipt::get_token_amount_from_stake(effective_stake, self.max_weight)? == 0
+        {
+            return err!(ErrorCode::ZeroLpDeposit);
+        }
+        let total_deposit_amount = checked_math!(
+            self.total_deposit_amount,
+            checked_add(amount),
+            ErrorCode::MathOverflow
+        )?;
+        if self.max_total_deposit != 0 && total_deposit_amount > self.max_total_deposit {
+            return err!(ErrorCode::TotalDepositCapExceeded);
+        }
+        self.total_deposit_amount = total_deposit_amount;
 
-            let scaled_balance_diff = balance_diff
-                .checked_mul(primitive::u128::from(SCALE_FACTOR_BASE_SQUARED))
-                .unwrap();
+        receipt.deposit_amount = amount;
+        receipt.effective_stake = u128(effective_stake.to_le_bytes());
+        receipt.lockup_duration = lockup_duration;
+        receipt.deposit_timestamp = now;
+        // iterate over reward pools setting the initial "claimed" amount based on `rewards_per_effective_stake`.
+        //  Setting these claimed amounts to the current rewards per effective stake, marks where this
+        //  deposit receipt can start accumulating rewards. Now any more rewards added to a reward pool will
+        //  be claimable, on a pro-rated basis, by this stake receipt.
+        receipt.claimed_amounts = self.get_claimed_amounts_of_reward_pools();
 
-            let additional_rewards_per_effective_stake = scaled_balance_diff
-                .checked_div(total_weighted_stake)
-                .unwrap();
+        let total_staked = checked_math!(
+            self.total_weighted_stake_u128(),
+            checked_add(effective_stake),
+            ErrorCode::MathOverflow
+        )?;
+        self.total_weighted_stake = u128(total_staked.to_le_bytes());
+        Ok(weight)
+    }
 
-            reward_pool.last_amount = token_account.amount;
-            let rewards_updated = reward_pool
-                .rewards_per_effective_stake_u128()
-                .checked_add(additional_rewards_per_effective_stake)
-                .unwrap();
+    /// Refuses deposits the owner did not authorize while `max_deposit_per_owner` is set, its
+    /// `UserStakeIndex` could not sum them
+    pub fn validate_owner_funded(&self, owner_funded: bool) -> Result<()> {
+        if self.max_deposit_per_owner != 0 && !owner_funded {
+            return err!(ErrorCode::MissingOwnerAuthorization);
+        }
+        Ok(())
+    }
 
-            reward_pool.rewards_per_effective_stake = u128(rewards_updated.to_le_bytes());
+    /// Checks the deposits of one owner, `owner_deposit_amount` as summed by its
+    /// `UserStakeIndex` after a deposit the owner authorized, against `max_deposit_per_owner`
+    pub fn validate_owner_deposit_amount(&self, owner_deposit_amount: u64) -> Result<()> {
+        if self.max_deposit_per_owner != 0 && owner_deposit_amount > self.max_deposit_per_owner {
+            return err!(ErrorCode::OwnerDepositCapExceeded);
         }
         Ok(())
     }
 
-    pub fn get_stake_weight(&self, duration: u64) -> u64 {
-        if duration < self.min_duration {
-            panic!("Unreachable: the lockup is less than the minimum allowed")
+    /// Removes the stake of `receipt` from `total_weighted_stake` and returns the new total.
+    /// Reward pools must be accrued beforehand so the withdrawing receipt keeps its rewards.
+    pub fn record_withdrawal(&mut self, receipt: &StakeDepositReceipt) -> Result<primitive::u128> {
+        let total_staked = checked_math!(
+            self.total_weighted_stake_u128(),
+            checked_sub(receipt.effective_stake_u128()),
+            ErrorCode::MathUnderflow
+        )?;
+        self.total_weighted_stake = u128(total_staked.to_le_bytes());
+        self.total_deposit_amount = checked_math!(
+            self.total_deposit_amount,
+            checked_sub(receipt.deposit_amount),
+            ErrorCode::MathUnderflow
+        )?;
+        Ok(total_staked)
+    }
+
+    /// Amounts `receipt` could claim at `now` once the reward pools were accrued against
+    /// `vault_amounts`, as for `accrue_reward_pools`. Each reward pool is accrued on a copy,
+    /// so the StakePool is neither written nor copied whole.
+    pub fn preview_claimable_amounts(
+        &self,
+        receipt: &StakeDepositReceipt,
+        vault_amounts: &[u64],
+        now: i64,
+    ) -> Result<[u64; MAX_REWARD_POOLS]> {
+        let total_weighted_stake = self.total_weighted_stake_u128();
+        let mut vault_amounts = vault_amounts.iter();
+        let mut claimable_amounts = [0u64; MAX_REWARD_POOLS];
+        for (index, reward_pool) in self.reward_pools.iter().enumerate() {
+            if reward_pool.is_empty() {
+                continue;
+            }
+            let vault_amount = vault_amounts
+                .next()
+                .ok_or(ErrorCode::InvalidRewardPoolVaultIndex)?;
+            let mut reward_pool = *reward_pool;
+            reward_pool.accrue(*vault_amount, total_weighted_stake, now)?;
+            (claimable_amounts[index], _) = reward_pool.get_claimable(
+                receipt.claimed_amounts[index].as_u128(),
+                receipt.effective_stake_u128(),
+            )?;
         }
+        Ok(claimable_amounts)
+    }
 
-        let duration_span = self.max_duration.checked_sub(self.min_duration).unwrap();
-        if duration_span == 0 {
-            return self.base_weight;
+    /// Moves `receipt` up to the current `rewards_per_effective_stake` of every active reward
+    /// pool and returns the amounts owed to it, which the caller must transfer out of the
+    /// reward vaults.
+    pub fn settle_claims(
+        &mut self,
+        receipt: &mut StakeDepositReceipt,
+    ) -> Result<[u64; MAX_REWARD_POOLS]> {
+        let mut claimed_amounts = [0u64; MAX_REWARD_POOLS];
+        for (index, reward_pool) in self.reward_pools.iter_mut().enumerate() {
+            if reward_pool.is_empty() {
+                continue;
+            }
+            let (claimable, dust) = reward_pool.get_claimable(
+                receipt.claimed_amounts[index].as_u128(),
+                receipt.effective_stake_u128(),
+            )?;
+            reward_pool.last_amount = checked_math!(
+                reward_pool.last_amount,
+                checked_sub(claimable),
+                ErrorCode::MathUnderflow
+            )?;
+            // the fraction floored away by the claim is never claimable by this receipt again
+            let unclaimable_dust = checked_math!(
+                reward_pool.unclaimable_dust.as_u128(),
+                checked_add(dust),
+                ErrorCode::MathOverflow
+            )?;
+            reward_pool.unclaimable_dust = u128(unclaimable_dust.to_le_bytes());
+            receipt.claimed_amounts[index] = reward_pool.rewards_per_effective_stake;
+            claimed_amounts[index] = claimable;
         }
+        Ok(claimed_amounts)
+    }
+}
 
-        let duration_exceeding_min = u64::min(
-            duration.checked_sub(self.min_duration).unwrap(),
-            duration_span,
-        );
+/// `RewardPool` as laid out before the undistributed reward and rounding fields, kept so
+/// `migrate_stake_pool` can read StakePools created with it
+#[assert_size(64)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct LegacyRewardPool {
+    pub reward_vault: Pubkey,
+    pub rewards_per_effective_stake: u128,
+    pub last_amount: u64,
+    pub total_funded: u64,
+}
 
-        let normalized_weight = U192::from(duration_exceeding_min)
-            .checked_mul(U192::from(SCALE_FACTOR_BASE))
-            .unwrap()
-            .checked_div(U192::from(duration_span))
-            .unwrap();
-        let weight_diff = U192::from(self.max_weight)
-            .checked_sub(U192::from(self.base_weight))
-            .unwrap();
-        let calculated_weight = U192::from(self.base_weight)
-            .checked_add(
-                normalized_weight
-                    .checked_mul(weight_diff)
-                    .unwrap()
-                    .checked_div(U192::from(SCALE_FACTOR_BASE))
-                    .unwrap(),
-            )
-            .unwrap();
+/// `StakePool` as laid out with `LegacyRewardPool`s, 1280 bytes short of `StakePool`
+#[assert_size(1112)]
+#[derive(Clone, Copy, Pod, Zeroable)]
+#[repr(C)]
+pub struct LegacyStakePool {
+    pub creator: Pubkey,
+    pub authority: Pubkey,
+    pub total_weighted_stake: u128,
+    pub vault: Pubkey,
+    pub mint: Pubkey,
+    pub stake_mint: Pubkey,
+    pub reward_pools: [LegacyRewardPool; MAX_REWARD_POOLS],
+    pub base_weight: u64,
+    pub max_weight: u64,
+    pub min_duration: u64,
+    pub max_duration: u64,
+    pub nonce: u8,
+    pub bump_seed: u8,
+    pub flags: u8,
+    _padding0: [u8; 5],
+    _reserved0: [u8; 256],
+}
+
+impl LegacyStakePool {
+    pub const LEN: usize = core::mem::size_of::<LegacyStakePool>();
 
-        u64::max(calculated_weight.as_u64(), self.base_weight)
+    /// Writes the pool into a zeroed `StakePool`. Fields the legacy layout lacks start out
+    /// as on a new pool. `vault_amount` stands in for the deposits of the open receipts,
+    /// which the legacy layout did not sum.
+    pub fn migrate_into(&self, stake_pool: &mut StakePool, vault_amount: u64) {
+        stake_pool.creator = self.creator;
+        stake_pool.authority = self.authority;
+        stake_pool.total_weighted_stake = self.total_weighted_stake;
+        stake_pool.vault = self.vault;
+        stake_pool.mint = self.mint;
+        stake_pool.stake_mint = self.stake_mint;
+        for (reward_pool, legacy) in stake_pool.reward_pools.iter_mut().zip(&self.reward_pools) {
+            if legacy.reward_vault == Pubkey::default() {
+                continue;
+            }
+            *reward_pool = RewardPool {
+                rewards_per_effective_stake: legacy.rewards_per_effective_stake,
+                last_amount: legacy.last_amount,
+                total_funded: legacy.total_funded,
+                ..RewardPool::new(&legacy.reward_vault)
+            };
+        }
+        stake_pool.base_weight = self.base_weight;
+        stake_pool.max_weight = self.max_weight;
+        stake_pool.min_duration = self.min_duration;
+        stake_pool.max_duration = self.max_duration;
+        stake_pool.nonce = self.nonce;
+        stake_pool.bump_seed = self.bump_seed;
+        stake_pool.flags = self.flags;
+        stake_pool.total_deposit_amount = vault_amount;
     }
 }
 
@@ -240,7 +745,7 @@ pub struct StakeDepositReceipt {
 }
 
 impl StakeDepositReceipt {
-    pub const LEN: usize = std::mem::size_of::<StakeDepositReceipt>();
+    pub const LEN: usize = core::mem::size_of::<StakeDepositReceipt>();
 
     pub fn effective_stake_u128(&self) -> primitive::u128 {
         self.effective_stake.as_u128()
@@ -254,37 +759,151 @@ impl StakeDepositReceipt {
         claimed
     }
 
-    pub fn get_effective_stake_amount(weight: u64, amount: u64) -> primitive::u128 {
-        primitive::u128::from(amount)
-            .checked_mul(primitive::u128::from(weight))
-            .unwrap()
+    pub fn get_effective_stake_amount(weight: u64, amount: u64) -> Result<primitive::u128> {
+        accounting::get_effective_stake_amount(weight, amount)
+            .map_err(|error| error.with_values((amount, weight)))
     }
 
-    pub fn get_token_amount_from_stake(effective_stake: primitive::u128, max_weight: u64) -> u64 {
-        let digit_shift = get_digit_shift_by_max_scalar(max_weight);
-        effective_stake
-            .checked_div(primitive::u128::from(SCALE_FACTOR_BASE))
-            .unwrap()
-            .checked_div(10u128.pow(digit_shift.into()))
-            .unwrap()
-            .try_into()
-            .unwrap()
+    pub fn get_token_amount_from_stake(
+        effective_stake: primitive::u128,
+        max_weight: u64,
+    ) -> Result<u64> {
+        accounting::get_token_amount_from_stake(effective_stake, max_weight)
+            .map_err(|error| error.with_values((effective_stake, max_weight)))
     }
 
-    pub fn validate_unlocked(&self) -> Result<()> {
-        let current_timestamp = Clock::get()?.unix_timestamp;
-        if current_timestamp
-            < self
-                .deposit_timestamp
-                .checked_add(self.lockup_duration.try_into().unwrap())
-                .unwrap()
-        {
-            return Err(ErrorCode::StakeStillLocked.into());
+    pub fn validate_unlocked(&self, current_timestamp: i64) -> Result<()> {
+        let unlock_timestamp = checked_math!(
+            self.deposit_timestamp,
+            checked_add(checked_cast!(self.lockup_duration, i64)?),
+            ErrorCode::MathOverflow
+        )?;
+        if current_timestamp < unlock_timestamp {
+            return Err(error!(ErrorCode::StakeStillLocked)
+                .with_values((current_timestamp, unlock_timestamp)));
         }
         Ok(())
     }
 }
 
+/// Open receipts of one owner in one StakePool, kept by `deposit` and `withdraw` so clients
+/// can enumerate positions without scanning program accounts. Only deposits the owner
+/// authorized, by signing or through a `token_authority` drawing on its tokens, are listed,
+/// up to `MAX_USER_STAKE_INDEX_RECEIPTS`, so nobody else can grow the index. Receipts
+/// deposited before the index existed or by others staking their own tokens for the owner
+/// are not listed.
+#[account]
+#[derive(Default)]
+pub struct UserStakeIndex {
+    pub owner: Pubkey,
+    pub stake_pool: Pubkey,
+    /// Above the nonce of every receipt deposited through the index, the nonce to use next
+    pub next_nonce: u32,
+    /// Sum of `deposit_amount` over `receipts`
+    pub total_deposit_amount: u64,
+    /// Sum of `effective_stake` over `receipts`
+    pub total_effective_stake: u128,
+    pub receipts: Vec<Pubkey>,
+}
+
+impl UserStakeIndex {
+    /// Account size holding `receipts` receipts, the account grows as deposits need it to
+    pub const fn space(receipts: usize) -> usize {
+        8 + 32 + 32 + 4 + 8 + 16 + 4 + 32 * receipts
+    }
+
+    pub fn total_effective_stake_u128(&self) -> primitive::u128 {
+        self.total_effective_stake.as_u128()
+    }
+
+    pub fn record_deposit(
+        &mut self,
+        receipt: &Pubkey,
+        nonce: u32,
+        stake_deposit_receipt: &StakeDepositReceipt,
+    ) -> Result<()> {
+        if self.receipts.len() >= MAX_USER_STAKE_INDEX_RECEIPTS {
+            return err!(ErrorCode::UserStakeIndexFull);
+        }
+        self.next_nonce = u32::max(
+            self.next_nonce,
+            checked_math!(nonce, checked_add(1), ErrorCode::MathOverflow)?,
+        );
+        self.total_deposit_amount = checked_math!(
+            self.total_deposit_amount,
+            checked_add(stake_deposit_receipt.deposit_amount),
+            ErrorCode::MathOverflow
+        )?;
+        self.total_effective_stake = u128(
+            checked_math!(
+                self.total_effective_stake_u128(),
+                checked_add(stake_deposit_receipt.effective_stake_u128()),
+                ErrorCode::MathOverflow
+            )?
+            .to_le_bytes(),
+        );
+        self.receipts.push(*receipt);
+        Ok(())
+    }
+
+    /// Drops a withdrawn receipt, receipts the index does not list are left alone
+    pub fn record_withdrawal(
+        &mut self,
+        receipt: &Pubkey,
+        stake_deposit_receipt: &StakeDepositReceipt,
+    ) -> Result<()> {
+        let Some(position) = self.receipts.iter().position(|key| key == receipt) else {
+            return Ok(());
+        };
+        self.receipts.swap_remove(position);
+        self.total_deposit_amount = checked_math!(
+            self.total_deposit_amount,
+            checked_sub(stake_deposit_receipt.deposit_amount),
+            ErrorCode::MathUnderflow
+        )?;
+        self.total_effective_stake = u128(
+            checked_math!(
+                self.total_effective_stake_u128(),
+                checked_sub(stake_deposit_receipt.effective_stake_u128()),
+                ErrorCode::MathUnderflow
+            )?
+            .to_le_bytes(),
+        );
+        Ok(())
+    }
+}
+
+/// What one owner deposited into a StakePool while it had a deposit allowlist, kept by
+/// `deposit` to hold the owner to the `max_amount` of its allowlist leaf. Only deposits the
+/// owner signs count against it, and withdrawals do not give allowance back.
+#[account]
+#[derive(Default)]
+pub struct DepositAllowance {
+    pub owner: Pubkey,
+    pub stake_pool: Pubkey,
+    /// Sum of the amounts deposited against the allowlist, after any transfer fee
+    pub deposited_amount: u64,
+}
+
+impl DepositAllowance {
+    pub const LEN: usize = 32 + 32 + 8;
+
+    /// Adds `amount` to what was deposited, `max_amount` zero for no limit
+    pub fn record_deposit(&mut self, amount: u64, max_amount: u64) -> Result<()> {
+        let deposited_amount = checked_math!(
+            self.deposited_amount,
+            checked_add(amount),
+            ErrorCode::MathOverflow
+        )?;
+        if max_amount != 0 && deposited_amount > max_amount {
+            return Err(error!(ErrorCode::DepositAllowanceExceeded)
+                .with_values((deposited_amount, max_amount)));
+        }
+        self.deposited_amount = deposited_amount;
+        Ok(())
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -304,7 +923,7 @@ mod tests {
     }
 
     fn generic_stakepool() -> StakePool {
-        let base_weight = 1 * SCALE_FACTOR_BASE;
+        let base_weight = SCALE_FACTOR_BASE;
         let max_weight = 2 * SCALE_FACTOR_BASE;
         let min_duration = 100;
         let max_duration = 200;
@@ -313,11 +932,113 @@ mod tests {
     }
 
     #[test]
-    #[should_panic(expected = "Unreachable: the lockup is less than the minimum allowed")]
     fn get_stake_weight_duration_less_than_min() {
         let stake_pool = generic_stakepool();
         let min_duration = stake_pool.min_duration;
-        stake_pool.get_stake_weight(min_duration - 1);
+        assert_eq!(
+            stake_pool.get_stake_weight(min_duration - 1),
+            Err(ErrorCode::DurationTooShort.into())
+        );
+    }
+
+    #[test]
+    fn get_stake_weight_logs_its_inputs() {
+        let stake_pool = generic_stakepool();
+        let min_duration = stake_pool.min_duration;
+        match stake_pool.get_stake_weight(min_duration - 1) {
+            Err(Error::AnchorError(error)) => match error.compared_values {
+                Some(ComparedValues::Values((duration, min))) => {
+                    assert_eq!(duration, (min_duration - 1).to_string());
+                    assert_eq!(min, min_duration.to_string());
+                }
+                values => panic!("unexpected {values:?}"),
+            },
+            result => panic!("unexpected {result:?}"),
+        }
+    }
+
+    #[test]
+    fn get_stake_weight_max_duration_less_than_min() {
+        let mut stake_pool = generic_stakepool();
+        stake_pool.max_duration = stake_pool.min_duration - 1;
+        let min_duration = stake_pool.min_duration;
+        assert_eq!(
+            stake_pool.get_stake_weight(min_duration),
+            Err(ErrorCode::InvalidStakePoolDuration.into())
+        );
+    }
+
+    #[test]
+    fn get_stake_weight_max_weight_less_than_base() {
+        let mut stake_pool = generic_stakepool();
+        stake_pool.max_weight = stake_pool.base_weight - 1;
+        let max_duration = stake_pool.max_duration;
+        assert_eq!(
+            stake_pool.get_stake_weight(max_duration),
+            Err(ErrorCode::InvalidStakePoolWeight.into())
+        );
+    }
+
+    #[test]
+    fn get_token_amount_from_stake_conversion_failure() {
+        assert_eq!(
+            StakeDepositReceipt::get_token_amount_from_stake(
+                primitive::u128::MAX,
+                SCALE_FACTOR_BASE
+            ),
+            Err(ErrorCode::ConversionFailure.into())
+        );
+    }
+
+    #[test]
+    fn get_digit_shift_by_max_scalar_boundaries() {
+        assert_eq!(get_digit_shift_by_max_scalar(SCALE_FACTOR_BASE).unwrap(), 0);
+        assert_eq!(
+            get_digit_shift_by_max_scalar(SCALE_FACTOR_BASE + 1).unwrap(),
+            1
+        );
+        assert_eq!(
+            get_digit_shift_by_max_scalar(10 * SCALE_FACTOR_BASE).unwrap(),
+            1
+        );
+        assert_eq!(
+            get_digit_shift_by_max_scalar(10 * SCALE_FACTOR_BASE + 1).unwrap(),
+            2
+        );
+    }
+
+    #[test]
+    fn get_token_amount_from_stake_rounds_dust_to_zero() {
+        let max_weight = 2 * SCALE_FACTOR_BASE;
+        let stake = |weight, amount| {
+            StakeDepositReceipt::get_token_amount_from_stake(
+                StakeDepositReceipt::get_effective_stake_amount(weight, amount).unwrap(),
+                max_weight,
+            )
+            .unwrap()
+        };
+        // one digit of shift drops anything below ten base-weighted tokens
+        assert_eq!(stake(SCALE_FACTOR_BASE, 9), 0);
+        assert_eq!(stake(SCALE_FACTOR_BASE, 10), 1);
+        assert_eq!(stake(max_weight, 4), 0);
+        assert_eq!(stake(max_weight, 5), 1);
+        // without a shift a single token mints
+        assert_eq!(
+            StakeDepositReceipt::get_token_amount_from_stake(
+                primitive::u128::from(SCALE_FACTOR_BASE),
+                SCALE_FACTOR_BASE
+            )
+            .unwrap(),
+            1
+        );
+    }
+
+    #[test]
+    fn get_effective_stake_amount_max_values() {
+        assert_eq!(
+            StakeDepositReceipt::get_effective_stake_amount(u64::MAX, u64::MAX).unwrap(),
+            primitive::u128::from(u64::MAX) * primitive::u128::from(u64::MAX)
+        );
     }
 
     #[test]
@@ -325,7 +1046,10 @@ mod tests {
         let stake_pool = generic_stakepool();
         let base_weight = stake_pool.base_weight;
         let min_duration = stake_pool.min_duration;
-        assert_eq!(stake_pool.get_stake_weight(min_duration), base_weight);
+        assert_eq!(
+            stake_pool.get_stake_weight(min_duration).unwrap(),
+            base_weight
+        );
     }
 
     #[test]
@@ -337,7 +1061,7 @@ mod tests {
         let max_duration = stake_pool.max_duration;
         let mid_duration = (min_duration + max_duration) / 2;
         assert_eq!(
-            stake_pool.get_stake_weight(mid_duration),
+            stake_pool.get_stake_weight(mid_duration).unwrap(),
             (base_weight + max_weight) / 2
         );
     }
@@ -347,7 +1071,10 @@ mod tests {
         let stake_pool = generic_stakepool();
         let max_weight = stake_pool.max_weight;
         let max_duration = stake_pool.max_duration;
-        assert_eq!(stake_pool.get_stake_weight(max_duration), max_weight);
+        assert_eq!(
+            stake_pool.get_stake_weight(max_duration).unwrap(),
+            max_weight
+        );
     }
 
     #[test]
@@ -355,7 +1082,10 @@ mod tests {
         let stake_pool = generic_stakepool();
         let max_weight = stake_pool.max_weight;
         let max_duration = stake_pool.max_duration;
-        assert_eq!(stake_pool.get_stake_weight(max_duration + 1), max_weight);
+        assert_eq!(
+            stake_pool.get_stake_weight(max_duration + 1).unwrap(),
+            max_weight
+        );
     }
 
     #[test]
@@ -364,6 +1094,226 @@ mod tests {
         stake_pool.max_duration = stake_pool.min_duration;
         let base_weight = stake_pool.base_weight;
         let max_duration = stake_pool.max_duration;
-        assert_eq!(stake_pool.get_stake_weight(max_duration + 1), base_weight);
+        assert_eq!(
+            stake_pool.get_stake_weight(max_duration + 1).unwrap(),
+            base_weight
+        );
+    }
+    #[test]
+    fn reward_pool_accrue_books_balance_diff() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        let total_weighted_stake = primitive::u128::from(4 * SCALE_FACTOR_BASE);
+
+        reward_pool.accrue(100, total_weighted_stake, 0).unwrap();
+        assert_eq!(reward_pool.last_amount, 100);
+        assert_eq!(
+            reward_pool.rewards_per_effective_stake_u128(),
+            primitive::u128::from(25 * SCALE_FACTOR_BASE)
+        );
+
+        // unchanged balance books nothing
+        reward_pool.accrue(100, total_weighted_stake, 0).unwrap();
+        assert_eq!(
+            reward_pool.rewards_per_effective_stake_u128(),
+            primitive::u128::from(25 * SCALE_FACTOR_BASE)
+        );
+    }
+
+    #[test]
+    fn reward_pool_accrue_vault_balance_decreased() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        reward_pool.last_amount = 100;
+        assert_eq!(
+            reward_pool.accrue(99, primitive::u128::from(SCALE_FACTOR_BASE), 0),
+            Err(ErrorCode::RewardVaultBalanceDecreased.into())
+        );
+    }
+
+    #[test]
+    fn reward_pool_get_claimable_claimed_above_current() {
+        let reward_pool = RewardPool::new(&Pubkey::new_unique());
+        assert_eq!(
+            reward_pool.get_claimable(1, primitive::u128::from(SCALE_FACTOR_BASE)),
+            Err(ErrorCode::MathUnderflow.into())
+        );
+    }
+
+    #[test]
+    fn reward_pool_accrue_carries_remainder() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        let total_weighted_stake = primitive::u128::from(3 * SCALE_FACTOR_BASE_SQUARED);
+
+        // 1 token over 3 * 10^18 stake leaves a remainder of 10^18
+        reward_pool.accrue(1, total_weighted_stake, 0).unwrap();
+        assert_eq!(reward_pool.rewards_per_effective_stake_u128(), 0);
+        assert_eq!(
+            reward_pool.reward_remainder.as_u128(),
+            primitive::u128::from(SCALE_FACTOR_BASE_SQUARED)
+        );
+
+        reward_pool.accrue(3, total_weighted_stake, 0).unwrap();
+        assert_eq!(reward_pool.rewards_per_effective_stake_u128(), 1);
+        assert_eq!(reward_pool.reward_remainder.as_u128(), 0);
+    }
+
+    #[test]
+    fn reward_pool_get_claimable_reports_dust() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        reward_pool.rewards_per_effective_stake = u128(3u128.to_le_bytes());
+        let effective_stake = primitive::u128::from(SCALE_FACTOR_BASE_SQUARED) / 2;
+
+        let (claimable, dust) = reward_pool.get_claimable(0, effective_stake).unwrap();
+        assert_eq!(claimable, 1);
+        assert_eq!(dust, effective_stake);
+
+        reward_pool.unclaimable_dust = u128((dust * 3).to_le_bytes());
+        assert_eq!(reward_pool.get_sweepable_dust().unwrap(), 1);
+    }
+
+    #[test]
+    fn reward_pool_accrue_holds_rewards_while_nothing_staked() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        reward_pool.release_period = 100;
+
+        reward_pool.accrue(1_000, 0, 0).unwrap();
+        assert_eq!(reward_pool.last_amount, 1_000);
+        assert_eq!(reward_pool.undistributed_amount, 1_000);
+        assert_eq!(reward_pool.rewards_per_effective_stake_u128(), 0);
+
+        // time passing with nothing staked does not release anything
+        reward_pool.accrue(1_000, 0, 500).unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 1_000);
+
+        // the release starts with the first stakers
+        let total_weighted_stake = primitive::u128::from(SCALE_FACTOR_BASE);
+        reward_pool
+            .accrue(1_000, total_weighted_stake, 525)
+            .unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 1_000);
+        assert_eq!(reward_pool.release_amount, 1_000);
+
+        reward_pool
+            .accrue(1_000, total_weighted_stake, 550)
+            .unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 750);
+        assert_eq!(
+            reward_pool.rewards_per_effective_stake_u128(),
+            primitive::u128::from(250 * SCALE_FACTOR_BASE)
+        );
+
+        reward_pool
+            .accrue(1_000, total_weighted_stake, 625)
+            .unwrap();
+        assert_eq!(reward_pool.release_amount, 0);
+        assert_eq!(reward_pool.undistributed_amount, 0);
+        assert_eq!(
+            reward_pool.rewards_per_effective_stake_u128(),
+            primitive::u128::from(1_000 * SCALE_FACTOR_BASE)
+        );
+    }
+
+    #[test]
+    fn reward_pool_accrue_releases_linearly_however_often_it_accrues() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        let release_period = DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD;
+        let total_weighted_stake = primitive::u128::from(SCALE_FACTOR_BASE);
+        reward_pool.accrue(1_000, 0, 0).unwrap();
+
+        // each accrual alone would round its release down to nothing
+        let mut now = 0;
+        while now < release_period as i64 {
+            reward_pool
+                .accrue(1_000, total_weighted_stake, now)
+                .unwrap();
+            if now == release_period as i64 / 2 {
+                assert_eq!(reward_pool.undistributed_amount, 500);
+            }
+            now += 7;
+        }
+        reward_pool
+            .accrue(1_000, total_weighted_stake, release_period as i64)
+            .unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 0);
+        assert_eq!(
+            reward_pool.rewards_per_effective_stake_u128(),
+            primitive::u128::from(1_000 * SCALE_FACTOR_BASE)
+        );
+    }
+
+    #[test]
+    fn reward_pool_accrue_restarts_release_after_stakers_leave() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        reward_pool.release_period = 100;
+        let total_weighted_stake = primitive::u128::from(SCALE_FACTOR_BASE);
+
+        reward_pool.accrue(1_000, 0, 0).unwrap();
+        reward_pool.accrue(1_000, total_weighted_stake, 0).unwrap();
+        reward_pool.accrue(1_000, total_weighted_stake, 40).unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 600);
+
+        // the gap without stakers releases nothing, the rest goes out over a new period
+        reward_pool.accrue(1_200, 0, 90).unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 800);
+        reward_pool
+            .accrue(1_200, total_weighted_stake, 1_000)
+            .unwrap();
+        reward_pool
+            .accrue(1_200, total_weighted_stake, 1_050)
+            .unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 400);
+        reward_pool
+            .accrue(1_200, total_weighted_stake, 1_100)
+            .unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 0);
+    }
+
+    #[test]
+    fn reward_pool_accrue_keeps_undistributed_for_sweep() {
+        let mut reward_pool = RewardPool::new(&Pubkey::new_unique());
+        reward_pool.undistributed_policy = UNDISTRIBUTED_SWEEP;
+
+        reward_pool.accrue(1_000, 0, 0).unwrap();
+        reward_pool
+            .accrue(1_500, primitive::u128::from(SCALE_FACTOR_BASE), 1_000_000)
+            .unwrap();
+        assert_eq!(reward_pool.undistributed_amount, 1_000);
+        assert_eq!(
+            reward_pool.rewards_per_effective_stake_u128(),
+            primitive::u128::from(500 * SCALE_FACTOR_BASE)
+        );
+    }
+
+    #[test]
+    fn user_stake_index_is_bounded() {
+        let receipt = StakeDepositReceipt {
+            owner: Pubkey::new_unique(),
+            payer: Pubkey::new_unique(),
+            stake_pool: Pubkey::new_unique(),
+            lockup_duration: 0,
+            deposit_timestamp: 0,
+            deposit_amount: 10,
+            effective_stake: u128(10u128.to_le_bytes()),
+            claimed_amounts: Default::default(),
+        };
+        let mut index = UserStakeIndex::default();
+        for nonce in 0..MAX_USER_STAKE_INDEX_RECEIPTS as u32 {
+            index
+                .record_deposit(&Pubkey::new_unique(), nonce, &receipt)
+                .unwrap();
+        }
+        assert_eq!(
+            index.record_deposit(&Pubkey::new_unique(), 64, &receipt),
+            Err(ErrorCode::UserStakeIndexFull.into())
+        );
+        assert_eq!(index.total_deposit_amount, 640);
+
+        // withdrawing a listed receipt makes room again
+        let listed = index.receipts[0];
+        index.record_withdrawal(&listed, &receipt).unwrap();
+        index
+            .record_deposit(&Pubkey::new_unique(), 64, &receipt)
+            .unwrap();
+        assert_eq!(index.receipts.len(), MAX_USER_STAKE_INDEX_RECEIPTS);
+        assert_eq!(index.next_nonce, 65);
     }
 }
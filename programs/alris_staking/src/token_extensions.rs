@@ -0,0 +1,58 @@
+//! Token-2022 mint extensions accepted for staked and reward mints. Anything that can block,
+//! redirect or claw back transfers in and out of the pool's vaults is rejected when the
+//! StakePool or RewardPool is created. Classic token program mints have no extensions.
+
+use anchor_lang::prelude::*;
+use anchor_spl::token_2022::spl_token_2022::{
+    self,
+    extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+    state::Mint,
+};
+
+use crate::errors::ErrorCode;
+
+/// Extensions that leave vault transfers untouched
+const SUPPORTED_MINT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::MintCloseAuthority,
+    ExtensionType::InterestBearingConfig,
+    ExtensionType::MetadataPointer,
+    ExtensionType::TokenMetadata,
+];
+
+/// Extensions of a `SOULBOUND_STAKE_MINT` LP mint. The metadata pointer points at the mint
+/// itself, `update_token_meta` writes the `TokenMetadata` extension.
+pub const SOULBOUND_STAKE_MINT_EXTENSIONS: [ExtensionType; 2] = [
+    ExtensionType::NonTransferable,
+    ExtensionType::MetadataPointer,
+];
+
+/// The staked mint may also charge a transfer fee, `deposit` credits what the vault received.
+pub fn validate_stake_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    validate_mint_extensions(mint, &[ExtensionType::TransferFeeConfig])
+}
+
+/// Reward mints may not charge a transfer fee, claims pay out exactly what was accrued.
+pub fn validate_reward_mint_extensions(mint: &AccountInfo) -> Result<()> {
+    validate_mint_extensions(mint, &[])
+}
+
+fn validate_mint_extensions(mint: &AccountInfo, also_supported: &[ExtensionType]) -> Result<()> {
+    if *mint.owner != spl_token_2022::ID {
+        return Ok(());
+    }
+    let data = mint.try_borrow_data()?;
+    let mint_state = StateWithExtensions::<Mint>::unpack(&data)?;
+    // extensions unknown to this program fail to parse and are rejected as well
+    let extension_types = mint_state
+        .get_extension_types()
+        .map_err(|_| ErrorCode::UnsupportedMintExtension)?;
+    for extension_type in extension_types {
+        if !SUPPORTED_MINT_EXTENSIONS.contains(&extension_type)
+            && !also_supported.contains(&extension_type)
+        {
+            msg!("Unsupported mint extension {:?}", extension_type);
+            return err!(ErrorCode::UnsupportedMintExtension);
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,267 @@
+mod common;
+
+use alris_staking::errors::ErrorCode;
+use alris_staking_client::instructions::{self, DepositArgs};
+use anchor_spl::token::spl_token;
+use common::*;
+use solana_sdk::{pubkey::Pubkey, signature::Signer};
+
+const STAKE: u64 = 1_000_000_000;
+
+/// Sends `deposit` for `staker` with its token accounts replaced by `from` and `destination`
+async fn deposit_with(
+    test: &mut TestContext,
+    stake_pool: &Pubkey,
+    staker: &Staker,
+    from: &Pubkey,
+    destination: &Pubkey,
+) -> Result<(), solana_program_test::BanksClientError> {
+    let pool = test.stake_pool(stake_pool).await;
+    let owner = staker.owner.pubkey();
+    test.process(
+        &[instructions::deposit(
+            stake_pool,
+            &pool,
+            &owner,
+            &owner,
+            None,
+            from,
+            destination,
+            &spl_token::ID,
+            DepositArgs {
+                nonce: 0,
+                amount: STAKE,
+                lockup_duration: MIN_DURATION,
+                allowlist_proof: None,
+            },
+        )],
+        &[&staker.owner],
+    )
+    .await
+}
+
+/// Sends `withdraw` for `staker` with its token accounts replaced by `from`, `destination`
+/// and `reward_recipients`
+async fn withdraw_with(
+    test: &mut TestContext,
+    stake_pool: &Pubkey,
+    staker: &Staker,
+    receipt: &Pubkey,
+    from: &Pubkey,
+    destination: &Pubkey,
+    reward_recipients: &[Pubkey],
+) -> Result<(), solana_program_test::BanksClientError> {
+    let pool = test.stake_pool(stake_pool).await;
+    let reward_mints = test.reward_mints(stake_pool).await;
+    test.process(
+        &[instructions::withdraw(
+            stake_pool,
+            &pool,
+            &staker.owner.pubkey(),
+            receipt,
+            from,
+            None,
+            destination,
+            &staker.owner.pubkey(),
+            &spl_token::ID,
+            &reward_mints,
+            reward_recipients,
+        )
+        .unwrap()],
+        &[&staker.owner],
+    )
+    .await
+}
+
+async fn claim_with(
+    test: &mut TestContext,
+    stake_pool: &Pubkey,
+    staker: &Staker,
+    receipt: &Pubkey,
+    reward_recipients: &[Pubkey],
+) -> Result<(), solana_program_test::BanksClientError> {
+    let pool = test.stake_pool(stake_pool).await;
+    let reward_mints = test.reward_mints(stake_pool).await;
+    test.process(
+        &[instructions::claim_all(
+            stake_pool,
+            &pool,
+            &staker.owner.pubkey(),
+            receipt,
+            &spl_token::ID,
+            &reward_mints,
+            reward_recipients,
+        )
+        .unwrap()],
+        &[&staker.owner],
+    )
+    .await
+}
+
+#[tokio::test]
+async fn deposit_rejects_mismatched_token_accounts() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let other = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let foreign_mint = test.create_mint(9).await;
+    let foreign_tokens = test
+        .create_funded_token_account(&foreign_mint, &staker.owner.pubkey(), STAKE)
+        .await;
+
+    assert_program_error(
+        deposit_with(&mut test, &stake_pool, &staker, &foreign_tokens, &staker.lp).await,
+        ErrorCode::InvalidStakeTokenMint,
+    );
+    // LP minted into a token account of the staked mint
+    assert_program_error(
+        deposit_with(
+            &mut test,
+            &stake_pool,
+            &staker,
+            &staker.tokens,
+            &staker.tokens,
+        )
+        .await,
+        ErrorCode::InvalidLpTokenMint,
+    );
+    assert_program_error(
+        deposit_with(&mut test, &stake_pool, &staker, &staker.tokens, &other.lp).await,
+        ErrorCode::InvalidLpTokenOwner,
+    );
+
+    deposit_with(&mut test, &stake_pool, &staker, &staker.tokens, &staker.lp)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn withdraw_rejects_mismatched_token_accounts() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let other = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    test.deposit(&stake_pool, &other, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    test.advance_clock(MIN_DURATION as i64);
+
+    // principal paid out to a token account of the LP mint
+    assert_program_error(
+        withdraw_with(
+            &mut test,
+            &stake_pool,
+            &staker,
+            &receipt,
+            &staker.lp,
+            &staker.lp,
+            &[],
+        )
+        .await,
+        ErrorCode::InvalidStakeTokenMint,
+    );
+    assert_program_error(
+        withdraw_with(
+            &mut test,
+            &stake_pool,
+            &staker,
+            &receipt,
+            &staker.tokens,
+            &staker.tokens,
+            &[],
+        )
+        .await,
+        ErrorCode::InvalidLpTokenMint,
+    );
+    assert_program_error(
+        withdraw_with(
+            &mut test,
+            &stake_pool,
+            &staker,
+            &receipt,
+            &other.lp,
+            &staker.tokens,
+            &[],
+        )
+        .await,
+        ErrorCode::InvalidLpTokenOwner,
+    );
+
+    withdraw_with(
+        &mut test,
+        &stake_pool,
+        &staker,
+        &receipt,
+        &staker.lp,
+        &staker.tokens,
+        &[],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+}
+
+#[tokio::test]
+async fn claims_reject_mismatched_reward_recipients() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    test.add_reward_pool(&stake_pool, 0).await;
+    test.add_reward_pool(&stake_pool, 1).await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let other = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    test.fund_reward_pool(&stake_pool, 0, 1_000).await;
+    test.fund_reward_pool(&stake_pool, 1, 1_000).await;
+
+    let swapped = [staker.reward_accounts[1], staker.reward_accounts[0]];
+    assert_program_error(
+        claim_with(&mut test, &stake_pool, &staker, &receipt, &swapped).await,
+        ErrorCode::InvalidRewardRecipientMint,
+    );
+    assert_program_error(
+        claim_with(
+            &mut test,
+            &stake_pool,
+            &staker,
+            &receipt,
+            &other.reward_accounts,
+        )
+        .await,
+        ErrorCode::InvalidRewardRecipientOwner,
+    );
+
+    // withdraw pays rewards through the same validation
+    test.advance_clock(MIN_DURATION as i64);
+    assert_program_error(
+        withdraw_with(
+            &mut test,
+            &stake_pool,
+            &staker,
+            &receipt,
+            &staker.lp,
+            &staker.tokens,
+            &other.reward_accounts,
+        )
+        .await,
+        ErrorCode::InvalidRewardRecipientOwner,
+    );
+
+    claim_with(
+        &mut test,
+        &stake_pool,
+        &staker,
+        &receipt,
+        &staker.reward_accounts,
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 1_000);
+    assert_eq!(test.token_balance(&staker.reward_accounts[1]).await, 1_000);
+}
@@ -0,0 +1,893 @@
+//! In-process harness for the integration tests. The program runs natively under
+//! `solana-program-test`, Metaplex is replaced by a minimal mock and the clock is set
+//! explicitly before every transaction so lockups can be tested deterministically. Token
+//! helpers work with mints of either token program.
+
+#![allow(dead_code)]
+
+use std::sync::Once;
+
+use alris_staking::{
+    errors::ErrorCode,
+    state::{StakeDepositReceipt, StakePool, UserStakeIndex},
+};
+use alris_staking_client::{
+    accounts::{deserialize_stake_pool, deserialize_user_stake_index},
+    instructions::{self, DepositArgs, InitializeStakePoolArgs, RewardMint},
+    pda::{
+        find_stake_deposit_receipt_address, find_stake_pool_address, find_user_stake_index_address,
+    },
+};
+use anchor_lang::{
+    prelude::*,
+    solana_program::{
+        entrypoint::ProgramResult,
+        program::invoke_signed,
+        program_stubs::{self, SyscallStubs},
+        system_instruction,
+    },
+    AccountDeserialize, Event,
+};
+use anchor_spl::{
+    metadata::mpl_token_metadata,
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        extension::{
+            transfer_fee::instruction::initialize_transfer_fee_config, BaseStateWithExtensions,
+            ExtensionType, StateWithExtensions,
+        },
+    },
+};
+use base64::{prelude::BASE64_STANDARD, Engine};
+use solana_program_test::{processor, BanksClientError, ProgramTest, ProgramTestContext};
+use solana_sdk::{
+    instruction::{Instruction, InstructionError},
+    signature::{Keypair, Signer},
+    transaction::{Transaction, TransactionError},
+};
+
+pub const MIN_DURATION: u64 = 100;
+pub const MAX_DURATION: u64 = 1_000;
+pub const MAX_WEIGHT: u64 = 4_000_000_000;
+pub const START_TIMESTAMP: i64 = 1_700_000_000;
+
+/// Size of the mock metadata account, enough for any `DataV2` the tests write
+const MOCK_METADATA_LEN: usize = 512;
+
+/// Token-2022 mint extension set up by `create_mint_2022`
+#[derive(Clone, Copy, Debug)]
+pub enum MintExtension {
+    TransferFee { basis_points: u16, maximum_fee: u64 },
+    MintCloseAuthority,
+    NonTransferable,
+    PermanentDelegate,
+}
+
+impl MintExtension {
+    fn extension_type(self) -> ExtensionType {
+        match self {
+            MintExtension::TransferFee { .. } => ExtensionType::TransferFeeConfig,
+            MintExtension::MintCloseAuthority => ExtensionType::MintCloseAuthority,
+            MintExtension::NonTransferable => ExtensionType::NonTransferable,
+            MintExtension::PermanentDelegate => ExtensionType::PermanentDelegate,
+        }
+    }
+
+    /// Initializes the extension, `authority` holds every authority it has
+    fn instruction(self, mint: &Pubkey, authority: &Pubkey) -> Instruction {
+        let program_id = &spl_token_2022::ID;
+        match self {
+            MintExtension::TransferFee {
+                basis_points,
+                maximum_fee,
+            } => initialize_transfer_fee_config(
+                program_id,
+                mint,
+                Some(authority),
+                Some(authority),
+                basis_points,
+                maximum_fee,
+            ),
+            MintExtension::MintCloseAuthority => {
+                spl_token_2022::instruction::initialize_mint_close_authority(
+                    program_id,
+                    mint,
+                    Some(authority),
+                )
+            }
+            MintExtension::NonTransferable => {
+                spl_token_2022::instruction::initialize_non_transferable_mint(program_id, mint)
+            }
+            MintExtension::PermanentDelegate => {
+                spl_token_2022::instruction::initialize_permanent_delegate(
+                    program_id, mint, authority,
+                )
+            }
+        }
+        .unwrap()
+    }
+}
+
+/// Placeholder installed for the moment `LogDataSyscallStubs` takes over the stubs it wraps
+struct NullSyscallStubs;
+
+impl SyscallStubs for NullSyscallStubs {}
+
+/// The syscall stubs of `solana-program-test`, which print the `sol_log_data` of natively
+/// run programs to stdout, with `sol_log_data` written to the transaction logs instead so
+/// the emitted events can be read back. The line goes through `sol_log`, so it reads
+/// `Program log: Program data: <base64>` rather than the `Program data: <base64>` of the
+/// runtime.
+struct LogDataSyscallStubs(Box<dyn SyscallStubs>);
+
+impl SyscallStubs for LogDataSyscallStubs {
+    fn sol_log(&self, message: &str) {
+        self.0.sol_log(message)
+    }
+    fn sol_log_compute_units(&self) {
+        self.0.sol_log_compute_units()
+    }
+    fn sol_remaining_compute_units(&self) -> u64 {
+        self.0.sol_remaining_compute_units()
+    }
+    fn sol_invoke_signed(
+        &self,
+        instruction: &Instruction,
+        account_infos: &[AccountInfo],
+        signers_seeds: &[&[&[u8]]],
+    ) -> ProgramResult {
+        self.0
+            .sol_invoke_signed(instruction, account_infos, signers_seeds)
+    }
+    fn sol_get_clock_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_clock_sysvar(var_addr)
+    }
+    fn sol_get_epoch_schedule_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_schedule_sysvar(var_addr)
+    }
+    fn sol_get_fees_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_fees_sysvar(var_addr)
+    }
+    fn sol_get_rent_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_rent_sysvar(var_addr)
+    }
+    fn sol_get_epoch_rewards_sysvar(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_epoch_rewards_sysvar(var_addr)
+    }
+    fn sol_get_last_restart_slot(&self, var_addr: *mut u8) -> u64 {
+        self.0.sol_get_last_restart_slot(var_addr)
+    }
+    unsafe fn sol_memcpy(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memcpy(dst, src, n)
+    }
+    unsafe fn sol_memmove(&self, dst: *mut u8, src: *const u8, n: usize) {
+        self.0.sol_memmove(dst, src, n)
+    }
+    unsafe fn sol_memcmp(&self, s1: *const u8, s2: *const u8, n: usize, result: *mut i32) {
+        self.0.sol_memcmp(s1, s2, n, result)
+    }
+    unsafe fn sol_memset(&self, s: *mut u8, c: u8, n: usize) {
+        self.0.sol_memset(s, c, n)
+    }
+    fn sol_get_return_data(&self) -> Option<(Pubkey, Vec<u8>)> {
+        self.0.sol_get_return_data()
+    }
+    fn sol_set_return_data(&self, data: &[u8]) {
+        self.0.sol_set_return_data(data)
+    }
+    fn sol_log_data(&self, fields: &[&[u8]]) {
+        let fields: Vec<String> = fields
+            .iter()
+            .map(|field| BASE64_STANDARD.encode(field))
+            .collect();
+        self.0
+            .sol_log(&format!("Program data: {}", fields.join(" ")))
+    }
+    fn sol_get_processed_sibling_instruction(&self, index: usize) -> Option<Instruction> {
+        self.0.sol_get_processed_sibling_instruction(index)
+    }
+    fn sol_get_stack_height(&self) -> u64 {
+        self.0.sol_get_stack_height()
+    }
+}
+
+fn process_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    // the anchor entrypoint ties the slice and account lifetimes together
+    let accounts = Box::leak(Box::new(accounts.to_vec()));
+    alris_staking::entry(program_id, accounts, data)
+}
+
+/// Stand-in for the Metaplex program handling the two instructions `update_token_meta`
+/// sends. The metadata account holds the borsh encoded `DataV2`.
+fn process_metadata_instruction(
+    program_id: &Pubkey,
+    accounts: &[AccountInfo],
+    data: &[u8],
+) -> ProgramResult {
+    use mpl_token_metadata::instructions::{
+        CreateMetadataAccountV3InstructionArgs, UpdateMetadataAccountV2InstructionArgs,
+    };
+
+    let (discriminator, args) = data
+        .split_first()
+        .ok_or(ProgramError::InvalidInstructionData)?;
+    match discriminator {
+        33 => {
+            let args = CreateMetadataAccountV3InstructionArgs::try_from_slice(args)?;
+            let [metadata, mint, mint_authority, payer, _update_authority, system_program, ..] =
+                accounts
+            else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if !mint_authority.is_signer || !payer.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            let (address, bump) = mpl_token_metadata::accounts::Metadata::find_pda(mint.key);
+            if address != *metadata.key {
+                return Err(ProgramError::InvalidSeeds);
+            }
+            invoke_signed(
+                &system_instruction::create_account(
+                    payer.key,
+                    metadata.key,
+                    Rent::get()?.minimum_balance(MOCK_METADATA_LEN),
+                    MOCK_METADATA_LEN as u64,
+                    program_id,
+                ),
+                &[payer.clone(), metadata.clone(), system_program.clone()],
+                &[&[b"metadata", program_id.as_ref(), mint.key.as_ref(), &[bump]]],
+            )?;
+            args.data
+                .serialize(&mut &mut metadata.try_borrow_mut_data()?[..])?;
+        }
+        15 => {
+            let args = UpdateMetadataAccountV2InstructionArgs::try_from_slice(args)?;
+            let [metadata, update_authority, ..] = accounts else {
+                return Err(ProgramError::NotEnoughAccountKeys);
+            };
+            if !update_authority.is_signer {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            if let Some(data) = args.data {
+                data.serialize(&mut &mut metadata.try_borrow_mut_data()?[..])?;
+            }
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    }
+    Ok(())
+}
+
+pub struct TestContext {
+    pub context: ProgramTestContext,
+    /// Authority of the pools created through `create_stake_pool`
+    pub authority: Keypair,
+    pub now: i64,
+    /// Log messages of the transaction last run by `process`
+    pub logs: Vec<String>,
+}
+
+impl TestContext {
+    pub async fn new() -> Self {
+        let mut program_test = ProgramTest::new(
+            "alris_staking",
+            alris_staking::ID,
+            processor!(process_instruction),
+        );
+        program_test.add_program(
+            "mpl_token_metadata",
+            mpl_token_metadata::ID,
+            processor!(process_metadata_instruction),
+        );
+        let context = program_test.start_with_context().await;
+        // after `start_with_context` installs the stubs being wrapped, and before any
+        // transaction runs
+        static LOG_DATA_STUBS: Once = Once::new();
+        LOG_DATA_STUBS.call_once(|| {
+            let stubs = program_stubs::set_syscall_stubs(Box::new(NullSyscallStubs));
+            program_stubs::set_syscall_stubs(Box::new(LogDataSyscallStubs(stubs)));
+        });
+        let mut test = Self {
+            context,
+            authority: Keypair::new(),
+            now: START_TIMESTAMP,
+            logs: Vec::new(),
+        };
+        test.airdrop(&test.authority.pubkey(), 10_000_000_000).await;
+        test
+    }
+
+    pub fn payer(&self) -> Pubkey {
+        self.context.payer.pubkey()
+    }
+
+    pub fn advance_clock(&mut self, seconds: i64) {
+        self.now += seconds;
+    }
+
+    /// Moves to a new slot with the clock at `self.now` and processes `instructions`
+    /// signed by the payer and `signers`, keeping the logs in `self.logs`
+    pub async fn process(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> std::result::Result<(), BanksClientError> {
+        let transaction = self.transaction(instructions, signers).await;
+        let processed = self
+            .context
+            .banks_client
+            .process_transaction_with_metadata(transaction)
+            .await?;
+        self.logs = processed
+            .metadata
+            .map(|metadata| metadata.log_messages)
+            .unwrap_or_default();
+        processed.result.map_err(BanksClientError::TransactionError)
+    }
+
+    /// Events of type `E` the transaction last run by `process` emitted, decoded from its
+    /// `Program data:` logs, see `LogDataSyscallStubs`
+    pub fn events<E: Event>(&self) -> Vec<E> {
+        self.logs
+            .iter()
+            .filter_map(|log| log.strip_prefix("Program log: Program data: "))
+            .map(|data| BASE64_STANDARD.decode(data).unwrap())
+            .filter(|data| data.starts_with(&E::DISCRIMINATOR))
+            .map(|data| E::try_from_slice(&data[E::DISCRIMINATOR.len()..]).unwrap())
+            .collect()
+    }
+
+    /// Like `process` but only simulates, returning the return data
+    pub async fn simulate_return_data(&mut self, instructions: &[Instruction]) -> Vec<u8> {
+        let transaction = self.transaction(instructions, &[]).await;
+        let simulation = self
+            .context
+            .banks_client
+            .simulate_transaction(transaction)
+            .await
+            .unwrap();
+        simulation.result.unwrap().unwrap();
+        simulation
+            .simulation_details
+            .and_then(|details| details.return_data)
+            .map(|return_data| return_data.data)
+            .unwrap_or_default()
+    }
+
+    async fn transaction(
+        &mut self,
+        instructions: &[Instruction],
+        signers: &[&Keypair],
+    ) -> Transaction {
+        let slot = self.context.banks_client.get_root_slot().await.unwrap();
+        self.context.warp_to_slot(slot + 2).unwrap();
+        let mut clock: Clock = self.context.banks_client.get_sysvar().await.unwrap();
+        clock.unix_timestamp = self.now;
+        self.context.set_sysvar(&clock);
+
+        let mut all_signers = vec![&self.context.payer];
+        all_signers.extend_from_slice(signers);
+        Transaction::new_signed_with_payer(
+            instructions,
+            Some(&self.context.payer.pubkey()),
+            &all_signers,
+            self.context.last_blockhash,
+        )
+    }
+
+    pub async fn airdrop(&mut self, to: &Pubkey, lamports: u64) {
+        let payer = self.payer();
+        self.process(&[system_instruction::transfer(&payer, to, lamports)], &[])
+            .await
+            .unwrap();
+    }
+
+    /// Creates a mint with the payer as mint authority
+    pub async fn create_mint(&mut self, decimals: u8) -> Pubkey {
+        self.create_mint_with(&spl_token::ID, decimals, &[]).await
+    }
+
+    /// Creates a Token-2022 mint with `extensions` and the payer as every authority
+    pub async fn create_mint_2022(&mut self, decimals: u8, extensions: &[MintExtension]) -> Pubkey {
+        self.create_mint_with(&spl_token_2022::ID, decimals, extensions)
+            .await
+    }
+
+    async fn create_mint_with(
+        &mut self,
+        token_program: &Pubkey,
+        decimals: u8,
+        extensions: &[MintExtension],
+    ) -> Pubkey {
+        let mint = Keypair::new();
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let extension_types: Vec<ExtensionType> = extensions
+            .iter()
+            .map(|extension| extension.extension_type())
+            .collect();
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Mint>(
+            &extension_types,
+        )
+        .unwrap();
+        let mut ixs = vec![system_instruction::create_account(
+            &payer,
+            &mint.pubkey(),
+            rent.minimum_balance(len),
+            len as u64,
+            token_program,
+        )];
+        ixs.extend(
+            extensions
+                .iter()
+                .map(|extension| extension.instruction(&mint.pubkey(), &payer)),
+        );
+        ixs.push(
+            spl_token_2022::instruction::initialize_mint2(
+                token_program,
+                &mint.pubkey(),
+                &payer,
+                None,
+                decimals,
+            )
+            .unwrap(),
+        );
+        self.process(&ixs, &[&mint]).await.unwrap();
+        mint.pubkey()
+    }
+
+    /// Owner of a mint or token account
+    pub async fn token_program(&mut self, address: &Pubkey) -> Pubkey {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .unwrap()
+            .owner
+    }
+
+    /// Creates a token account of `mint` under the mint's token program, sized for the
+    /// account extensions the mint requires. Token-2022 accounts get an immutable owner like
+    /// associated token accounts do.
+    pub async fn create_token_account(&mut self, mint: &Pubkey, owner: &Pubkey) -> Pubkey {
+        let account = Keypair::new();
+        let payer = self.payer();
+        let rent = self.context.banks_client.get_rent().await.unwrap();
+        let token_program = self.token_program(mint).await;
+        let mint_data = self.account_data(mint).await.unwrap();
+        let mint_extension_types =
+            StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&mint_data)
+                .unwrap()
+                .get_extension_types()
+                .unwrap();
+        let mut account_extension_types =
+            ExtensionType::get_required_init_account_extensions(&mint_extension_types);
+        let mut instructions = vec![];
+        if token_program == spl_token_2022::ID {
+            account_extension_types.push(ExtensionType::ImmutableOwner);
+            instructions.push(
+                spl_token_2022::instruction::initialize_immutable_owner(
+                    &token_program,
+                    &account.pubkey(),
+                )
+                .unwrap(),
+            );
+        }
+        let len = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(
+            &account_extension_types,
+        )
+        .unwrap();
+        instructions.insert(
+            0,
+            system_instruction::create_account(
+                &payer,
+                &account.pubkey(),
+                rent.minimum_balance(len),
+                len as u64,
+                &token_program,
+            ),
+        );
+        instructions.push(
+            spl_token_2022::instruction::initialize_account3(
+                &token_program,
+                &account.pubkey(),
+                mint,
+                owner,
+            )
+            .unwrap(),
+        );
+        self.process(&instructions, &[&account]).await.unwrap();
+        account.pubkey()
+    }
+
+    pub async fn mint_to(&mut self, mint: &Pubkey, destination: &Pubkey, amount: u64) {
+        let payer = self.payer();
+        let token_program = self.token_program(mint).await;
+        self.process(
+            &[spl_token_2022::instruction::mint_to(
+                &token_program,
+                mint,
+                destination,
+                &payer,
+                &[],
+                amount,
+            )
+            .unwrap()],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    /// Creates a token account of `mint` for `owner` holding `amount`
+    pub async fn create_funded_token_account(
+        &mut self,
+        mint: &Pubkey,
+        owner: &Pubkey,
+        amount: u64,
+    ) -> Pubkey {
+        let account = self.create_token_account(mint, owner).await;
+        self.mint_to(mint, &account, amount).await;
+        account
+    }
+
+    async fn token_account(&mut self, address: &Pubkey) -> spl_token_2022::state::Account {
+        let data = self.account_data(address).await.unwrap();
+        StateWithExtensions::<spl_token_2022::state::Account>::unpack(&data)
+            .unwrap()
+            .base
+    }
+
+    pub async fn token_balance(&mut self, account: &Pubkey) -> u64 {
+        self.token_account(account).await.amount
+    }
+
+    pub async fn mint_decimals(&mut self, mint: &Pubkey) -> u8 {
+        let data = self.account_data(mint).await.unwrap();
+        StateWithExtensions::<spl_token_2022::state::Mint>::unpack(&data)
+            .unwrap()
+            .base
+            .decimals
+    }
+
+    pub async fn account_data(&mut self, address: &Pubkey) -> Option<Vec<u8>> {
+        self.context
+            .banks_client
+            .get_account(*address)
+            .await
+            .unwrap()
+            .map(|account| account.data)
+    }
+
+    pub async fn lamports(&mut self, address: &Pubkey) -> u64 {
+        self.context
+            .banks_client
+            .get_balance(*address)
+            .await
+            .unwrap()
+    }
+
+    pub async fn stake_pool(&mut self, address: &Pubkey) -> StakePool {
+        deserialize_stake_pool(&self.account_data(address).await.unwrap()).unwrap()
+    }
+
+    pub async fn stake_deposit_receipt(&mut self, address: &Pubkey) -> StakeDepositReceipt {
+        let data = self.account_data(address).await.unwrap();
+        StakeDepositReceipt::try_deserialize(&mut &data[..]).unwrap()
+    }
+
+    pub async fn user_stake_index(
+        &mut self,
+        owner: &Pubkey,
+        stake_pool: &Pubkey,
+    ) -> Option<UserStakeIndex> {
+        let (address, _) = find_user_stake_index_address(owner, stake_pool);
+        let data = self.account_data(&address).await?;
+        Some(deserialize_user_stake_index(&data).unwrap())
+    }
+
+    /// Creates a StakePool over a new 9 decimal mint with the test durations and weight
+    pub async fn create_stake_pool(&mut self) -> (Pubkey, Pubkey) {
+        let mint = self.create_mint(9).await;
+        let stake_pool = self.create_stake_pool_for(&mint).await.unwrap();
+        (stake_pool, mint)
+    }
+
+    /// Creates a StakePool over `mint` through the mint's token program
+    pub async fn create_stake_pool_for(
+        &mut self,
+        mint: &Pubkey,
+    ) -> std::result::Result<Pubkey, BanksClientError> {
+        let token_program = self.token_program(mint).await;
+        self.create_stake_pool_with_program(mint, &token_program)
+            .await
+    }
+
+    pub async fn create_stake_pool_with_program(
+        &mut self,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> std::result::Result<Pubkey, BanksClientError> {
+        self.create_stake_pool_with_options(mint, token_program, 0)
+            .await
+    }
+
+    pub async fn create_stake_pool_with_options(
+        &mut self,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        options: u8,
+    ) -> std::result::Result<Pubkey, BanksClientError> {
+        let authority = self.authority.insecure_clone();
+        let payer = self.payer();
+        self.process(
+            &[instructions::initialize_stake_pool(
+                &payer,
+                &authority.pubkey(),
+                mint,
+                token_program,
+                InitializeStakePoolArgs {
+                    nonce: 0,
+                    max_weight: MAX_WEIGHT,
+                    min_duration: MIN_DURATION,
+                    max_duration: MAX_DURATION,
+                    options,
+                },
+            )],
+            &[&authority],
+        )
+        .await?;
+        Ok(find_stake_pool_address(0, mint, &authority.pubkey()).0)
+    }
+
+    /// Adds a reward pool over a new 6 decimal mint and returns the mint
+    pub async fn add_reward_pool(&mut self, stake_pool: &Pubkey, index: u8) -> Pubkey {
+        let reward_mint = self.create_mint(6).await;
+        self.add_reward_pool_for(stake_pool, index, &reward_mint)
+            .await
+            .unwrap();
+        reward_mint
+    }
+
+    /// Adds a reward pool over `reward_mint` through the mint's token program
+    pub async fn add_reward_pool_for(
+        &mut self,
+        stake_pool: &Pubkey,
+        index: u8,
+        reward_mint: &Pubkey,
+    ) -> std::result::Result<(), BanksClientError> {
+        let reward_mint = RewardMint {
+            mint: *reward_mint,
+            token_program: self.token_program(reward_mint).await,
+        };
+        let authority = self.authority.insecure_clone();
+        let payer = self.payer();
+        self.process(
+            &[instructions::add_reward_pool(
+                stake_pool,
+                &payer,
+                &authority.pubkey(),
+                &reward_mint,
+                index,
+            )],
+            &[&authority],
+        )
+        .await
+    }
+
+    /// Mint and token program of every active reward pool, in pool order
+    pub async fn reward_mints(&mut self, stake_pool: &Pubkey) -> Vec<RewardMint> {
+        let pool = self.stake_pool(stake_pool).await;
+        let mut reward_mints = vec![];
+        for reward_pool in pool.reward_pools.iter().filter(|pool| !pool.is_empty()) {
+            reward_mints.push(self.reward_mint(&reward_pool.reward_vault).await);
+        }
+        reward_mints
+    }
+
+    /// Mint and token program of a reward vault
+    pub async fn reward_mint(&mut self, reward_vault: &Pubkey) -> RewardMint {
+        RewardMint {
+            mint: self.token_mint(reward_vault).await,
+            token_program: self.token_program(reward_vault).await,
+        }
+    }
+
+    pub async fn set_flags(
+        &mut self,
+        stake_pool: &Pubkey,
+        flags: u8,
+    ) -> std::result::Result<(), BanksClientError> {
+        let authority = self.authority.insecure_clone();
+        self.process(
+            &[instructions::set_flags(
+                stake_pool,
+                &authority.pubkey(),
+                flags,
+            )],
+            &[&authority],
+        )
+        .await
+    }
+
+    /// Transfers `amount` of reward tokens into the reward pool through `fund_reward_pool`
+    pub async fn fund_reward_pool(&mut self, stake_pool: &Pubkey, index: u8, amount: u64) {
+        let pool = self.stake_pool(stake_pool).await;
+        let vault = pool.reward_pools[usize::from(index)].reward_vault;
+        let reward_mint = self.reward_mint(&vault).await;
+        let payer = self.payer();
+        let from = self
+            .create_funded_token_account(&reward_mint.mint, &payer, amount)
+            .await;
+        self.process(
+            &[instructions::fund_reward_pool(
+                stake_pool,
+                &pool,
+                &payer,
+                &from,
+                &reward_mint,
+                index,
+                amount,
+            )],
+            &[],
+        )
+        .await
+        .unwrap();
+    }
+
+    pub async fn token_mint(&mut self, account: &Pubkey) -> Pubkey {
+        self.token_account(account).await.mint
+    }
+
+    pub async fn new_staker(&mut self, mint: &Pubkey, stake_pool: &Pubkey, amount: u64) -> Staker {
+        let owner = Keypair::new();
+        self.airdrop(&owner.pubkey(), 1_000_000_000).await;
+        let pool = self.stake_pool(stake_pool).await;
+        let tokens = self
+            .create_funded_token_account(mint, &owner.pubkey(), amount)
+            .await;
+        let lp = self
+            .create_token_account(&pool.stake_mint, &owner.pubkey())
+            .await;
+        let mut reward_accounts = vec![];
+        for reward_pool in pool.reward_pools.iter().filter(|pool| !pool.is_empty()) {
+            let reward_mint = self.token_mint(&reward_pool.reward_vault).await;
+            reward_accounts.push(
+                self.create_token_account(&reward_mint, &owner.pubkey())
+                    .await,
+            );
+        }
+        Staker {
+            owner,
+            tokens,
+            lp,
+            reward_accounts,
+        }
+    }
+
+    pub async fn deposit(
+        &mut self,
+        stake_pool: &Pubkey,
+        staker: &Staker,
+        nonce: u32,
+        amount: u64,
+        lockup_duration: u64,
+    ) -> std::result::Result<Pubkey, BanksClientError> {
+        let pool = self.stake_pool(stake_pool).await;
+        let token_program = self.token_program(&pool.mint).await;
+        let owner = staker.owner.pubkey();
+        self.process(
+            &[instructions::deposit(
+                stake_pool,
+                &pool,
+                &owner,
+                &owner,
+                None,
+                &staker.tokens,
+                &staker.lp,
+                &token_program,
+                DepositArgs {
+                    nonce,
+                    amount,
+                    lockup_duration,
+                    allowlist_proof: None,
+                },
+            )],
+            &[&staker.owner],
+        )
+        .await?;
+        Ok(find_stake_deposit_receipt_address(&owner, stake_pool, nonce).0)
+    }
+
+    pub async fn claim_all(
+        &mut self,
+        stake_pool: &Pubkey,
+        staker: &Staker,
+        receipt: &Pubkey,
+    ) -> std::result::Result<(), BanksClientError> {
+        let pool = self.stake_pool(stake_pool).await;
+        let token_program = self.token_program(&pool.mint).await;
+        let reward_mints = self.reward_mints(stake_pool).await;
+        self.process(
+            &[instructions::claim_all(
+                stake_pool,
+                &pool,
+                &staker.owner.pubkey(),
+                receipt,
+                &token_program,
+                &reward_mints,
+                &staker.reward_accounts,
+            )
+            .unwrap()],
+            &[&staker.owner],
+        )
+        .await
+    }
+
+    pub async fn withdraw(
+        &mut self,
+        stake_pool: &Pubkey,
+        staker: &Staker,
+        receipt: &Pubkey,
+    ) -> std::result::Result<(), BanksClientError> {
+        let pool = self.stake_pool(stake_pool).await;
+        let token_program = self.token_program(&pool.mint).await;
+        let reward_mints = self.reward_mints(stake_pool).await;
+        // a receipt that is already closed refunds nobody, the owner stands in
+        let rent_recipient = match self.account_data(receipt).await {
+            Some(data) => {
+                pool.rent_recipient(&StakeDepositReceipt::try_deserialize(&mut &data[..]).unwrap())
+            }
+            None => staker.owner.pubkey(),
+        };
+        self.process(
+            &[instructions::withdraw(
+                stake_pool,
+                &pool,
+                &staker.owner.pubkey(),
+                receipt,
+                &staker.lp,
+                None,
+                &staker.tokens,
+                &rent_recipient,
+                &token_program,
+                &reward_mints,
+                &staker.reward_accounts,
+            )
+            .unwrap()],
+            &[&staker.owner],
+        )
+        .await
+    }
+}
+
+/// Owner of a deposit along with its token accounts
+pub struct Staker {
+    pub owner: Keypair,
+    /// Staked mint account, debited on deposit and credited on withdraw
+    pub tokens: Pubkey,
+    /// LP token account
+    pub lp: Pubkey,
+    /// One account per active reward pool, in pool order
+    pub reward_accounts: Vec<Pubkey>,
+}
+
+/// Asserts that the transaction failed on `error` raised by the program
+pub fn assert_program_error(result: std::result::Result<(), BanksClientError>, error: ErrorCode) {
+    assert_custom_error(result, u32::from(error));
+}
+
+pub fn assert_custom_error(result: std::result::Result<(), BanksClientError>, code: u32) {
+    match result {
+        Err(BanksClientError::TransactionError(TransactionError::InstructionError(
+            _,
+            InstructionError::Custom(actual),
+        ))) => assert_eq!(actual, code),
+        other => panic!("expected custom error {code}, got {other:?}"),
+    }
+}
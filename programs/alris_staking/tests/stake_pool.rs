@@ -0,0 +1,512 @@
+mod common;
+
+use alris_staking::{
+    errors::ErrorCode,
+    state::{
+        LegacyRewardPool, LegacyStakePool, StakePool, DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD,
+        MAX_UNBACKED_LP_REASON_LEN, SCALE_FACTOR_BASE, UNDISTRIBUTED_SWEEP,
+    },
+};
+use alris_staking_client::{
+    instructions::{self, InitializeStakePoolArgs},
+    pda::find_stake_mint_metadata_address,
+};
+use anchor_lang::{prelude::*, Discriminator};
+use anchor_spl::{metadata::mpl_token_metadata::types::DataV2, token::spl_token};
+use bytemuck::Zeroable;
+use common::*;
+use solana_sdk::{
+    account::Account,
+    signature::{Keypair, Signer},
+};
+
+#[tokio::test]
+async fn initialize_stake_pool() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    assert_eq!(pool.authority, test.authority.pubkey());
+    assert_eq!(pool.creator, test.authority.pubkey());
+    assert_eq!(pool.mint, mint);
+    assert_eq!(pool.base_weight, SCALE_FACTOR_BASE);
+    assert_eq!(pool.max_weight, MAX_WEIGHT);
+    assert_eq!(pool.min_duration, MIN_DURATION);
+    assert_eq!(pool.max_duration, MAX_DURATION);
+    assert_eq!(pool.total_weighted_stake_u128(), 0);
+    // a 4x max weight needs one digit of headroom in the LP supply
+    assert_eq!(test.mint_decimals(&pool.stake_mint).await, 8);
+    assert_eq!(test.token_balance(&pool.vault).await, 0);
+}
+
+#[tokio::test]
+async fn initialize_stake_pool_rejects_invalid_config() {
+    let mut test = TestContext::new().await;
+    let mint = test.create_mint(9).await;
+    let authority = test.authority.insecure_clone();
+    let payer = test.payer();
+
+    let result = test
+        .process(
+            &[instructions::initialize_stake_pool(
+                &payer,
+                &authority.pubkey(),
+                &mint,
+                &spl_token::ID,
+                InitializeStakePoolArgs {
+                    nonce: 0,
+                    max_weight: MAX_WEIGHT,
+                    min_duration: MAX_DURATION + 1,
+                    max_duration: MAX_DURATION,
+                    options: 0,
+                },
+            )],
+            &[&authority],
+        )
+        .await;
+    assert_program_error(result, ErrorCode::InvalidStakePoolDuration);
+
+    let result = test
+        .process(
+            &[instructions::initialize_stake_pool(
+                &payer,
+                &authority.pubkey(),
+                &mint,
+                &spl_token::ID,
+                InitializeStakePoolArgs {
+                    nonce: 1,
+                    max_weight: SCALE_FACTOR_BASE - 1,
+                    min_duration: MIN_DURATION,
+                    max_duration: MAX_DURATION,
+                    options: 0,
+                },
+            )],
+            &[&authority],
+        )
+        .await;
+    assert_program_error(result, ErrorCode::InvalidStakePoolWeight);
+}
+
+#[tokio::test]
+async fn set_flags_requires_authority() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, _) = test.create_stake_pool().await;
+
+    let impostor = Keypair::new();
+    let result = test
+        .process(
+            &[instructions::set_flags(&stake_pool, &impostor.pubkey(), 1)],
+            &[&impostor],
+        )
+        .await;
+    assert_program_error(result, ErrorCode::InvalidAuthority);
+
+    test.set_flags(&stake_pool, 0b0001_1101).await.unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    assert!(pool.escape_hatch_enabled());
+    assert!(pool.deposits_disabled());
+    assert!(pool.deposits_ignores_lp());
+    assert!(pool.withdraw_ignores_lp());
+}
+
+#[tokio::test]
+async fn transfer_authority() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, _) = test.create_stake_pool().await;
+    let authority = test.authority.insecure_clone();
+    let new_authority = Keypair::new();
+
+    test.process(
+        &[instructions::transfer_authority(
+            &stake_pool,
+            &authority.pubkey(),
+            &new_authority.pubkey(),
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.stake_pool(&stake_pool).await.authority,
+        new_authority.pubkey()
+    );
+
+    // the previous authority lost its rights
+    assert_program_error(
+        test.set_flags(&stake_pool, 1).await,
+        ErrorCode::InvalidAuthority,
+    );
+}
+
+#[tokio::test]
+async fn undistributed_recipient_is_set_by_authority() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let reward_mint = test.add_reward_pool(&stake_pool, 0).await;
+    let staker = test.new_staker(&mint, &stake_pool, 1_000).await;
+    test.deposit(&stake_pool, &staker, 0, 1_000, MAX_DURATION)
+        .await
+        .unwrap();
+    let authority = test.authority.insecure_clone();
+    let recipient = test
+        .create_token_account(&reward_mint, &authority.pubkey())
+        .await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let result = test
+        .process(
+            &[instructions::set_reward_pool_policy(
+                &stake_pool,
+                &pool,
+                None,
+                0,
+                UNDISTRIBUTED_SWEEP,
+                0,
+            )],
+            &[&authority],
+        )
+        .await;
+    assert_program_error(result, ErrorCode::InvalidUndistributedRecipient);
+
+    test.process(
+        &[instructions::set_reward_pool_policy(
+            &stake_pool,
+            &pool,
+            Some(&recipient),
+            0,
+            UNDISTRIBUTED_SWEEP,
+            0,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    // funding from another account leaves the recipient alone
+    test.fund_reward_pool(&stake_pool, 0, 1_000).await;
+    let reward_pool = test.stake_pool(&stake_pool).await.reward_pools[0];
+    assert_eq!(reward_pool.undistributed_policy, UNDISTRIBUTED_SWEEP);
+    assert_eq!(reward_pool.undistributed_recipient, recipient);
+}
+
+/// `pool` as a StakePool created before the reward pool fields were added would hold it
+fn legacy_stake_pool(pool: &StakePool) -> LegacyStakePool {
+    let mut legacy = LegacyStakePool::zeroed();
+    legacy.creator = pool.creator;
+    legacy.authority = pool.authority;
+    legacy.total_weighted_stake = pool.total_weighted_stake;
+    legacy.vault = pool.vault;
+    legacy.mint = pool.mint;
+    legacy.stake_mint = pool.stake_mint;
+    for (legacy, reward_pool) in legacy.reward_pools.iter_mut().zip(&pool.reward_pools) {
+        *legacy = LegacyRewardPool {
+            reward_vault: reward_pool.reward_vault,
+            rewards_per_effective_stake: reward_pool.rewards_per_effective_stake,
+            last_amount: reward_pool.last_amount,
+            total_funded: reward_pool.total_funded,
+        };
+    }
+    legacy.base_weight = pool.base_weight;
+    legacy.max_weight = pool.max_weight;
+    legacy.min_duration = pool.min_duration;
+    legacy.max_duration = pool.max_duration;
+    legacy.nonce = pool.nonce;
+    legacy.bump_seed = pool.bump_seed;
+    legacy.flags = pool.flags;
+    legacy
+}
+
+#[tokio::test]
+async fn migrate_legacy_stake_pool() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    test.add_reward_pool(&stake_pool, 0).await;
+    let staker = test.new_staker(&mint, &stake_pool, 1_000).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, 1_000, MIN_DURATION)
+        .await
+        .unwrap();
+    test.fund_reward_pool(&stake_pool, 0, 500).await;
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    test.set_flags(&stake_pool, 1).await.unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+
+    let mut data = StakePool::DISCRIMINATOR.to_vec();
+    data.extend_from_slice(bytemuck::bytes_of(&legacy_stake_pool(&pool)));
+    // lamports are left as they are, changing them would unbalance the bank
+    let lamports = test.lamports(&stake_pool).await;
+    test.context.set_account(
+        &stake_pool,
+        &Account {
+            lamports,
+            data,
+            owner: alris_staking::ID,
+            executable: false,
+            rent_epoch: 0,
+        }
+        .into(),
+    );
+
+    let authority = test.authority.insecure_clone();
+    let payer = test.payer();
+    assert_program_error(
+        test.process(
+            &[instructions::migrate_stake_pool(
+                &stake_pool,
+                &payer,
+                &staker.owner.pubkey(),
+            )],
+            &[&staker.owner],
+        )
+        .await,
+        ErrorCode::InvalidAuthority,
+    );
+    let migrate = instructions::migrate_stake_pool(&stake_pool, &payer, &authority.pubkey());
+    test.process(std::slice::from_ref(&migrate), &[&authority])
+        .await
+        .unwrap();
+
+    let migrated = test.stake_pool(&stake_pool).await;
+    assert_eq!(
+        test.account_data(&stake_pool).await.unwrap().len(),
+        8 + StakePool::LEN
+    );
+    assert_eq!(migrated.authority, pool.authority);
+    assert_eq!(migrated.vault, pool.vault);
+    assert_eq!(migrated.stake_mint, pool.stake_mint);
+    assert_eq!(
+        migrated.total_weighted_stake_u128(),
+        pool.total_weighted_stake_u128()
+    );
+    assert_eq!(migrated.max_weight, pool.max_weight);
+    assert_eq!(migrated.bump_seed, pool.bump_seed);
+    assert_eq!(migrated.flags, pool.flags);
+    assert_eq!(migrated.total_deposit_amount, 1_000);
+    let (reward_pool, migrated_reward_pool) = (&pool.reward_pools[0], &migrated.reward_pools[0]);
+    assert_eq!(migrated_reward_pool.reward_vault, reward_pool.reward_vault);
+    assert_eq!(
+        migrated_reward_pool.rewards_per_effective_stake_u128(),
+        reward_pool.rewards_per_effective_stake_u128()
+    );
+    assert_eq!(migrated_reward_pool.last_amount, reward_pool.last_amount);
+    assert_eq!(migrated_reward_pool.total_funded, 500);
+    assert_eq!(
+        migrated_reward_pool.release_period,
+        DEFAULT_UNDISTRIBUTED_RELEASE_PERIOD
+    );
+    assert!(migrated.reward_pools[1].is_empty());
+
+    assert_program_error(
+        test.process(&[migrate], &[&authority]).await,
+        ErrorCode::StakePoolAlreadyMigrated,
+    );
+    // the migrated pool carries on where the legacy one left off, its deposits count
+    // towards the total cap
+    test.process(
+        &[instructions::set_deposit_caps(
+            &stake_pool,
+            &authority.pubkey(),
+            1_500,
+            0,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let newcomer = test.new_staker(&mint, &stake_pool, 1_000).await;
+    assert_program_error(
+        test.deposit(&stake_pool, &newcomer, 0, 1_000, MIN_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::TotalDepositCapExceeded,
+    );
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    test.deposit(&stake_pool, &newcomer, 0, 1_000, MIN_DURATION)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, 1_000);
+    assert_eq!(
+        test.stake_pool(&stake_pool).await.total_deposit_amount,
+        1_000
+    );
+}
+
+#[tokio::test]
+async fn dangerously_mint_lp_within_cap() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, _) = test.create_stake_pool().await;
+    let pool = test.stake_pool(&stake_pool).await;
+    let payer = test.payer();
+    let authority = test.authority.insecure_clone();
+    let destination = test
+        .create_token_account(&pool.stake_mint, &authority.pubkey())
+        .await;
+    let mint_lp = |amount, reason: &str| {
+        instructions::dangerously_mint_lp(
+            &stake_pool,
+            &pool,
+            &payer,
+            &destination,
+            &spl_token::ID,
+            amount,
+            reason.to_string(),
+        )
+    };
+
+    // nothing may be minted until the authority sets a cap
+    assert_program_error(
+        test.process(&[mint_lp(42, "airdrop")], &[&authority]).await,
+        ErrorCode::UnbackedLpCapExceeded,
+    );
+    let cap_authority = Keypair::new();
+    test.process(
+        &[
+            instructions::set_unbacked_lp_cap_authority(
+                &stake_pool,
+                &authority.pubkey(),
+                &cap_authority.pubkey(),
+            ),
+            instructions::set_unbacked_lp_cap(&stake_pool, &cap_authority.pubkey(), 100),
+        ],
+        &[&authority, &cap_authority],
+    )
+    .await
+    .unwrap();
+    for reason in [String::new(), "x".repeat(MAX_UNBACKED_LP_REASON_LEN + 1)] {
+        assert_program_error(
+            test.process(&[mint_lp(42, &reason)], &[&authority]).await,
+            ErrorCode::InvalidUnbackedLpReason,
+        );
+    }
+    test.process(&[mint_lp(42, "airdrop")], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&destination).await, 42);
+    assert_eq!(test.stake_pool(&stake_pool).await.minted_unbacked, 42);
+    assert_program_error(
+        test.process(&[mint_lp(59, "airdrop")], &[&authority]).await,
+        ErrorCode::UnbackedLpCapExceeded,
+    );
+
+    let impostor = Keypair::new();
+    let mut ix = mint_lp(1, "airdrop");
+    ix.accounts[1].pubkey = impostor.pubkey();
+    assert_program_error(
+        test.process(&[ix], &[&impostor]).await,
+        ErrorCode::InvalidAuthority,
+    );
+
+    // burning reconciles the LP supply with what the receipts minted
+    let burn_lp = |amount| {
+        instructions::burn_unbacked_lp(
+            &stake_pool,
+            &pool,
+            &destination,
+            &spl_token::ID,
+            amount,
+            "reconcile".to_string(),
+        )
+    };
+    assert_program_error(
+        test.process(&[burn_lp(43)], &[&authority]).await,
+        ErrorCode::UnbackedLpBurnExceedsMinted,
+    );
+    test.process(&[burn_lp(42)], &[&authority]).await.unwrap();
+    assert_eq!(test.token_balance(&destination).await, 0);
+    assert_eq!(test.stake_pool(&stake_pool).await.minted_unbacked, 0);
+}
+
+#[tokio::test]
+async fn unbacked_lp_cap_is_raised_only_by_its_authority() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, _) = test.create_stake_pool().await;
+    let authority = test.authority.insecure_clone();
+    let cap_authority = Keypair::new();
+    let set_cap = |signer: &Keypair, cap| {
+        instructions::set_unbacked_lp_cap(&stake_pool, &signer.pubkey(), cap)
+    };
+
+    // the pool authority may not raise the cap, even before a cap authority is named
+    assert_program_error(
+        test.process(&[set_cap(&authority, 1)], &[&authority]).await,
+        ErrorCode::InvalidUnbackedLpCapAuthority,
+    );
+    assert_program_error(
+        test.process(&[set_cap(&cap_authority, 1)], &[&cap_authority])
+            .await,
+        ErrorCode::InvalidAuthority,
+    );
+
+    test.process(
+        &[instructions::set_unbacked_lp_cap_authority(
+            &stake_pool,
+            &authority.pubkey(),
+            &cap_authority.pubkey(),
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.stake_pool(&stake_pool).await.unbacked_lp_cap_authority,
+        cap_authority.pubkey()
+    );
+    // once named, only the cap authority may hand it on
+    assert_program_error(
+        test.process(
+            &[instructions::set_unbacked_lp_cap_authority(
+                &stake_pool,
+                &authority.pubkey(),
+                &authority.pubkey(),
+            )],
+            &[&authority],
+        )
+        .await,
+        ErrorCode::InvalidUnbackedLpCapAuthority,
+    );
+
+    test.process(&[set_cap(&cap_authority, 100)], &[&cap_authority])
+        .await
+        .unwrap();
+    test.process(&[set_cap(&authority, 60)], &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(test.stake_pool(&stake_pool).await.unbacked_lp_cap, 60);
+    assert_program_error(
+        test.process(&[set_cap(&authority, 61)], &[&authority])
+            .await,
+        ErrorCode::InvalidUnbackedLpCapAuthority,
+    );
+}
+
+#[tokio::test]
+async fn update_token_meta_creates_then_updates() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, _) = test.create_stake_pool().await;
+    let pool = test.stake_pool(&stake_pool).await;
+    let metadata = find_stake_mint_metadata_address(&pool.stake_mint).0;
+    let authority = test.authority.insecure_clone();
+
+    for (name, symbol) in [("Staked Alris", "sALR"), ("Staked Alris v2", "sALR2")] {
+        test.process(
+            &[instructions::update_token_meta(
+                &stake_pool,
+                &pool,
+                name.to_string(),
+                symbol.to_string(),
+                "https://alris.io/sALR.json".to_string(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+        let data = test.account_data(&metadata).await.unwrap();
+        let meta = DataV2::deserialize(&mut &data[..]).unwrap();
+        assert_eq!(meta.name, name);
+        assert_eq!(meta.symbol, symbol);
+    }
+}
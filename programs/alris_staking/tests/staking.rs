@@ -0,0 +1,1410 @@
+mod common;
+
+use alris_staking::{
+    errors::ErrorCode,
+    events::{Deposited, RewardsClaimed, Withdrawn},
+    state::{
+        DEPOSIT_IGNORES_LP, DISABLE_DEPOSITS, ESCAPE_HATCH_ENABLED, REFUND_RENT_TO_OWNER,
+        SCALE_FACTOR_BASE, UNDISTRIBUTED_HOLD, UNDISTRIBUTED_SWEEP, WITHDRAW_IGNORES_LP,
+    },
+};
+use alris_staking_client::{
+    accounts::deserialize_deposit_allowance,
+    allowlist::Allowlist,
+    instructions::{self, DepositArgs},
+    pda::{find_deposit_allowance_address, find_stake_deposit_receipt_address},
+};
+use anchor_spl::token::spl_token;
+use common::*;
+use solana_sdk::{
+    pubkey::Pubkey,
+    signature::{Keypair, Signer},
+};
+
+const STAKE: u64 = 1_000_000_000;
+
+#[tokio::test]
+async fn deposit_then_withdraw_after_lockup() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MAX_DURATION)
+        .await
+        .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    let deposit = test.stake_deposit_receipt(&receipt).await;
+    assert_eq!(deposit.deposit_amount, STAKE);
+    assert_eq!(deposit.deposit_timestamp, START_TIMESTAMP);
+    assert_eq!(deposit.lockup_duration, MAX_DURATION);
+    let effective_stake = u128::from(STAKE) * u128::from(MAX_WEIGHT);
+    assert_eq!(deposit.effective_stake_u128(), effective_stake);
+    assert_eq!(pool.total_weighted_stake_u128(), effective_stake);
+    assert_eq!(test.token_balance(&pool.vault).await, STAKE);
+    assert_eq!(test.token_balance(&staker.tokens).await, 0);
+    // 4 tokens of stake with one digit shifted off
+    assert_eq!(test.token_balance(&staker.lp).await, 4 * STAKE / 10);
+
+    test.advance_clock(MAX_DURATION as i64 - 1);
+    assert_program_error(
+        test.withdraw(&stake_pool, &staker, &receipt).await,
+        ErrorCode::StakeStillLocked,
+    );
+
+    test.advance_clock(1);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+    assert_eq!(test.token_balance(&staker.lp).await, 0);
+    assert_eq!(test.token_balance(&pool.vault).await, 0);
+    assert_eq!(
+        test.stake_pool(&stake_pool)
+            .await
+            .total_weighted_stake_u128(),
+        0
+    );
+    assert!(test.account_data(&receipt).await.is_none());
+}
+
+#[tokio::test]
+async fn deposit_claim_and_withdraw_emit_events() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    test.add_reward_pool(&stake_pool, 0).await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let owner = staker.owner.pubkey();
+
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let deposited = test.events::<Deposited>();
+    assert_eq!(deposited.len(), 1);
+    assert_eq!(deposited[0].stake_pool, stake_pool);
+    assert_eq!(deposited[0].owner, owner);
+    assert_eq!(deposited[0].stake_deposit_receipt, receipt);
+    assert_eq!(deposited[0].amount, STAKE);
+
+    test.fund_reward_pool(&stake_pool, 0, 1_000).await;
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    let claimed = test.events::<RewardsClaimed>();
+    assert_eq!(claimed.len(), 1);
+    assert_eq!(claimed[0].stake_pool, stake_pool);
+    assert_eq!(claimed[0].owner, owner);
+    assert_eq!(claimed[0].stake_deposit_receipt, receipt);
+    assert_eq!(claimed[0].reward_pool_index, 0);
+    assert_eq!(claimed[0].reward_vault, pool.reward_pools[0].reward_vault);
+    assert_eq!(claimed[0].amount, 1_000);
+
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    let withdrawn = test.events::<Withdrawn>();
+    assert_eq!(withdrawn.len(), 1);
+    assert_eq!(withdrawn[0].stake_pool, stake_pool);
+    assert_eq!(withdrawn[0].owner, owner);
+    assert_eq!(withdrawn[0].stake_deposit_receipt, receipt);
+    assert_eq!(withdrawn[0].amount, STAKE);
+}
+
+#[tokio::test]
+async fn user_stake_index_tracks_open_receipts() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, 3 * STAKE).await;
+    let owner = staker.owner.pubkey();
+    assert!(test.user_stake_index(&owner, &stake_pool).await.is_none());
+
+    // the index grows past its initial single receipt
+    let mut receipts = vec![];
+    for (nonce, lockup_duration) in [(0, MIN_DURATION), (5, MAX_DURATION), (2, MIN_DURATION)] {
+        receipts.push(
+            test.deposit(&stake_pool, &staker, nonce, STAKE, lockup_duration)
+                .await
+                .unwrap(),
+        );
+    }
+    let index = test.user_stake_index(&owner, &stake_pool).await.unwrap();
+    assert_eq!(index.owner, owner);
+    assert_eq!(index.stake_pool, stake_pool);
+    assert_eq!(index.next_nonce, 6);
+    assert_eq!(index.receipts, receipts);
+    assert_eq!(index.total_deposit_amount, 3 * STAKE);
+    assert_eq!(
+        index.total_effective_stake_u128(),
+        u128::from(STAKE) * u128::from(2 * SCALE_FACTOR_BASE + MAX_WEIGHT)
+    );
+
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipts[0])
+        .await
+        .unwrap();
+    let index = test.user_stake_index(&owner, &stake_pool).await.unwrap();
+    assert_eq!(index.next_nonce, 6);
+    assert_eq!(index.receipts, vec![receipts[2], receipts[1]]);
+    assert_eq!(index.total_deposit_amount, 2 * STAKE);
+    assert_eq!(
+        index.total_effective_stake_u128(),
+        u128::from(STAKE) * u128::from(SCALE_FACTOR_BASE + MAX_WEIGHT)
+    );
+}
+
+#[tokio::test]
+async fn user_stake_index_lists_owner_authorized_deposits_only() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let owner = staker.owner.pubkey();
+    let griefer = test.new_staker(&mint, &stake_pool, 10).await;
+    let pool = test.stake_pool(&stake_pool).await;
+
+    // a third party staking its own tokens for the owner opens a receipt the index skips
+    test.process(
+        &[instructions::deposit(
+            &stake_pool,
+            &pool,
+            &griefer.owner.pubkey(),
+            &owner,
+            None,
+            &griefer.tokens,
+            &staker.lp,
+            &spl_token::ID,
+            DepositArgs {
+                nonce: 0,
+                amount: 10,
+                lockup_duration: MIN_DURATION,
+                allowlist_proof: None,
+            },
+        )],
+        &[&griefer.owner],
+    )
+    .await
+    .unwrap();
+    let unlisted = find_stake_deposit_receipt_address(&owner, &stake_pool, 0).0;
+    assert_eq!(test.stake_deposit_receipt(&unlisted).await.owner, owner);
+    assert!(test.user_stake_index(&owner, &stake_pool).await.is_none());
+
+    let listed = test
+        .deposit(&stake_pool, &staker, 1, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let index = test.user_stake_index(&owner, &stake_pool).await.unwrap();
+    assert_eq!(index.receipts, vec![listed]);
+    assert_eq!(index.total_deposit_amount, STAKE);
+
+    // the owner withdraws either receipt, only the listed one leaves the index
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &unlisted)
+        .await
+        .unwrap();
+    let index = test.user_stake_index(&owner, &stake_pool).await.unwrap();
+    assert_eq!(index.receipts, vec![listed]);
+    test.withdraw(&stake_pool, &staker, &listed).await.unwrap();
+    let index = test.user_stake_index(&owner, &stake_pool).await.unwrap();
+    assert!(index.receipts.is_empty());
+    assert_eq!(index.total_deposit_amount, 0);
+}
+
+#[tokio::test]
+async fn deposit_rejects_short_lockup() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+
+    let result = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION - 1)
+        .await
+        .map(|_| ());
+    assert_program_error(result, ErrorCode::DurationTooShort);
+}
+
+#[tokio::test]
+async fn claims_across_reward_pools() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    test.add_reward_pool(&stake_pool, 0).await;
+    test.add_reward_pool(&stake_pool, 2).await;
+    let short = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let long = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let short_receipt = test
+        .deposit(&stake_pool, &short, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let long_receipt = test
+        .deposit(&stake_pool, &long, 0, STAKE, MAX_DURATION)
+        .await
+        .unwrap();
+
+    // weights of 1x and 4x split rewards 1:4
+    test.fund_reward_pool(&stake_pool, 0, 5_000).await;
+    test.fund_reward_pool(&stake_pool, 2, 1_000).await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let claimable = instructions::decode_claimable(
+        &test
+            .simulate_return_data(&[instructions::get_claimable(
+                &stake_pool,
+                &pool,
+                &short_receipt,
+            )])
+            .await,
+    )
+    .unwrap();
+    assert_eq!((claimable[0], claimable[2]), (1_000, 200));
+
+    test.claim_all(&stake_pool, &short, &short_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&short.reward_accounts[0]).await, 1_000);
+    assert_eq!(test.token_balance(&short.reward_accounts[1]).await, 200);
+
+    // claiming again pays nothing more
+    test.claim_all(&stake_pool, &short, &short_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&short.reward_accounts[0]).await, 1_000);
+
+    test.fund_reward_pool(&stake_pool, 2, 500).await;
+    test.advance_clock(MAX_DURATION as i64);
+    // withdraw pays out the pending rewards along with the principal
+    test.withdraw(&stake_pool, &long, &long_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&long.reward_accounts[0]).await, 4_000);
+    assert_eq!(test.token_balance(&long.reward_accounts[1]).await, 1_200);
+    assert_eq!(test.token_balance(&long.tokens).await, STAKE);
+
+    test.claim_all(&stake_pool, &short, &short_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&short.reward_accounts[1]).await, 300);
+    let pool = test.stake_pool(&stake_pool).await;
+    assert_eq!(
+        test.token_balance(&pool.reward_pools[0].reward_vault).await,
+        0
+    );
+    assert_eq!(
+        test.token_balance(&pool.reward_pools[2].reward_vault).await,
+        0
+    );
+}
+
+#[tokio::test]
+async fn sweep_undistributed_takes_rewards_sent_while_nothing_staked() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let reward_mint = test.add_reward_pool(&stake_pool, 0).await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let authority = test.authority.insecure_clone();
+    let recipient = test
+        .create_token_account(&reward_mint, &authority.pubkey())
+        .await;
+    let reward_mints = test.reward_mints(&stake_pool).await;
+
+    for policy in [UNDISTRIBUTED_SWEEP, UNDISTRIBUTED_HOLD] {
+        let pool = test.stake_pool(&stake_pool).await;
+        test.process(
+            &[instructions::set_reward_pool_policy(
+                &stake_pool,
+                &pool,
+                Some(&recipient),
+                0,
+                policy,
+                0,
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+        let pool = test.stake_pool(&stake_pool).await;
+        let result = test
+            .process(
+                &[instructions::sweep_undistributed(
+                    &stake_pool,
+                    &pool,
+                    &reward_mints[0],
+                    0,
+                )],
+                &[&authority],
+            )
+            .await;
+        match policy {
+            UNDISTRIBUTED_SWEEP => assert_program_error(result, ErrorCode::InvalidAmount),
+            _ => assert_program_error(result, ErrorCode::InvalidUndistributedPolicy),
+        }
+    }
+
+    // 1_000 is sent straight to the vault while nothing is staked, 500 is funded after the
+    // deposit
+    let pool = test.stake_pool(&stake_pool).await;
+    test.process(
+        &[instructions::set_reward_pool_policy(
+            &stake_pool,
+            &pool,
+            Some(&recipient),
+            0,
+            UNDISTRIBUTED_SWEEP,
+            0,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    test.mint_to(&reward_mint, &pool.reward_pools[0].reward_vault, 1_000)
+        .await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    test.fund_reward_pool(&stake_pool, 0, 500).await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let sweep = instructions::sweep_undistributed(&stake_pool, &pool, &reward_mints[0], 0);
+    test.process(std::slice::from_ref(&sweep), &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 1_000);
+    let pool = test.stake_pool(&stake_pool).await;
+    assert_eq!(pool.reward_pools[0].undistributed_amount, 0);
+    assert_eq!(pool.reward_pools[0].last_amount, 500);
+
+    // the staker is still paid everything that arrived while staked
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 500);
+    assert_eq!(
+        test.token_balance(&pool.reward_pools[0].reward_vault).await,
+        0
+    );
+    assert_program_error(
+        test.process(&[sweep], &[&authority]).await,
+        ErrorCode::InvalidAmount,
+    );
+}
+
+#[tokio::test]
+async fn switching_to_sweep_keeps_released_rewards_with_stakers() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let reward_mint = test.add_reward_pool(&stake_pool, 0).await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let authority = test.authority.insecure_clone();
+    let recipient = test
+        .create_token_account(&reward_mint, &authority.pubkey())
+        .await;
+    let reward_mints = test.reward_mints(&stake_pool).await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    test.process(
+        &[instructions::set_reward_pool_policy(
+            &stake_pool,
+            &pool,
+            None,
+            0,
+            UNDISTRIBUTED_HOLD,
+            1_000,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    test.mint_to(&reward_mint, &pool.reward_pools[0].reward_vault, 1_000)
+        .await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    // the first accrual with stakers starts releasing the held 1_000 over 1_000 seconds
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 0);
+
+    // halfway through the release the authority switches to sweeping
+    test.advance_clock(500);
+    let pool = test.stake_pool(&stake_pool).await;
+    test.process(
+        &[instructions::set_reward_pool_policy(
+            &stake_pool,
+            &pool,
+            Some(&recipient),
+            0,
+            UNDISTRIBUTED_SWEEP,
+            1_000,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    assert_eq!(pool.reward_pools[0].undistributed_amount, 500);
+    test.process(
+        &[instructions::sweep_undistributed(
+            &stake_pool,
+            &pool,
+            &reward_mints[0],
+            0,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&recipient).await, 500);
+
+    // the unlocked half stays with the staker
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 500);
+}
+
+#[tokio::test]
+async fn sweep_reward_dust_takes_whole_tokens_floored_from_claims() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let reward_mint = test.add_reward_pool(&stake_pool, 0).await;
+    let first = test.new_staker(&mint, &stake_pool, 1_000).await;
+    let second = test.new_staker(&mint, &stake_pool, 1_000).await;
+    let first_receipt = test
+        .deposit(&stake_pool, &first, 0, 1_000, MIN_DURATION)
+        .await
+        .unwrap();
+    let second_receipt = test
+        .deposit(&stake_pool, &second, 0, 1_000, MIN_DURATION)
+        .await
+        .unwrap();
+    let authority = test.authority.insecure_clone();
+    let destination = test
+        .create_token_account(&reward_mint, &authority.pubkey())
+        .await;
+    let reward_mints = test.reward_mints(&stake_pool).await;
+    let pool = test.stake_pool(&stake_pool).await;
+    let reward_vault = pool.reward_pools[0].reward_vault;
+    let sweep =
+        instructions::sweep_reward_dust(&stake_pool, &pool, &destination, &reward_mints[0], 0);
+
+    // 3 tokens split evenly is 1.5 each, nothing has been floored away yet
+    test.fund_reward_pool(&stake_pool, 0, 3).await;
+    assert_program_error(
+        test.process(std::slice::from_ref(&sweep), &[&authority])
+            .await,
+        ErrorCode::InvalidAmount,
+    );
+
+    // each claim floors away half a token
+    test.claim_all(&stake_pool, &first, &first_receipt)
+        .await
+        .unwrap();
+    test.claim_all(&stake_pool, &second, &second_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&first.reward_accounts[0]).await, 1);
+    assert_eq!(test.token_balance(&second.reward_accounts[0]).await, 1);
+    assert_eq!(
+        test.stake_pool(&stake_pool).await.reward_pools[0]
+            .get_sweepable_dust()
+            .unwrap(),
+        1
+    );
+
+    test.process(std::slice::from_ref(&sweep), &[&authority])
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&destination).await, 1);
+    assert_eq!(test.token_balance(&reward_vault).await, 0);
+    let reward_pool = test.stake_pool(&stake_pool).await.reward_pools[0];
+    assert_eq!(reward_pool.last_amount, 0);
+    assert_eq!(reward_pool.unclaimable_dust.as_u128(), 0);
+
+    // later rewards are paid out in full
+    test.fund_reward_pool(&stake_pool, 0, 4).await;
+    test.claim_all(&stake_pool, &first, &first_receipt)
+        .await
+        .unwrap();
+    test.claim_all(&stake_pool, &second, &second_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&first.reward_accounts[0]).await, 3);
+    assert_eq!(test.token_balance(&second.reward_accounts[0]).await, 3);
+    assert_eq!(test.token_balance(&reward_vault).await, 0);
+    assert_program_error(
+        test.process(&[sweep], &[&authority]).await,
+        ErrorCode::InvalidAmount,
+    );
+}
+
+#[tokio::test]
+async fn claim_many_pays_each_reward_pool_once() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    test.add_reward_pool(&stake_pool, 0).await;
+    test.add_reward_pool(&stake_pool, 2).await;
+    let staker = test.new_staker(&mint, &stake_pool, 2 * STAKE).await;
+    let other = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let short_receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let long_receipt = test
+        .deposit(&stake_pool, &staker, 1, STAKE, MAX_DURATION)
+        .await
+        .unwrap();
+    let other_receipt = test
+        .deposit(&stake_pool, &other, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+
+    // weights of 1x, 4x and 1x split rewards 1:4:1
+    test.fund_reward_pool(&stake_pool, 0, 6_000).await;
+    test.fund_reward_pool(&stake_pool, 2, 1_200).await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let reward_mints = test.reward_mints(&stake_pool).await;
+    let claim_many = |receipts: &[Pubkey]| {
+        instructions::claim_many(
+            &stake_pool,
+            &pool,
+            &staker.owner.pubkey(),
+            receipts,
+            &reward_mints,
+            &staker.reward_accounts,
+        )
+        .unwrap()
+    };
+    assert_program_error(
+        test.process(
+            &[claim_many(&[short_receipt, other_receipt])],
+            &[&staker.owner],
+        )
+        .await,
+        ErrorCode::InvalidOwner,
+    );
+    assert_program_error(
+        test.process(&[claim_many(&[])], &[&staker.owner]).await,
+        ErrorCode::MissingStakeDepositReceipts,
+    );
+
+    // a receipt passed twice is only paid once
+    test.process(
+        &[claim_many(&[short_receipt, long_receipt, short_receipt])],
+        &[&staker.owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 5_000);
+    assert_eq!(test.token_balance(&staker.reward_accounts[1]).await, 1_000);
+
+    // both receipts are settled
+    test.claim_all(&stake_pool, &staker, &short_receipt)
+        .await
+        .unwrap();
+    test.claim_all(&stake_pool, &staker, &long_receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 5_000);
+    assert_eq!(test.token_balance(&staker.reward_accounts[1]).await, 1_000);
+    let pool = test.stake_pool(&stake_pool).await;
+    assert_eq!(
+        test.token_balance(&pool.reward_pools[0].reward_vault).await,
+        1_000
+    );
+}
+
+#[tokio::test]
+async fn withdraw_many_closes_unlocked_receipts() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    test.add_reward_pool(&stake_pool, 1).await;
+    let staker = test.new_staker(&mint, &stake_pool, 3 * STAKE).await;
+    let mut receipts = vec![];
+    for (nonce, lockup_duration) in [(0, MIN_DURATION), (1, MAX_DURATION), (2, MIN_DURATION)] {
+        receipts.push(
+            test.deposit(&stake_pool, &staker, nonce, STAKE, lockup_duration)
+                .await
+                .unwrap(),
+        );
+    }
+    // weights of 1x, 4x and 1x split rewards 1:4:1
+    test.fund_reward_pool(&stake_pool, 1, 6_000).await;
+    test.advance_clock(MIN_DURATION as i64);
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let reward_mints = test.reward_mints(&stake_pool).await;
+    let withdraw_many = |receipts: &[Pubkey]| {
+        instructions::withdraw_many(
+            &stake_pool,
+            &pool,
+            &staker.owner.pubkey(),
+            receipts,
+            &staker.lp,
+            None,
+            &staker.tokens,
+            &staker.owner.pubkey(),
+            &spl_token::ID,
+            &reward_mints,
+            &staker.reward_accounts,
+        )
+        .unwrap()
+    };
+    assert_program_error(
+        test.process(&[withdraw_many(&receipts)], &[&staker.owner])
+            .await,
+        ErrorCode::StakeStillLocked,
+    );
+    assert_program_error(
+        test.process(
+            &[withdraw_many(&[receipts[0], receipts[2], receipts[0]])],
+            &[&staker.owner],
+        )
+        .await,
+        ErrorCode::DuplicateStakeDepositReceipt,
+    );
+
+    test.process(
+        &[withdraw_many(&[receipts[0], receipts[2]])],
+        &[&staker.owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, 2 * STAKE);
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 2_000);
+    // the long receipt's LP tokens are left
+    assert_eq!(test.token_balance(&staker.lp).await, 4 * STAKE / 10);
+    assert!(test.account_data(&receipts[0]).await.is_none());
+    assert!(test.account_data(&receipts[2]).await.is_none());
+    let index = test
+        .user_stake_index(&staker.owner.pubkey(), &stake_pool)
+        .await
+        .unwrap();
+    assert_eq!(index.receipts, vec![receipts[1]]);
+    assert_eq!(index.total_deposit_amount, STAKE);
+    assert_eq!(
+        test.stake_pool(&stake_pool)
+            .await
+            .total_weighted_stake_u128(),
+        u128::from(STAKE) * u128::from(MAX_WEIGHT)
+    );
+}
+
+#[tokio::test]
+async fn relayer_deposits_with_owner_authorization() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, 2 * STAKE).await;
+    let relayer = Keypair::new();
+    test.airdrop(&relayer.pubkey(), 1_000_000_000).await;
+    let relayer_tokens = test
+        .create_funded_token_account(&mint, &relayer.pubkey(), STAKE)
+        .await;
+    let pool = test.stake_pool(&stake_pool).await;
+    let owner = staker.owner.pubkey();
+    let deposit = |nonce, token_authority: &Pubkey, from: &Pubkey| {
+        instructions::deposit(
+            &stake_pool,
+            &pool,
+            &relayer.pubkey(),
+            &owner,
+            Some(token_authority),
+            from,
+            &staker.lp,
+            &spl_token::ID,
+            DepositArgs {
+                nonce,
+                amount: STAKE,
+                lockup_duration: MIN_DURATION,
+                allowlist_proof: None,
+            },
+        )
+    };
+
+    // the relayer cannot stake its own tokens on the owner's behalf this way
+    assert_program_error(
+        test.process(
+            &[deposit(0, &relayer.pubkey(), &relayer_tokens)],
+            &[&relayer],
+        )
+        .await,
+        ErrorCode::InvalidDepositSourceOwner,
+    );
+    // nor move the owner's tokens without the owner
+    assert!(test
+        .process(
+            &[deposit(0, &relayer.pubkey(), &staker.tokens)],
+            &[&relayer],
+        )
+        .await
+        .is_err());
+
+    // the owner signs for the tokens while the relayer pays
+    let owner_lamports = test.lamports(&owner).await;
+    test.process(
+        &[deposit(0, &owner, &staker.tokens)],
+        &[&relayer, &staker.owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.lamports(&owner).await, owner_lamports);
+    let receipt = test
+        .stake_deposit_receipt(&find_stake_deposit_receipt_address(&owner, &stake_pool, 0).0)
+        .await;
+    assert_eq!(receipt.owner, owner);
+    assert_eq!(receipt.payer, relayer.pubkey());
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+
+    // or approves the relayer as a delegate up front
+    test.process(
+        &[spl_token::instruction::approve(
+            &spl_token::ID,
+            &staker.tokens,
+            &relayer.pubkey(),
+            &owner,
+            &[],
+            STAKE,
+        )
+        .unwrap()],
+        &[&staker.owner],
+    )
+    .await
+    .unwrap();
+    test.process(
+        &[deposit(1, &relayer.pubkey(), &staker.tokens)],
+        &[&relayer],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, 0);
+    assert_eq!(test.token_balance(&relayer_tokens).await, STAKE);
+    assert_eq!(test.token_balance(&staker.lp).await, 2 * STAKE / 10);
+
+    // both deposits drew on the owner's tokens and are listed
+    let index = test.user_stake_index(&owner, &stake_pool).await.unwrap();
+    assert_eq!(
+        index.receipts,
+        vec![
+            find_stake_deposit_receipt_address(&owner, &stake_pool, 0).0,
+            find_stake_deposit_receipt_address(&owner, &stake_pool, 1).0,
+        ]
+    );
+    assert_eq!(index.next_nonce, 2);
+    assert_eq!(index.total_deposit_amount, 2 * STAKE);
+}
+
+#[tokio::test]
+async fn withdraw_refunds_rent_to_receipt_payer() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, 0).await;
+    let relayer = Keypair::new();
+    test.airdrop(&relayer.pubkey(), 1_000_000_000).await;
+    let relayer_tokens = test
+        .create_funded_token_account(&mint, &relayer.pubkey(), 2 * STAKE)
+        .await;
+    let pool = test.stake_pool(&stake_pool).await;
+    let owner = staker.owner.pubkey();
+    let mut receipts = vec![];
+    for nonce in 0..2 {
+        test.process(
+            &[instructions::deposit(
+                &stake_pool,
+                &pool,
+                &relayer.pubkey(),
+                &owner,
+                None,
+                &relayer_tokens,
+                &staker.lp,
+                &spl_token::ID,
+                DepositArgs {
+                    nonce,
+                    amount: STAKE,
+                    lockup_duration: MIN_DURATION,
+                    allowlist_proof: None,
+                },
+            )],
+            &[&relayer],
+        )
+        .await
+        .unwrap();
+        receipts.push(find_stake_deposit_receipt_address(&owner, &stake_pool, nonce).0);
+    }
+    test.advance_clock(MIN_DURATION as i64);
+
+    let withdraw = |receipt: &Pubkey, rent_recipient: &Pubkey| {
+        instructions::withdraw(
+            &stake_pool,
+            &pool,
+            &owner,
+            receipt,
+            &staker.lp,
+            None,
+            &staker.tokens,
+            rent_recipient,
+            &spl_token::ID,
+            &[],
+            &[],
+        )
+        .unwrap()
+    };
+    assert_program_error(
+        test.process(&[withdraw(&receipts[0], &owner)], &[&staker.owner])
+            .await,
+        ErrorCode::InvalidRentRecipient,
+    );
+    let rent = test.lamports(&receipts[0]).await;
+    let relayer_lamports = test.lamports(&relayer.pubkey()).await;
+    test.process(
+        &[withdraw(&receipts[0], &relayer.pubkey())],
+        &[&staker.owner],
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        test.lamports(&relayer.pubkey()).await,
+        relayer_lamports + rent
+    );
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+
+    // the pool may keep refunding owners
+    test.set_flags(&stake_pool, REFUND_RENT_TO_OWNER)
+        .await
+        .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    let withdraw = |rent_recipient: &Pubkey| {
+        instructions::withdraw(
+            &stake_pool,
+            &pool,
+            &owner,
+            &receipts[1],
+            &staker.lp,
+            None,
+            &staker.tokens,
+            rent_recipient,
+            &spl_token::ID,
+            &[],
+            &[],
+        )
+        .unwrap()
+    };
+    assert_program_error(
+        test.process(&[withdraw(&relayer.pubkey())], &[&staker.owner])
+            .await,
+        ErrorCode::InvalidRentRecipient,
+    );
+    let owner_lamports = test.lamports(&owner).await;
+    test.process(&[withdraw(&owner)], &[&staker.owner])
+        .await
+        .unwrap();
+    assert_eq!(test.lamports(&owner).await, owner_lamports + rent);
+}
+
+#[tokio::test]
+async fn deposit_caps() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let first = test.new_staker(&mint, &stake_pool, 3 * STAKE).await;
+    let second = test.new_staker(&mint, &stake_pool, 3 * STAKE).await;
+    let authority = test.authority.insecure_clone();
+    test.process(
+        &[instructions::set_deposit_caps(
+            &stake_pool,
+            &authority.pubkey(),
+            3 * STAKE,
+            2 * STAKE,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+
+    let receipt = test
+        .deposit(&stake_pool, &first, 0, 2 * STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    assert_program_error(
+        test.deposit(&stake_pool, &first, 1, 10, MIN_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::OwnerDepositCapExceeded,
+    );
+    assert_program_error(
+        test.deposit(&stake_pool, &second, 0, STAKE + 1, MIN_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::TotalDepositCapExceeded,
+    );
+    // the per owner cap holds however the deposit is made, nobody can deposit for the owner
+    // without its authorization
+    let pool = test.stake_pool(&stake_pool).await;
+    let deposit_for_first = |token_authority: Option<&Pubkey>, from: &Pubkey| {
+        instructions::deposit(
+            &stake_pool,
+            &pool,
+            &second.owner.pubkey(),
+            &first.owner.pubkey(),
+            token_authority,
+            from,
+            &first.lp,
+            &spl_token::ID,
+            DepositArgs {
+                nonce: 1,
+                amount: STAKE / 2,
+                lockup_duration: MIN_DURATION,
+                allowlist_proof: None,
+            },
+        )
+    };
+    assert_program_error(
+        test.process(&[deposit_for_first(None, &second.tokens)], &[&second.owner])
+            .await,
+        ErrorCode::MissingOwnerAuthorization,
+    );
+    test.process(
+        &[spl_token::instruction::approve(
+            &spl_token::ID,
+            &first.tokens,
+            &second.owner.pubkey(),
+            &first.owner.pubkey(),
+            &[],
+            STAKE / 2,
+        )
+        .unwrap()],
+        &[&first.owner],
+    )
+    .await
+    .unwrap();
+    assert_program_error(
+        test.process(
+            &[deposit_for_first(
+                Some(&second.owner.pubkey()),
+                &first.tokens,
+            )],
+            &[&second.owner],
+        )
+        .await,
+        ErrorCode::OwnerDepositCapExceeded,
+    );
+    test.deposit(&stake_pool, &second, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    assert_eq!(
+        test.stake_pool(&stake_pool).await.total_deposit_amount,
+        3 * STAKE
+    );
+
+    // withdrawing frees room under both caps
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &first, &receipt).await.unwrap();
+    assert_eq!(
+        test.stake_pool(&stake_pool).await.total_deposit_amount,
+        STAKE
+    );
+    test.deposit(&stake_pool, &first, 2, 2 * STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+
+    // only the authority adjusts the caps, zero lifts them
+    assert_program_error(
+        test.process(
+            &[instructions::set_deposit_caps(
+                &stake_pool,
+                &first.owner.pubkey(),
+                0,
+                0,
+            )],
+            &[&first.owner],
+        )
+        .await,
+        ErrorCode::InvalidAuthority,
+    );
+    test.process(
+        &[instructions::set_deposit_caps(
+            &stake_pool,
+            &authority.pubkey(),
+            0,
+            0,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    test.deposit(&stake_pool, &second, 1, 2 * STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn min_deposit_amount_and_dust() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let authority = test.authority.insecure_clone();
+
+    // MAX_WEIGHT shifts the LP amount by one digit, so a deposit mints a tenth of its
+    // weighted amount
+    assert_program_error(
+        test.deposit(&stake_pool, &staker, 0, 9, MIN_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::ZeroLpDeposit,
+    );
+    assert_program_error(
+        test.deposit(&stake_pool, &staker, 0, 2, MAX_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::ZeroLpDeposit,
+    );
+    test.deposit(&stake_pool, &staker, 0, 10, MIN_DURATION)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.lp).await, 1);
+    test.deposit(&stake_pool, &staker, 1, 3, MAX_DURATION)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.lp).await, 2);
+
+    assert_program_error(
+        test.process(
+            &[instructions::set_min_deposit_amount(
+                &stake_pool,
+                &staker.owner.pubkey(),
+                0,
+            )],
+            &[&staker.owner],
+        )
+        .await,
+        ErrorCode::InvalidAuthority,
+    );
+    test.process(
+        &[instructions::set_min_deposit_amount(
+            &stake_pool,
+            &authority.pubkey(),
+            100,
+        )],
+        &[&authority],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.stake_pool(&stake_pool).await.min_deposit_amount, 100);
+    assert_program_error(
+        test.deposit(&stake_pool, &staker, 2, 99, MAX_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::DepositTooSmall,
+    );
+    test.deposit(&stake_pool, &staker, 2, 100, MIN_DURATION)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn allowlisted_deposits() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let capped = test.new_staker(&mint, &stake_pool, 3 * STAKE).await;
+    let unlimited = test.new_staker(&mint, &stake_pool, 3 * STAKE).await;
+    let outsider = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let authority = test.authority.insecure_clone();
+    let allowlist = Allowlist::new(vec![
+        (capped.owner.pubkey(), 2 * STAKE),
+        (unlimited.owner.pubkey(), 0),
+    ]);
+    let set_root =
+        |root| instructions::set_deposit_allowlist(&stake_pool, &authority.pubkey(), root);
+    assert_program_error(
+        test.process(
+            &[instructions::set_deposit_allowlist(
+                &stake_pool,
+                &outsider.owner.pubkey(),
+                [0; 32],
+            )],
+            &[&outsider.owner],
+        )
+        .await,
+        ErrorCode::InvalidAuthority,
+    );
+    test.process(&[set_root(allowlist.root())], &[&authority])
+        .await
+        .unwrap();
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let deposit = |staker: &Staker, nonce, amount, allowlist_proof| {
+        instructions::deposit(
+            &stake_pool,
+            &pool,
+            &staker.owner.pubkey(),
+            &staker.owner.pubkey(),
+            None,
+            &staker.tokens,
+            &staker.lp,
+            &spl_token::ID,
+            DepositArgs {
+                nonce,
+                amount,
+                lockup_duration: MIN_DURATION,
+                allowlist_proof,
+            },
+        )
+    };
+    let capped_proof = allowlist.proof(&capped.owner.pubkey());
+
+    // no proof, or another owner's proof, does not get in
+    assert_program_error(
+        test.deposit(&stake_pool, &outsider, 0, STAKE, MIN_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::DepositNotAllowlisted,
+    );
+    assert_program_error(
+        test.process(
+            &[deposit(&outsider, 0, STAKE, capped_proof.clone())],
+            &[&outsider.owner],
+        )
+        .await,
+        ErrorCode::DepositNotAllowlisted,
+    );
+    assert_program_error(
+        test.deposit(&stake_pool, &capped, 0, STAKE, MIN_DURATION)
+            .await
+            .map(|_| ()),
+        ErrorCode::DepositNotAllowlisted,
+    );
+    // nor does a third party spending the owner's allowance without its signature
+    assert_program_error(
+        test.process(
+            &[instructions::deposit(
+                &stake_pool,
+                &pool,
+                &outsider.owner.pubkey(),
+                &capped.owner.pubkey(),
+                None,
+                &outsider.tokens,
+                &capped.lp,
+                &spl_token::ID,
+                DepositArgs {
+                    nonce: 0,
+                    amount: STAKE,
+                    lockup_duration: MIN_DURATION,
+                    allowlist_proof: capped_proof.clone(),
+                },
+            )],
+            &[&outsider.owner],
+        )
+        .await,
+        ErrorCode::MissingOwnerSignature,
+    );
+
+    let receipt = find_stake_deposit_receipt_address(&capped.owner.pubkey(), &stake_pool, 0).0;
+    test.process(
+        &[deposit(&capped, 0, STAKE, capped_proof.clone())],
+        &[&capped.owner],
+    )
+    .await
+    .unwrap();
+    assert_program_error(
+        test.process(
+            &[deposit(&capped, 1, STAKE + 10, capped_proof.clone())],
+            &[&capped.owner],
+        )
+        .await,
+        ErrorCode::DepositAllowanceExceeded,
+    );
+
+    // withdrawing does not give allowance back
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &capped, &receipt).await.unwrap();
+    assert_program_error(
+        test.process(
+            &[deposit(&capped, 1, STAKE + 10, capped_proof.clone())],
+            &[&capped.owner],
+        )
+        .await,
+        ErrorCode::DepositAllowanceExceeded,
+    );
+    test.process(
+        &[deposit(&capped, 1, STAKE, capped_proof)],
+        &[&capped.owner],
+    )
+    .await
+    .unwrap();
+    let allowance_address = find_deposit_allowance_address(&capped.owner.pubkey(), &stake_pool).0;
+    let allowance =
+        deserialize_deposit_allowance(&test.account_data(&allowance_address).await.unwrap())
+            .unwrap();
+    assert_eq!(allowance.owner, capped.owner.pubkey());
+    assert_eq!(allowance.deposited_amount, 2 * STAKE);
+
+    test.process(
+        &[deposit(
+            &unlimited,
+            0,
+            3 * STAKE,
+            allowlist.proof(&unlimited.owner.pubkey()),
+        )],
+        &[&unlimited.owner],
+    )
+    .await
+    .unwrap();
+
+    // an all zero root lets anyone deposit again
+    test.process(&[set_root([0; 32])], &[&authority])
+        .await
+        .unwrap();
+    test.deposit(&stake_pool, &outsider, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn escape_hatch_allows_early_withdraw() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MAX_DURATION)
+        .await
+        .unwrap();
+
+    test.set_flags(&stake_pool, ESCAPE_HATCH_ENABLED)
+        .await
+        .unwrap();
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+}
+
+#[tokio::test]
+async fn disable_deposits_flag() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, 2 * STAKE).await;
+
+    test.set_flags(&stake_pool, DISABLE_DEPOSITS).await.unwrap();
+    let result = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .map(|_| ());
+    assert_program_error(result, ErrorCode::DepositsDisabled);
+
+    test.set_flags(&stake_pool, 0).await.unwrap();
+    test.deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn deposit_ignores_lp_flag() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+
+    test.set_flags(&stake_pool, DEPOSIT_IGNORES_LP | WITHDRAW_IGNORES_LP)
+        .await
+        .unwrap();
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.lp).await, 0);
+    assert_eq!(
+        test.stake_pool(&stake_pool)
+            .await
+            .total_weighted_stake_u128(),
+        u128::from(STAKE) * u128::from(SCALE_FACTOR_BASE)
+    );
+
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+}
+
+#[tokio::test]
+async fn withdraw_ignores_lp_flag() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let lp_amount = test.token_balance(&staker.lp).await;
+    assert_eq!(lp_amount, STAKE / 10);
+
+    test.set_flags(&stake_pool, WITHDRAW_IGNORES_LP)
+        .await
+        .unwrap();
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    assert_eq!(test.token_balance(&staker.lp).await, lp_amount);
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+}
+
+#[tokio::test]
+async fn withdraw_burns_lp_moved_to_helper_wallet() {
+    let mut test = TestContext::new().await;
+    let (stake_pool, mint) = test.create_stake_pool().await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    let lp_amount = test.token_balance(&staker.lp).await;
+
+    let helper = Keypair::new();
+    let helper_lp = test
+        .create_token_account(&pool.stake_mint, &helper.pubkey())
+        .await;
+    test.process(
+        &[spl_token::instruction::transfer(
+            &spl_token::ID,
+            &staker.lp,
+            &helper_lp,
+            &staker.owner.pubkey(),
+            &[],
+            lp_amount,
+        )
+        .unwrap()],
+        &[&staker.owner],
+    )
+    .await
+    .unwrap();
+    test.advance_clock(MIN_DURATION as i64);
+    assert!(test.withdraw(&stake_pool, &staker, &receipt).await.is_err());
+
+    let withdraw_from_helper = |lp_authority| {
+        instructions::withdraw(
+            &stake_pool,
+            &pool,
+            &staker.owner.pubkey(),
+            &receipt,
+            &helper_lp,
+            Some(&lp_authority),
+            &staker.tokens,
+            &staker.owner.pubkey(),
+            &spl_token::ID,
+            &[],
+            &[],
+        )
+        .unwrap()
+    };
+    // the signer must hold the LP token account
+    let impostor = Keypair::new();
+    assert_program_error(
+        test.process(
+            &[withdraw_from_helper(impostor.pubkey())],
+            &[&staker.owner, &impostor],
+        )
+        .await,
+        ErrorCode::InvalidLpTokenOwner,
+    );
+
+    test.process(
+        &[withdraw_from_helper(helper.pubkey())],
+        &[&staker.owner, &helper],
+    )
+    .await
+    .unwrap();
+    assert_eq!(test.token_balance(&helper_lp).await, 0);
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+}
@@ -0,0 +1,336 @@
+mod common;
+
+use alris_staking::{errors::ErrorCode, state::SOULBOUND_STAKE_MINT};
+use alris_staking_client::instructions::{self, RewardMint};
+use anchor_spl::{
+    token::spl_token,
+    token_2022::spl_token_2022::{
+        self,
+        error::TokenError,
+        extension::{BaseStateWithExtensions, ExtensionType, StateWithExtensions},
+        state::Mint,
+    },
+};
+use common::*;
+use solana_program_test::BanksClientError;
+use solana_sdk::{instruction::InstructionError, signature::Signer, transaction::TransactionError};
+use spl_token_metadata_interface::state::TokenMetadata;
+
+const STAKE: u64 = 1_000_000_000;
+/// Arrives in the vault as `STAKE` once the transfer fee, rounded up, is withheld
+const STAKE_WITH_FEE: u64 = 1_010_101_011;
+/// 1% of every transfer of the staked mint
+const TRANSFER_FEE: MintExtension = MintExtension::TransferFee {
+    basis_points: 100,
+    maximum_fee: u64::MAX,
+};
+
+#[tokio::test]
+async fn transfer_fee_staked_mint_credits_received_amount() {
+    let mut test = TestContext::new().await;
+    let mint = test.create_mint_2022(9, &[TRANSFER_FEE]).await;
+    let stake_pool = test.create_stake_pool_for(&mint).await.unwrap();
+    test.add_reward_pool(&stake_pool, 0).await;
+    let reward_mint = test
+        .create_mint_2022(6, &[MintExtension::MintCloseAuthority])
+        .await;
+    test.add_reward_pool_for(&stake_pool, 1, &reward_mint)
+        .await
+        .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    for account in [pool.stake_mint, pool.vault] {
+        assert_eq!(test.token_program(&account).await, spl_token_2022::ID);
+    }
+    let reward_vault = pool.reward_pools[1].reward_vault;
+    assert_eq!(test.token_program(&reward_vault).await, spl_token_2022::ID);
+
+    let staker = test.new_staker(&mint, &stake_pool, STAKE_WITH_FEE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE_WITH_FEE, MAX_DURATION)
+        .await
+        .unwrap();
+    assert_eq!(
+        test.stake_deposit_receipt(&receipt).await.deposit_amount,
+        STAKE
+    );
+    assert_eq!(test.token_balance(&pool.vault).await, STAKE);
+    assert_eq!(test.token_balance(&staker.lp).await, 4 * STAKE / 10);
+
+    // rewards from both token programs are paid in one claim
+    test.fund_reward_pool(&stake_pool, 0, 1_000).await;
+    test.fund_reward_pool(&stake_pool, 1, 2_000).await;
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 1_000);
+    assert_eq!(test.token_balance(&staker.reward_accounts[1]).await, 2_000);
+
+    // the owner bears the fee on the way out
+    test.advance_clock(MAX_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    assert_eq!(
+        test.token_balance(&staker.tokens).await,
+        STAKE - STAKE / 100
+    );
+    assert_eq!(test.token_balance(&pool.vault).await, 0);
+    assert_eq!(test.token_balance(&staker.lp).await, 0);
+}
+
+#[tokio::test]
+async fn rejects_unsupported_mint_extensions() {
+    let mut test = TestContext::new().await;
+    for extension in [
+        MintExtension::NonTransferable,
+        MintExtension::PermanentDelegate,
+    ] {
+        let mint = test.create_mint_2022(9, &[extension]).await;
+        assert_program_error(
+            test.create_stake_pool_for(&mint).await.map(|_| ()),
+            ErrorCode::UnsupportedMintExtension,
+        );
+    }
+
+    let mint = test
+        .create_mint_2022(9, &[MintExtension::MintCloseAuthority])
+        .await;
+    let stake_pool = test.create_stake_pool_for(&mint).await.unwrap();
+    for extension in [
+        TRANSFER_FEE,
+        MintExtension::NonTransferable,
+        MintExtension::PermanentDelegate,
+    ] {
+        let reward_mint = test.create_mint_2022(6, &[extension]).await;
+        assert_program_error(
+            test.add_reward_pool_for(&stake_pool, 0, &reward_mint).await,
+            ErrorCode::UnsupportedMintExtension,
+        );
+    }
+}
+
+#[tokio::test]
+async fn rejects_mismatched_token_program() {
+    let mut test = TestContext::new().await;
+    // the token program rejects creating the vault for a mint it does not own
+    let mint = test.create_mint(9).await;
+    assert!(matches!(
+        test.create_stake_pool_with_program(&mint, &spl_token_2022::ID)
+            .await,
+        Err(BanksClientError::TransactionError(
+            TransactionError::InstructionError(0, InstructionError::IncorrectProgramId)
+        ))
+    ));
+
+    let stake_pool = test.create_stake_pool_for(&mint).await.unwrap();
+    test.add_reward_pool(&stake_pool, 0).await;
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    test.fund_reward_pool(&stake_pool, 0, 1_000).await;
+
+    let pool = test.stake_pool(&stake_pool).await;
+    let reward_mint = test.reward_mints(&stake_pool).await[0];
+    let other_mint = test.create_mint(6).await;
+    for (reward_mint, error) in [
+        (
+            RewardMint {
+                token_program: spl_token_2022::ID,
+                ..reward_mint
+            },
+            ErrorCode::InvalidTokenProgram,
+        ),
+        (
+            RewardMint {
+                mint: other_mint,
+                ..reward_mint
+            },
+            ErrorCode::InvalidRewardMint,
+        ),
+    ] {
+        assert_program_error(
+            test.process(
+                &[instructions::claim_all(
+                    &stake_pool,
+                    &pool,
+                    &staker.owner.pubkey(),
+                    &receipt,
+                    &spl_token::ID,
+                    &[reward_mint],
+                    &staker.reward_accounts,
+                )
+                .unwrap()],
+                &[&staker.owner],
+            )
+            .await,
+            error,
+        );
+    }
+
+    test.claim_all(&stake_pool, &staker, &receipt)
+        .await
+        .unwrap();
+    assert_eq!(test.token_balance(&staker.reward_accounts[0]).await, 1_000);
+}
+
+#[tokio::test]
+async fn soulbound_stake_mint_cannot_be_transferred() {
+    let mut test = TestContext::new().await;
+    let mint = test.create_mint(9).await;
+    let stake_pool = test
+        .create_stake_pool_with_options(&mint, &spl_token::ID, SOULBOUND_STAKE_MINT)
+        .await
+        .unwrap();
+    test.add_reward_pool(&stake_pool, 0).await;
+    let pool = test.stake_pool(&stake_pool).await;
+    assert!(pool.stake_mint_is_soulbound());
+    assert_eq!(
+        test.token_program(&pool.stake_mint).await,
+        spl_token_2022::ID
+    );
+    assert_eq!(test.token_program(&pool.vault).await, spl_token::ID);
+    let stake_mint_data = test.account_data(&pool.stake_mint).await.unwrap();
+    assert_eq!(
+        StateWithExtensions::<Mint>::unpack(&stake_mint_data)
+            .unwrap()
+            .get_extension_types()
+            .unwrap(),
+        [
+            ExtensionType::NonTransferable,
+            ExtensionType::MetadataPointer
+        ]
+    );
+
+    let staker = test.new_staker(&mint, &stake_pool, STAKE).await;
+    let receipt = test
+        .deposit(&stake_pool, &staker, 0, STAKE, MIN_DURATION)
+        .await
+        .unwrap();
+    let lp_amount = test.token_balance(&staker.lp).await;
+    assert!(lp_amount > 0);
+
+    let other = test
+        .create_token_account(&pool.stake_mint, &test.payer())
+        .await;
+    let decimals = test.mint_decimals(&pool.stake_mint).await;
+    assert_custom_error(
+        test.process(
+            &[spl_token_2022::instruction::transfer_checked(
+                &spl_token_2022::ID,
+                &staker.lp,
+                &pool.stake_mint,
+                &other,
+                &staker.owner.pubkey(),
+                &[],
+                lp_amount,
+                decimals,
+            )
+            .unwrap()],
+            &[&staker.owner],
+        )
+        .await,
+        TokenError::NonTransferable as u32,
+    );
+
+    // the LP tokens are still there to be burned
+    test.advance_clock(MIN_DURATION as i64);
+    test.withdraw(&stake_pool, &staker, &receipt).await.unwrap();
+    assert_eq!(test.token_balance(&staker.lp).await, 0);
+    assert_eq!(test.token_balance(&staker.tokens).await, STAKE);
+}
+
+#[tokio::test]
+async fn soulbound_stake_mint_holds_its_metadata() {
+    let mut test = TestContext::new().await;
+    let mint = test.create_mint(9).await;
+    let stake_pool = test
+        .create_stake_pool_with_options(&mint, &spl_token::ID, SOULBOUND_STAKE_MINT)
+        .await
+        .unwrap();
+    let pool = test.stake_pool(&stake_pool).await;
+    let authority = test.authority.insecure_clone();
+
+    for (name, symbol, uri) in [
+        ("Staked Alris", "sALR", "https://alris.io/sALR.json"),
+        (
+            "Staked Alris Governance",
+            "sALRG",
+            "https://alris.io/metadata/sALRG.json",
+        ),
+        ("sALR", "s", "https://alris.io/s.json"),
+    ] {
+        test.process(
+            &[instructions::update_token_meta(
+                &stake_pool,
+                &pool,
+                name.to_string(),
+                symbol.to_string(),
+                uri.to_string(),
+            )],
+            &[&authority],
+        )
+        .await
+        .unwrap();
+
+        let data = test.account_data(&pool.stake_mint).await.unwrap();
+        let stake_mint = StateWithExtensions::<Mint>::unpack(&data).unwrap();
+        let metadata = stake_mint
+            .get_variable_len_extension::<TokenMetadata>()
+            .unwrap();
+        assert_eq!(metadata.name, name);
+        assert_eq!(metadata.symbol, symbol);
+        assert_eq!(metadata.uri, uri);
+        assert_eq!(
+            Option::<anchor_lang::prelude::Pubkey>::from(metadata.update_authority),
+            Some(stake_pool)
+        );
+    }
+
+    // the Metaplex path is closed to soulbound pools
+    let mut ix = instructions::update_token_meta(
+        &stake_pool,
+        &pool,
+        "Staked Alris".to_string(),
+        "sALR".to_string(),
+        "https://alris.io/sALR.json".to_string(),
+    );
+    ix.accounts[1].pubkey =
+        alris_staking_client::pda::find_stake_mint_metadata_address(&pool.stake_mint).0;
+    assert_program_error(
+        test.process(&[ix], &[&authority]).await,
+        ErrorCode::InvalidMetadataAccount,
+    );
+}
+
+#[tokio::test]
+async fn rejects_invalid_stake_pool_options() {
+    let mut test = TestContext::new().await;
+    let mint = test.create_mint(9).await;
+    assert_program_error(
+        test.create_stake_pool_with_options(&mint, &spl_token::ID, 2)
+            .await
+            .map(|_| ()),
+        ErrorCode::InvalidStakePoolOptions,
+    );
+
+    // a soulbound LP mint is always created under Token-2022
+    let authority = test.authority.insecure_clone();
+    let mut ix = instructions::initialize_stake_pool(
+        &test.payer(),
+        &authority.pubkey(),
+        &mint,
+        &spl_token::ID,
+        instructions::InitializeStakePoolArgs {
+            nonce: 0,
+            max_weight: MAX_WEIGHT,
+            min_duration: MIN_DURATION,
+            max_duration: MAX_DURATION,
+            options: SOULBOUND_STAKE_MINT,
+        },
+    );
+    ix.accounts[7].pubkey = spl_token::ID;
+    assert_program_error(
+        test.process(&[ix], &[&authority]).await,
+        ErrorCode::InvalidTokenProgram,
+    );
+}
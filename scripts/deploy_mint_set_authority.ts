@@ -2,7 +2,7 @@
 import { Connection, Keypair, PublicKey } from "@solana/web3.js";
 import { createMint } from "@solana/spl-token";
 import fs from "fs";
-import { ADMIN_PUBLIC_KEY } from "@alris-labs/token-staking";
+import { ADMIN_PUBLIC_KEY } from "@meimfhd/alris-token-staking";
 
 // Load keypair from JSON file
 function loadKeypairFromFile(filePath: string) {
@@ -1,4 +1,7 @@
-import { AlrisStakingProgram, StakePoolFlags } from "@alris-labs/token-staking";
+import {
+  AlrisStakingProgram,
+  StakePoolFlags,
+} from "@meimfhd/alris-token-staking";
 import { AnchorProvider, Wallet } from "@coral-xyz/anchor";
 import { Connection, Keypair } from "@solana/web3.js";
 import fs from "fs";
@@ -3,7 +3,7 @@ import {
   MAX_DURATION,
   MIN_DURATION,
   MAX_WEIGHT,
-} from "@alris-labs/token-staking";
+} from "@meimfhd/alris-token-staking";
 import { AnchorProvider, BN, Wallet } from "@coral-xyz/anchor";
 import { Connection, Keypair } from "@solana/web3.js";
 import fs from "fs";
@@ -1,4 +1,4 @@
-import { AlrisStakingProgram } from "@alris-labs/token-staking";
+import { AlrisStakingProgram } from "@meimfhd/alris-token-staking";
 import { AnchorProvider, Wallet } from "@coral-xyz/anchor";
 import { Connection, Keypair } from "@solana/web3.js";
 import fs from "fs";
@@ -128,7 +128,7 @@ export class AlrisStakingProgram {
     maxDuration: BN
   ): Promise<IdlAccounts<AlrisStaking>["stakePool"]> {
     let intialize_stake_pool_ix = await this.program.methods
-      .initializeStakePool(1, maxWeight, minDuration, maxDuration)
+      .initializeStakePool(1, maxWeight, minDuration, maxDuration, 0)
       .accounts({
         authority: this.admin.publicKey,
         mint: this.alrMint,
@@ -136,6 +136,7 @@ export class AlrisStakingProgram {
         stakeMint: this.stakeMint,
         vault: this.vaultPda,
         tokenProgram: TOKEN_PROGRAM_ID,
+        stakeTokenProgram: TOKEN_PROGRAM_ID,
         rent: web3.SYSVAR_RENT_PUBKEY,
         payer: this.wallet.publicKey,
         systemProgram: web3.SystemProgram.programId,
@@ -194,6 +195,19 @@ export class AlrisStakingProgram {
       this.programId
     )[0];
   }
+  getUserStakeIndexPda(
+    owner: web3.PublicKey,
+    stakePool: web3.PublicKey
+  ): web3.PublicKey {
+    return web3.PublicKey.findProgramAddressSync(
+      [
+        owner.toBuffer(),
+        stakePool.toBuffer(),
+        Buffer.from("userStakeIndex", "utf-8"),
+      ],
+      this.programId
+    )[0];
+  }
   async getOrCreateAta(mint: web3.PublicKey, owner: web3.PublicKey) {
     const userAta = await getOrCreateAssociatedTokenAccount(
       this.program.provider.connection,
@@ -240,24 +254,32 @@ export class AlrisStakingProgram {
     );
 
     let tx = await this.program.methods
-      .deposit(nonce, amount, lockupDuration)
+      .deposit(nonce, amount, lockupDuration, null)
       .accounts({
-        stakePool: this.stakePoolPda,
-        destination: userStakeMintTokenAccount,
-        from: userALRMintTokenAccount,
-        owner: userKeypair.publicKey,
         payer: userKeypair.publicKey,
+        owner: userKeypair.publicKey,
+        from: userALRMintTokenAccount,
+        vault: this.vaultPda,
+        mint: this.alrMint,
         stakeMint: this.stakeMint,
-        rent: web3.SYSVAR_RENT_PUBKEY,
+        destination: userStakeMintTokenAccount,
+        stakePool: this.stakePoolPda,
         stakeDepositReceipt: stakeDepositReceiptPda,
-        systemProgram: web3.SystemProgram.programId,
+        userStakeIndex: this.getUserStakeIndexPda(
+          userKeypair.publicKey,
+          this.stakePoolPda
+        ),
         tokenProgram: TOKEN_PROGRAM_ID,
-        vault: this.vaultPda,
+        stakeTokenProgram: TOKEN_PROGRAM_ID,
+        rent: web3.SYSVAR_RENT_PUBKEY,
+        systemProgram: web3.SystemProgram.programId,
+        tokenAuthority: null,
+        depositAllowance: null,
       })
       .remainingAccounts([
         {
           isSigner: false,
-          isWritable: true,
+          isWritable: false,
           pubkey: this.rewardVaultPda,
         },
       ])